@@ -0,0 +1,30 @@
+-- Situação do leito passa a ser um tipo enumerado
+CREATE TYPE situacao_leito AS ENUM (
+		'livre',
+		'ocupado',
+		'reservado',
+		'em_higienizacao',
+		'bloqueado',
+		'em_manutencao'
+);
+
+-- Valores livres desconhecidos ficam bloqueados até revisão manual,
+-- em vez de serem contados como leitos disponíveis
+ALTER TABLE leito
+		ALTER COLUMN situacao TYPE situacao_leito
+		USING (
+			CASE lower(trim(situacao))
+				WHEN 'livre' THEN 'livre'
+				WHEN 'vazio' THEN 'livre'
+				WHEN 'desocupado' THEN 'livre'
+				WHEN 'disponível' THEN 'livre'
+				WHEN 'disponivel' THEN 'livre'
+				WHEN 'ocupado' THEN 'ocupado'
+				WHEN 'reservado' THEN 'reservado'
+				WHEN 'em higienização' THEN 'em_higienizacao'
+				WHEN 'em higienizacao' THEN 'em_higienizacao'
+				WHEN 'em manutenção' THEN 'em_manutencao'
+				WHEN 'em manutencao' THEN 'em_manutencao'
+				ELSE 'bloqueado'
+			END
+		)::situacao_leito;
@@ -0,0 +1,38 @@
+-- Classificação do leito passa a ser um tipo enumerado
+CREATE TYPE tipo_leito AS ENUM (
+		'uti_adulto',
+		'uti_pediatrica',
+		'uti_neonatal',
+		'uci',
+		'enfermaria_clinica',
+		'enfermaria_cirurgica',
+		'isolamento_respiratorio',
+		'semi_intensiva'
+);
+
+-- Tipos livres desconhecidos são tratados como enfermaria clínica,
+-- a categoria com menos recursos
+ALTER TABLE leito
+		ALTER COLUMN tipo TYPE tipo_leito
+		USING (
+			CASE lower(trim(tipo))
+				WHEN 'uti' THEN 'uti_adulto'
+				WHEN 'uti adulto' THEN 'uti_adulto'
+				WHEN 'uti pediátrica' THEN 'uti_pediatrica'
+				WHEN 'uti pediatrica' THEN 'uti_pediatrica'
+				WHEN 'uti neonatal' THEN 'uti_neonatal'
+				WHEN 'uci' THEN 'uci'
+				WHEN 'enfermaria cirúrgica' THEN 'enfermaria_cirurgica'
+				WHEN 'enfermaria cirurgica' THEN 'enfermaria_cirurgica'
+				WHEN 'isolamento' THEN 'isolamento_respiratorio'
+				WHEN 'semi-intensiva' THEN 'semi_intensiva'
+				WHEN 'semi intensiva' THEN 'semi_intensiva'
+				ELSE 'enfermaria_clinica'
+			END
+		)::tipo_leito;
+
+-- Recursos disponíveis em cada leito
+ALTER TABLE leito
+		ADD COLUMN ventilador_mecanico BOOLEAN NOT NULL DEFAULT false,
+		ADD COLUMN isolamento_pressao_negativa BOOLEAN NOT NULL DEFAULT false,
+		ADD COLUMN hemodialise BOOLEAN NOT NULL DEFAULT false;
@@ -0,0 +1,39 @@
+-- Histórico de internações: o leito atual do paciente passa a ser
+-- a internação sem alta, e não mais uma coluna de paciente
+ALTER TABLE paciente ADD PRIMARY KEY (id);
+
+CREATE TYPE motivo_alta AS ENUM (
+		'alta_medica',
+		'transferencia',
+		'obito',
+		'evasao'
+);
+
+CREATE TABLE internacao(
+		id uuid NOT NULL,
+		PRIMARY KEY (id),
+		paciente_id uuid NOT NULL,
+		leito_id uuid NOT NULL,
+		admitido_em timestamptz NOT NULL DEFAULT now(),
+		alta_em timestamptz,
+		motivo_alta motivo_alta,
+		FOREIGN KEY (paciente_id) REFERENCES paciente (id) ON DELETE CASCADE,
+		FOREIGN KEY (leito_id) REFERENCES leito (id),
+		CHECK ((alta_em IS NULL) = (motivo_alta IS NULL))
+);
+
+CREATE INDEX internacao_paciente_id_idx ON internacao (paciente_id);
+CREATE INDEX internacao_leito_id_idx ON internacao (leito_id);
+
+-- gen_random_uuid só é nativa a partir do PostgreSQL 13; antes, vem do pgcrypto
+CREATE EXTENSION IF NOT EXISTS pgcrypto;
+
+-- Os vínculos existentes viram internações em aberto
+INSERT INTO internacao (id, paciente_id, leito_id)
+SELECT gen_random_uuid(), id, leito_id FROM paciente;
+
+-- e os leitos desses vínculos passam a constar como ocupados
+UPDATE leito SET situacao = 'ocupado'
+WHERE id IN (SELECT leito_id FROM internacao WHERE alta_em IS NULL);
+
+ALTER TABLE paciente DROP COLUMN leito_id;
@@ -0,0 +1,23 @@
+-- O cadastro antigo permitia dois pacientes no mesmo leito, e cada vínculo virou uma
+-- internação em aberto. Mantém só a mais antiga de cada leito e de cada paciente; as
+-- demais são encerradas para que o paciente seja internado de novo pela regulação.
+UPDATE internacao SET alta_em = now(), motivo_alta = 'transferencia'
+WHERE alta_em IS NULL
+  AND id NOT IN (
+		SELECT DISTINCT ON (leito_id) id FROM internacao
+		WHERE alta_em IS NULL
+		ORDER BY leito_id, admitido_em, id
+  );
+
+UPDATE internacao SET alta_em = now(), motivo_alta = 'transferencia'
+WHERE alta_em IS NULL
+  AND id NOT IN (
+		SELECT DISTINCT ON (paciente_id) id FROM internacao
+		WHERE alta_em IS NULL
+		ORDER BY paciente_id, admitido_em, id
+  );
+
+-- No máximo uma internação em aberto por leito e por paciente, mesmo que
+-- duas transações concorrentes passem pelas validações da aplicação
+CREATE UNIQUE INDEX internacao_leito_em_aberto_idx ON internacao (leito_id) WHERE alta_em IS NULL;
+CREATE UNIQUE INDEX internacao_paciente_em_aberto_idx ON internacao (paciente_id) WHERE alta_em IS NULL;
@@ -0,0 +1,37 @@
+-- Transferências de pacientes entre leitos, na mesma unidade ou entre unidades
+CREATE TYPE situacao_transferencia AS ENUM (
+		'solicitada',
+		'aceita',
+		'em_transito',
+		'concluida',
+		'recusada'
+);
+
+CREATE TABLE transferencia(
+		id uuid NOT NULL,
+		PRIMARY KEY (id),
+		paciente_id uuid NOT NULL,
+		internacao_origem_id uuid NOT NULL,
+		leito_origem_id uuid NOT NULL,
+		unidade_destino_id uuid NOT NULL,
+		leito_destino_id uuid,
+		internacao_destino_id uuid,
+		situacao situacao_transferencia NOT NULL DEFAULT 'solicitada',
+		solicitado_por TEXT NOT NULL,
+		aceito_por TEXT,
+		motivo_recusa TEXT,
+		solicitada_em timestamptz NOT NULL DEFAULT now(),
+		aceita_em timestamptz,
+		iniciada_em timestamptz,
+		finalizada_em timestamptz,
+		FOREIGN KEY (paciente_id) REFERENCES paciente (id) ON DELETE CASCADE,
+		FOREIGN KEY (internacao_origem_id) REFERENCES internacao (id) ON DELETE CASCADE,
+		FOREIGN KEY (leito_origem_id) REFERENCES leito (id),
+		FOREIGN KEY (unidade_destino_id) REFERENCES unidadeSaude (id),
+		FOREIGN KEY (leito_destino_id) REFERENCES leito (id),
+		FOREIGN KEY (internacao_destino_id) REFERENCES internacao (id) ON DELETE SET NULL
+);
+
+-- Uma internação só pode ter uma transferência em andamento
+CREATE UNIQUE INDEX transferencia_em_andamento_idx ON transferencia (internacao_origem_id)
+		WHERE situacao IN ('solicitada', 'aceita', 'em_transito');
@@ -0,0 +1,19 @@
+-- Fila da central de regulação: pacientes aguardando um leito
+CREATE TABLE fila_regulacao(
+		id uuid NOT NULL,
+		PRIMARY KEY (id),
+		paciente_id uuid NOT NULL,
+		FOREIGN KEY (paciente_id) REFERENCES paciente (id) ON DELETE CASCADE,
+		-- escore de gravidade: 1 (menos grave) a 5 (mais grave)
+		prioridade SMALLINT NOT NULL CHECK (prioridade BETWEEN 1 AND 5),
+		tipo_leito tipo_leito NOT NULL,
+		municipio_origem TEXT NOT NULL,
+		entrada_em TIMESTAMPTZ NOT NULL DEFAULT now(),
+		atendida_em TIMESTAMPTZ,
+		internacao_id uuid,
+		FOREIGN KEY (internacao_id) REFERENCES internacao (id) ON DELETE SET NULL
+);
+
+-- Um paciente aguarda no máximo uma vez na fila
+CREATE UNIQUE INDEX fila_regulacao_aguardando_idx ON fila_regulacao (paciente_id) WHERE atendida_em IS NULL;
+CREATE INDEX fila_regulacao_ordem_idx ON fila_regulacao (prioridade DESC, entrada_em) WHERE atendida_em IS NULL;
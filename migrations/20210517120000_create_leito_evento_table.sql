@@ -0,0 +1,52 @@
+-- Histórico append-only das mudanças de situação dos leitos
+CREATE TABLE leito_evento(
+		id BIGSERIAL NOT NULL,
+		PRIMARY KEY (id),
+		-- sem chave estrangeira: o histórico sobrevive à remoção do leito
+		leito_id uuid NOT NULL,
+		unidade_id uuid NOT NULL,
+		situacao_anterior situacao_leito,
+		-- NULL quando o leito foi removido
+		situacao situacao_leito,
+		ocorrido_em TIMESTAMPTZ NOT NULL DEFAULT now()
+);
+
+CREATE INDEX leito_evento_ocorrido_em_idx ON leito_evento (ocorrido_em);
+CREATE INDEX leito_evento_leito_idx ON leito_evento (leito_id, ocorrido_em);
+
+-- Todo caminho que altera a situação passa pelo banco, então o registro é feito por trigger
+CREATE FUNCTION registrar_leito_evento() RETURNS trigger AS $$
+BEGIN
+	IF TG_OP = 'INSERT' THEN
+		INSERT INTO leito_evento (leito_id, unidade_id, situacao_anterior, situacao)
+		VALUES (NEW.id, NEW.unidade_id, NULL, NEW.situacao);
+	ELSIF TG_OP = 'UPDATE' THEN
+		IF NEW.situacao IS DISTINCT FROM OLD.situacao OR NEW.unidade_id IS DISTINCT FROM OLD.unidade_id THEN
+			INSERT INTO leito_evento (leito_id, unidade_id, situacao_anterior, situacao)
+			VALUES (NEW.id, NEW.unidade_id, OLD.situacao, NEW.situacao);
+		END IF;
+	ELSE
+		INSERT INTO leito_evento (leito_id, unidade_id, situacao_anterior, situacao)
+		VALUES (OLD.id, OLD.unidade_id, OLD.situacao, NULL);
+	END IF;
+	RETURN NULL;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER leito_evento_trigger
+	AFTER INSERT OR UPDATE OR DELETE ON leito
+	FOR EACH ROW EXECUTE FUNCTION registrar_leito_evento();
+
+CREATE FUNCTION impedir_alteracao_leito_evento() RETURNS trigger AS $$
+BEGIN
+	RAISE EXCEPTION 'leito_evento é append-only';
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER leito_evento_append_only
+	BEFORE UPDATE OR DELETE ON leito_evento
+	FOR EACH ROW EXECUTE FUNCTION impedir_alteracao_leito_evento();
+
+-- Situação atual dos leitos existentes como ponto de partida do histórico
+INSERT INTO leito_evento (leito_id, unidade_id, situacao_anterior, situacao)
+SELECT id, unidade_id, NULL, situacao FROM leito;
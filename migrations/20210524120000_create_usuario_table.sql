@@ -0,0 +1,24 @@
+-- Usuários do sistema e seus tokens de acesso
+CREATE TABLE usuario(
+		id uuid NOT NULL,
+		PRIMARY KEY (id),
+		nome TEXT NOT NULL,
+		email TEXT NOT NULL UNIQUE,
+		-- hash argon2 no formato PHC, com parâmetros e salt
+		senha_hash TEXT NOT NULL,
+		criado_em TIMESTAMPTZ NOT NULL DEFAULT now()
+);
+
+-- Tokens opacos: só o SHA-256 do token é guardado
+CREATE TABLE token_acesso(
+		id uuid NOT NULL,
+		PRIMARY KEY (id),
+		usuario_id uuid NOT NULL,
+		FOREIGN KEY (usuario_id) REFERENCES usuario (id) ON DELETE CASCADE,
+		token_hash TEXT NOT NULL UNIQUE,
+		criado_em TIMESTAMPTZ NOT NULL DEFAULT now(),
+		expira_em TIMESTAMPTZ NOT NULL,
+		revogado_em TIMESTAMPTZ
+);
+
+CREATE INDEX token_acesso_usuario_idx ON token_acesso (usuario_id);
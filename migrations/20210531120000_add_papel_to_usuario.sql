@@ -0,0 +1,13 @@
+-- Papéis de acesso; usuários já cadastrados mantêm acesso total como administradores
+CREATE TYPE papel_usuario AS ENUM ('admin', 'regulador_estadual', 'gestor_municipal', 'funcionario_unidade');
+
+ALTER TABLE usuario
+	ADD COLUMN papel papel_usuario NOT NULL DEFAULT 'admin',
+	ADD COLUMN unidade_id uuid,
+	ADD COLUMN municipio TEXT,
+	ADD FOREIGN KEY (unidade_id) REFERENCES unidadeSaude (id),
+	-- funcionário pertence a uma unidade e gestor a um município
+	ADD CONSTRAINT usuario_unidade_check CHECK ((papel = 'funcionario_unidade') = (unidade_id IS NOT NULL)),
+	ADD CONSTRAINT usuario_municipio_check CHECK ((papel = 'gestor_municipal') = (municipio IS NOT NULL));
+
+ALTER TABLE usuario ALTER COLUMN papel DROP DEFAULT;
@@ -0,0 +1,83 @@
+-- Registro imutável de toda escrita em unidades, leitos e pacientes
+CREATE TYPE entidade_auditada AS ENUM ('unidade', 'leito', 'paciente');
+CREATE TYPE operacao_auditada AS ENUM ('insercao', 'atualizacao', 'remocao');
+
+CREATE TABLE auditoria(
+		id BIGSERIAL NOT NULL,
+		PRIMARY KEY (id),
+		-- sem chave estrangeira: o registro sobrevive ao usuário e à entidade.
+		-- NULL quando a escrita não veio da API (migrações, manutenção manual)
+		usuario_id uuid,
+		rota TEXT,
+		entidade entidade_auditada NOT NULL,
+		entidade_id uuid NOT NULL,
+		operacao operacao_auditada NOT NULL,
+		-- linha completa na inserção e na remoção; só as colunas alteradas na atualização
+		antes JSONB,
+		depois JSONB,
+		ocorrido_em TIMESTAMPTZ NOT NULL DEFAULT now()
+);
+
+CREATE INDEX auditoria_entidade_idx ON auditoria (entidade, entidade_id, id);
+
+-- Autor e rota chegam pelas configurações locais da transação, definidas pela API
+-- antes de escrever; a trigger grava na mesma transação da mudança
+CREATE FUNCTION registrar_auditoria() RETURNS trigger AS $$
+DECLARE
+	v_antes JSONB;
+	v_depois JSONB;
+BEGIN
+	IF TG_OP = 'INSERT' THEN
+		v_depois := to_jsonb(NEW);
+	ELSIF TG_OP = 'DELETE' THEN
+		v_antes := to_jsonb(OLD);
+	ELSE
+		SELECT jsonb_object_agg(A.key, A.value), jsonb_object_agg(D.key, D.value)
+		INTO v_antes, v_depois
+		FROM jsonb_each(to_jsonb(OLD)) as A JOIN jsonb_each(to_jsonb(NEW)) as D ON D.key = A.key
+		WHERE A.value IS DISTINCT FROM D.value;
+
+		IF v_antes IS NULL THEN
+			RETURN NULL;
+		END IF;
+	END IF;
+
+	INSERT INTO auditoria (usuario_id, rota, entidade, entidade_id, operacao, antes, depois)
+	VALUES (
+		NULLIF(current_setting('auditoria.usuario_id', true), '')::uuid,
+		NULLIF(current_setting('auditoria.rota', true), ''),
+		TG_ARGV[0]::entidade_auditada,
+		(COALESCE(to_jsonb(NEW), to_jsonb(OLD)) ->> 'id')::uuid,
+		CASE TG_OP WHEN 'INSERT' THEN 'insercao' WHEN 'UPDATE' THEN 'atualizacao' ELSE 'remocao' END::operacao_auditada,
+		v_antes,
+		v_depois
+	);
+	RETURN NULL;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER auditoria_unidade_trigger
+	AFTER INSERT OR UPDATE OR DELETE ON unidadeSaude
+	FOR EACH ROW EXECUTE FUNCTION registrar_auditoria('unidade');
+
+CREATE TRIGGER auditoria_leito_trigger
+	AFTER INSERT OR UPDATE OR DELETE ON leito
+	FOR EACH ROW EXECUTE FUNCTION registrar_auditoria('leito');
+
+CREATE TRIGGER auditoria_paciente_trigger
+	AFTER INSERT OR UPDATE OR DELETE ON paciente
+	FOR EACH ROW EXECUTE FUNCTION registrar_auditoria('paciente');
+
+CREATE FUNCTION impedir_alteracao_auditoria() RETURNS trigger AS $$
+BEGIN
+	RAISE EXCEPTION 'auditoria é append-only';
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER auditoria_append_only
+	BEFORE UPDATE OR DELETE ON auditoria
+	FOR EACH ROW EXECUTE FUNCTION impedir_alteracao_auditoria();
+
+CREATE TRIGGER auditoria_sem_truncate
+	BEFORE TRUNCATE ON auditoria
+	FOR EACH STATEMENT EXECUTE FUNCTION impedir_alteracao_auditoria();
@@ -0,0 +1,31 @@
+-- Anonimização LGPD: nome, email e telefone são apagados e os dados clínicos preservados
+ALTER TABLE paciente ADD COLUMN anonimizado_em TIMESTAMPTZ;
+
+-- Substitui os dados pessoais de um registro de paciente em JSON, mantendo as demais chaves
+CREATE FUNCTION anonimizar_dados_pessoais(dados JSONB) RETURNS JSONB AS $$
+	SELECT dados || COALESCE(
+		(SELECT jsonb_object_agg(chave, to_jsonb('anonimizado'::text))
+		 FROM jsonb_object_keys(dados) as chave
+		 WHERE chave IN ('nome', 'email', 'telefone')),
+		'{}'::jsonb
+	)
+$$ LANGUAGE sql IMMUTABLE;
+
+-- A auditoria continua imutável, exceto pela anonimização dos registros do paciente
+-- que a própria transação está anonimizando
+CREATE OR REPLACE FUNCTION impedir_alteracao_auditoria() RETURNS trigger AS $$
+BEGIN
+	IF TG_OP = 'UPDATE' THEN
+		IF OLD.entidade = 'paciente'
+		   AND OLD.entidade_id::text = current_setting('auditoria.anonimizar_paciente', true)
+		   AND NEW.antes IS NOT DISTINCT FROM anonimizar_dados_pessoais(OLD.antes)
+		   AND NEW.depois IS NOT DISTINCT FROM anonimizar_dados_pessoais(OLD.depois)
+		   AND (NEW.id, NEW.usuario_id, NEW.rota, NEW.entidade, NEW.entidade_id, NEW.operacao, NEW.ocorrido_em)
+		       IS NOT DISTINCT FROM
+		       (OLD.id, OLD.usuario_id, OLD.rota, OLD.entidade, OLD.entidade_id, OLD.operacao, OLD.ocorrido_em) THEN
+			RETURN NEW;
+		END IF;
+	END IF;
+	RAISE EXCEPTION 'auditoria é append-only';
+END;
+$$ LANGUAGE plpgsql;
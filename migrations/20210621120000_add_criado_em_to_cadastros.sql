@@ -0,0 +1,9 @@
+-- Ordem de cadastro: ordenação padrão e chave da paginação das listagens.
+-- Os registros existentes ficam com o instante da migração e desempatam pelo id
+ALTER TABLE unidadeSaude ADD COLUMN criado_em TIMESTAMPTZ NOT NULL DEFAULT now();
+ALTER TABLE leito ADD COLUMN criado_em TIMESTAMPTZ NOT NULL DEFAULT now();
+ALTER TABLE paciente ADD COLUMN criado_em TIMESTAMPTZ NOT NULL DEFAULT now();
+
+CREATE INDEX unidadeSaude_criado_em_idx ON unidadeSaude (criado_em, id);
+CREATE INDEX leito_criado_em_idx ON leito (criado_em, id);
+CREATE INDEX paciente_criado_em_idx ON paciente (criado_em, id);
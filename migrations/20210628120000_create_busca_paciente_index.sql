@@ -0,0 +1,12 @@
+-- Busca de pacientes por nome ignorando acentos e tolerando erros de digitação
+CREATE EXTENSION IF NOT EXISTS unaccent;
+CREATE EXTENSION IF NOT EXISTS pg_trgm;
+
+-- `unaccent` é só STABLE (depende do dicionário configurado); fixar o dicionário
+-- permite usá-la em índices
+CREATE FUNCTION sem_acento(texto TEXT) RETURNS TEXT AS $$
+	SELECT public.unaccent('public.unaccent'::regdictionary, lower(texto))
+$$ LANGUAGE sql IMMUTABLE PARALLEL SAFE STRICT;
+
+CREATE INDEX paciente_nome_fts_idx ON paciente USING GIN (to_tsvector('simple', sem_acento(nome)));
+CREATE INDEX paciente_nome_trgm_idx ON paciente USING GIN (sem_acento(nome) gin_trgm_ops);
@@ -0,0 +1,6 @@
+-- Idade passa a ser numérica; valores antigos que não são um número inteiro ficam nulos
+-- e precisam ser informados na próxima atualização do cadastro
+ALTER TABLE paciente ALTER COLUMN idade DROP NOT NULL;
+ALTER TABLE paciente ALTER COLUMN idade TYPE INTEGER
+	USING CASE WHEN trim(idade) ~ '^\d{1,3}$' THEN trim(idade)::integer END;
+ALTER TABLE paciente ADD CONSTRAINT paciente_idade_valida CHECK (idade BETWEEN 0 AND 130);
@@ -0,0 +1,34 @@
+-- Situação clínica de COVID-19 em registros próprios, com o histórico de testes do paciente
+CREATE TYPE situacao_covid AS ENUM ('suspeito', 'confirmado', 'descartado', 'recuperado');
+CREATE TYPE tipo_teste_covid AS ENUM ('rt_pcr', 'antigeno', 'sorologia');
+
+CREATE TABLE registro_covid(
+		id uuid NOT NULL,
+		PRIMARY KEY (id),
+		paciente_id uuid NOT NULL REFERENCES paciente (id) ON DELETE CASCADE,
+		situacao situacao_covid NOT NULL,
+		tipo_teste tipo_teste_covid,
+		coletado_em DATE,
+		resultado_em DATE,
+		inicio_sintomas_em DATE,
+		registrado_em TIMESTAMPTZ NOT NULL DEFAULT now(),
+		CHECK (resultado_em IS NULL OR coletado_em IS NULL OR resultado_em >= coletado_em)
+);
+
+CREATE INDEX registro_covid_paciente_idx ON registro_covid (paciente_id, registrado_em DESC, id DESC);
+
+-- A situação atual é a do registro mais recente de cada paciente
+CREATE VIEW situacao_covid_atual AS
+	SELECT DISTINCT ON (paciente_id) paciente_id, situacao, registrado_em
+	FROM registro_covid
+	ORDER BY paciente_id, registrado_em DESC, id DESC;
+
+-- O antigo 'Sim' vira um caso confirmado sem dados de teste; 'Não' não dizia se o
+-- paciente foi testado, então não gera registro. No PostgreSQL 12, gen_random_uuid
+-- vem do pgcrypto
+CREATE EXTENSION IF NOT EXISTS pgcrypto;
+
+INSERT INTO registro_covid (id, paciente_id, situacao)
+	SELECT gen_random_uuid(), id, 'confirmado' FROM paciente WHERE covid_19 = 'Sim';
+
+ALTER TABLE paciente DROP COLUMN covid_19;
@@ -0,0 +1,34 @@
+-- Catálogo de agravos de notificação compulsória e as condições de cada paciente
+CREATE TABLE condicao_notificavel(
+		id uuid NOT NULL,
+		PRIMARY KEY (id),
+		nome TEXT NOT NULL UNIQUE,
+		exige_isolamento BOOLEAN NOT NULL DEFAULT false,
+		criado_em TIMESTAMPTZ NOT NULL DEFAULT now()
+);
+
+CREATE TABLE paciente_condicao(
+		paciente_id uuid NOT NULL REFERENCES paciente (id) ON DELETE CASCADE,
+		condicao_id uuid NOT NULL REFERENCES condicao_notificavel (id),
+		PRIMARY KEY (paciente_id, condicao_id),
+		registrado_em TIMESTAMPTZ NOT NULL DEFAULT now()
+);
+
+CREATE INDEX paciente_condicao_condicao_idx ON paciente_condicao (condicao_id);
+
+-- Leitos que podem receber pacientes em isolamento
+CREATE FUNCTION permite_isolamento(tipo tipo_leito, isolamento_pressao_negativa BOOLEAN) RETURNS BOOLEAN AS $$
+	SELECT isolamento_pressao_negativa OR tipo = 'isolamento_respiratorio'
+$$ LANGUAGE sql IMMUTABLE;
+
+-- ids fixos, iguais em todos os ambientes
+INSERT INTO condicao_notificavel (id, nome, exige_isolamento) VALUES
+	('6c0d1e2a-0000-4000-8000-000000000001', 'COVID-19', true),
+	('6c0d1e2a-0000-4000-8000-000000000002', 'Influenza', true),
+	('6c0d1e2a-0000-4000-8000-000000000003', 'Tuberculose', true),
+	('6c0d1e2a-0000-4000-8000-000000000004', 'Sarampo', true),
+	('6c0d1e2a-0000-4000-8000-000000000005', 'Varicela', true),
+	('6c0d1e2a-0000-4000-8000-000000000006', 'Doença meningocócica', true),
+	('6c0d1e2a-0000-4000-8000-000000000007', 'Dengue', false),
+	('6c0d1e2a-0000-4000-8000-000000000008', 'Chikungunya', false),
+	('6c0d1e2a-0000-4000-8000-000000000009', 'Leptospirose', false);
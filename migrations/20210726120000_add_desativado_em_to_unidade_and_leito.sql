@@ -0,0 +1,26 @@
+-- Remoção lógica: unidades e leitos com histórico deixam de operar, mas continuam
+-- resolvíveis pelos relatórios e pelas internações e transferências antigas
+ALTER TABLE unidadeSaude ADD COLUMN desativado_em TIMESTAMPTZ;
+ALTER TABLE leito ADD COLUMN desativado_em TIMESTAMPTZ;
+
+-- A desativação entra no histórico como a remoção do leito
+CREATE OR REPLACE FUNCTION registrar_leito_evento() RETURNS trigger AS $$
+BEGIN
+	IF TG_OP = 'INSERT' THEN
+		INSERT INTO leito_evento (leito_id, unidade_id, situacao_anterior, situacao)
+		VALUES (NEW.id, NEW.unidade_id, NULL, NEW.situacao);
+	ELSIF TG_OP = 'UPDATE' THEN
+		IF NEW.desativado_em IS NOT NULL AND OLD.desativado_em IS NULL THEN
+			INSERT INTO leito_evento (leito_id, unidade_id, situacao_anterior, situacao)
+			VALUES (NEW.id, NEW.unidade_id, OLD.situacao, NULL);
+		ELSIF NEW.situacao IS DISTINCT FROM OLD.situacao OR NEW.unidade_id IS DISTINCT FROM OLD.unidade_id THEN
+			INSERT INTO leito_evento (leito_id, unidade_id, situacao_anterior, situacao)
+			VALUES (NEW.id, NEW.unidade_id, OLD.situacao, NEW.situacao);
+		END IF;
+	ELSE
+		INSERT INTO leito_evento (leito_id, unidade_id, situacao_anterior, situacao)
+		VALUES (OLD.id, OLD.unidade_id, OLD.situacao, NULL);
+	END IF;
+	RETURN NULL;
+END;
+$$ LANGUAGE plpgsql;
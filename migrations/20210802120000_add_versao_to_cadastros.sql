@@ -0,0 +1,79 @@
+-- Versão de cada recurso, exposta como ETag para o controle de concorrência otimista
+ALTER TABLE unidadeSaude ADD COLUMN versao INTEGER NOT NULL DEFAULT 1;
+ALTER TABLE leito ADD COLUMN versao INTEGER NOT NULL DEFAULT 1;
+ALTER TABLE paciente ADD COLUMN versao INTEGER NOT NULL DEFAULT 1;
+
+-- Toda atualização que muda a linha avança a versão, venha ela da API ou de outra trigger
+CREATE FUNCTION incrementar_versao() RETURNS trigger AS $$
+BEGIN
+	IF NEW IS DISTINCT FROM OLD THEN
+		NEW.versao := OLD.versao + 1;
+	END IF;
+	RETURN NEW;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER versao_unidade_trigger
+	BEFORE UPDATE ON unidadeSaude
+	FOR EACH ROW EXECUTE FUNCTION incrementar_versao();
+
+CREATE TRIGGER versao_leito_trigger
+	BEFORE UPDATE ON leito
+	FOR EACH ROW EXECUTE FUNCTION incrementar_versao();
+
+CREATE TRIGGER versao_paciente_trigger
+	BEFORE UPDATE ON paciente
+	FOR EACH ROW EXECUTE FUNCTION incrementar_versao();
+
+-- A representação do paciente inclui o leito atual e a situação de COVID-19, que vivem
+-- em outras tabelas; mudanças nelas também avançam a versão do paciente
+CREATE FUNCTION incrementar_versao_paciente() RETURNS trigger AS $$
+BEGIN
+	UPDATE paciente SET versao = versao + 1 WHERE id = NEW.paciente_id;
+	RETURN NULL;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER versao_paciente_internacao_trigger
+	AFTER INSERT OR UPDATE ON internacao
+	FOR EACH ROW EXECUTE FUNCTION incrementar_versao_paciente();
+
+CREATE TRIGGER versao_paciente_covid_trigger
+	AFTER INSERT ON registro_covid
+	FOR EACH ROW EXECUTE FUNCTION incrementar_versao_paciente();
+
+-- A versão não é um dado do recurso: fica fora do diff, e uma atualização que só muda
+-- a versão não gera registro
+CREATE OR REPLACE FUNCTION registrar_auditoria() RETURNS trigger AS $$
+DECLARE
+	v_antes JSONB;
+	v_depois JSONB;
+BEGIN
+	IF TG_OP = 'INSERT' THEN
+		v_depois := to_jsonb(NEW);
+	ELSIF TG_OP = 'DELETE' THEN
+		v_antes := to_jsonb(OLD);
+	ELSE
+		SELECT jsonb_object_agg(A.key, A.value), jsonb_object_agg(D.key, D.value)
+		INTO v_antes, v_depois
+		FROM jsonb_each(to_jsonb(OLD)) as A JOIN jsonb_each(to_jsonb(NEW)) as D ON D.key = A.key
+		WHERE A.value IS DISTINCT FROM D.value AND A.key <> 'versao';
+
+		IF v_antes IS NULL THEN
+			RETURN NULL;
+		END IF;
+	END IF;
+
+	INSERT INTO auditoria (usuario_id, rota, entidade, entidade_id, operacao, antes, depois)
+	VALUES (
+		NULLIF(current_setting('auditoria.usuario_id', true), '')::uuid,
+		NULLIF(current_setting('auditoria.rota', true), ''),
+		TG_ARGV[0]::entidade_auditada,
+		(COALESCE(to_jsonb(NEW), to_jsonb(OLD)) ->> 'id')::uuid,
+		CASE TG_OP WHEN 'INSERT' THEN 'insercao' WHEN 'UPDATE' THEN 'atualizacao' ELSE 'remocao' END::operacao_auditada,
+		v_antes,
+		v_depois
+	);
+	RETURN NULL;
+END;
+$$ LANGUAGE plpgsql;
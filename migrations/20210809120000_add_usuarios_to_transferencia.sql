@@ -0,0 +1,10 @@
+-- Quem solicita e quem aceita a transferência passam a ser os usuários autenticados,
+-- em vez de um texto livre enviado pelo cliente. Os nomes já gravados ficam apenas
+-- como histórico.
+ALTER TABLE transferencia RENAME COLUMN solicitado_por TO solicitado_por_nome;
+ALTER TABLE transferencia RENAME COLUMN aceito_por TO aceito_por_nome;
+ALTER TABLE transferencia ALTER COLUMN solicitado_por_nome DROP NOT NULL;
+
+ALTER TABLE transferencia
+		ADD COLUMN solicitado_por uuid REFERENCES usuario (id),
+		ADD COLUMN aceito_por uuid REFERENCES usuario (id);
@@ -0,0 +1,22 @@
+-- Todo paciente pertence a uma unidade, mesmo sem internação em aberto: a da internação
+-- mais recente ou, se nunca foi internado, a que fez o cadastro
+ALTER TABLE paciente ADD COLUMN unidade_cadastro_id uuid REFERENCES unidadeSaude (id);
+
+UPDATE paciente as P SET unidade_cadastro_id = (
+	SELECT L.unidade_id
+	FROM internacao as I JOIN leito as L ON L.id = I.leito_id
+	WHERE I.paciente_id = P.id
+	ORDER BY I.admitido_em, I.id
+	LIMIT 1
+);
+
+CREATE VIEW unidade_do_paciente AS
+	SELECT P.id as paciente_id, COALESCE(
+		(SELECT L.unidade_id
+		 FROM internacao as I JOIN leito as L ON L.id = I.leito_id
+		 WHERE I.paciente_id = P.id
+		 ORDER BY I.admitido_em DESC, I.id DESC
+		 LIMIT 1),
+		P.unidade_cadastro_id
+	) as unidade_id
+	FROM paciente as P;
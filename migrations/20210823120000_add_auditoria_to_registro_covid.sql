@@ -0,0 +1,6 @@
+-- A situação de COVID-19 saiu da tabela paciente e passa a ter auditoria própria
+ALTER TYPE entidade_auditada ADD VALUE 'registro_covid';
+
+CREATE TRIGGER auditoria_registro_covid_trigger
+	AFTER INSERT OR UPDATE OR DELETE ON registro_covid
+	FOR EACH ROW EXECUTE FUNCTION registrar_auditoria('registro_covid');
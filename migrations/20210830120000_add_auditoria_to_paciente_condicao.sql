@@ -0,0 +1,41 @@
+-- paciente_condicao não tem coluna id: o segundo argumento da trigger diz qual coluna
+-- identifica a entidade, e os vínculos ficam registrados sob o paciente
+ALTER TYPE entidade_auditada ADD VALUE 'paciente_condicao';
+
+CREATE OR REPLACE FUNCTION registrar_auditoria() RETURNS trigger AS $$
+DECLARE
+	v_antes JSONB;
+	v_depois JSONB;
+BEGIN
+	IF TG_OP = 'INSERT' THEN
+		v_depois := to_jsonb(NEW);
+	ELSIF TG_OP = 'DELETE' THEN
+		v_antes := to_jsonb(OLD);
+	ELSE
+		SELECT jsonb_object_agg(A.key, A.value), jsonb_object_agg(D.key, D.value)
+		INTO v_antes, v_depois
+		FROM jsonb_each(to_jsonb(OLD)) as A JOIN jsonb_each(to_jsonb(NEW)) as D ON D.key = A.key
+		WHERE A.value IS DISTINCT FROM D.value AND A.key <> 'versao';
+
+		IF v_antes IS NULL THEN
+			RETURN NULL;
+		END IF;
+	END IF;
+
+	INSERT INTO auditoria (usuario_id, rota, entidade, entidade_id, operacao, antes, depois)
+	VALUES (
+		NULLIF(current_setting('auditoria.usuario_id', true), '')::uuid,
+		NULLIF(current_setting('auditoria.rota', true), ''),
+		TG_ARGV[0]::entidade_auditada,
+		(COALESCE(to_jsonb(NEW), to_jsonb(OLD)) ->> COALESCE(TG_ARGV[1], 'id'))::uuid,
+		CASE TG_OP WHEN 'INSERT' THEN 'insercao' WHEN 'UPDATE' THEN 'atualizacao' ELSE 'remocao' END::operacao_auditada,
+		v_antes,
+		v_depois
+	);
+	RETURN NULL;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER auditoria_paciente_condicao_trigger
+	AFTER INSERT OR UPDATE OR DELETE ON paciente_condicao
+	FOR EACH ROW EXECUTE FUNCTION registrar_auditoria('paciente_condicao', 'paciente_id');
@@ -0,0 +1,52 @@
+//! src/audit.rs
+use std::future::{ready, Ready};
+
+use actix_web::dev::Payload;
+use actix_web::{FromRequest, HttpRequest};
+use sqlx::{types::Uuid, PgPool, Postgres, Transaction};
+
+use crate::authentication::UsuarioAutenticado;
+use crate::error::ApiError;
+
+/// Autor e rota da requisição, gravados pelas triggers de auditoria em cada escrita
+/// feita dentro da transação aberta por [`Auditoria::begin`].
+#[derive(Debug, Clone)]
+pub struct Auditoria {
+    usuario_id: Option<Uuid>,
+    rota: String,
+}
+
+impl Auditoria {
+    /// Abre uma transação identificada; toda escrita em unidades, leitos e pacientes
+    /// deve passar por uma delas para que a auditoria saiba quem a fez.
+    pub async fn begin(&self, pool: &PgPool) -> Result<Transaction<'static, Postgres>, ApiError> {
+        let mut transaction = pool.begin().await?;
+
+        // `set_config` com `is_local = true` vale só até o fim da transação
+        sqlx::query!(
+            r#"
+            SELECT set_config('auditoria.usuario_id', $1, true) as usuario_id,
+                   set_config('auditoria.rota', $2, true) as rota
+            "#,
+            self.usuario_id.map(|id| id.to_string()).unwrap_or_default(),
+            self.rota
+        )
+        .fetch_one(&mut transaction)
+        .await?;
+
+        Ok(transaction)
+    }
+}
+
+impl FromRequest for Auditoria {
+    type Config = ();
+    type Error = actix_web::Error;
+    type Future = Ready<Result<Self, Self::Error>>;
+
+    fn from_request(req: &HttpRequest, _: &mut Payload) -> Self::Future {
+        ready(Ok(Auditoria {
+            usuario_id: req.extensions().get::<UsuarioAutenticado>().map(|usuario| usuario.id),
+            rota: format!("{} {}", req.method(), req.path()),
+        }))
+    }
+}
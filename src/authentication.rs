@@ -0,0 +1,188 @@
+//! src/authentication.rs
+use std::future::{ready, Future, Ready};
+use std::pin::Pin;
+use std::rc::Rc;
+use std::task::{Context, Poll};
+
+use actix_web::dev::{Service, ServiceRequest, ServiceResponse, Transform};
+use actix_web::http::{header::AUTHORIZATION, Method};
+use actix_web::{web, HttpMessage};
+use anyhow::Context as _;
+use argon2::password_hash::{PasswordHash, PasswordHasher, PasswordVerifier, SaltString};
+use argon2::Argon2;
+use rand::{distributions::Alphanumeric, Rng, RngCore};
+use sha2::{Digest, Sha256};
+use sqlx::{types::Uuid, PgPool};
+
+use crate::domain::Papel;
+use crate::error::ApiError;
+
+/// Usuário dono do token da requisição, disponível para os handlers via `web::ReqData`.
+#[derive(Debug, Clone)]
+pub struct UsuarioAutenticado {
+    pub id: Uuid,
+    pub token_id: Uuid,
+    pub papel: Papel,
+    pub unidade_id: Option<Uuid>,
+    pub municipio: Option<String>,
+}
+
+/// Tempo de vida dos tokens emitidos no login.
+#[derive(Debug, Clone, Copy)]
+pub struct ValidadeToken(pub chrono::Duration);
+
+pub async fn hash_senha(senha: String) -> Result<String, ApiError> {
+    web::block(move || {
+        let mut bytes = [0u8; 16];
+        rand::thread_rng().fill_bytes(&mut bytes);
+        let salt = SaltString::encode_b64(&bytes).map_err(|e| anyhow::anyhow!(e))?;
+
+        Argon2::default()
+            .hash_password(senha.as_bytes(), &salt)
+            .map(|hash| hash.to_string())
+            .map_err(|e| anyhow::anyhow!(e))
+    })
+    .await
+    .context("Falha ao executar o hash da senha")
+    .map_err(ApiError::Internal)?
+    .map_err(ApiError::Internal)
+}
+
+pub async fn verificar_senha(senha: String, senha_hash: String) -> Result<bool, ApiError> {
+    web::block(move || {
+        let hash = PasswordHash::new(&senha_hash).map_err(|e| anyhow::anyhow!(e))?;
+        Ok(Argon2::default().verify_password(senha.as_bytes(), &hash).is_ok())
+    })
+    .await
+    .context("Falha ao verificar a senha")
+    .map_err(ApiError::Internal)?
+    .map_err(ApiError::Internal)
+}
+
+/// Gera um token opaco; só o hash dele vai para o banco.
+pub fn gerar_token() -> String {
+    rand::thread_rng()
+        .sample_iter(&Alphanumeric)
+        .take(48)
+        .map(char::from)
+        .collect()
+}
+
+pub fn hash_token(token: &str) -> String {
+    hex::encode(Sha256::digest(token.as_bytes()))
+}
+
+// Rotas acessíveis sem token
+fn rota_publica(req: &ServiceRequest) -> bool {
+    req.path() == "/ping" || (req.path() == "/login" && req.method() == Method::POST)
+}
+
+async fn autenticar(req: &ServiceRequest, pool: &PgPool) -> Result<Option<UsuarioAutenticado>, ApiError> {
+    let token = match req.headers().get(AUTHORIZATION) {
+        Some(valor) => valor
+            .to_str()
+            .ok()
+            .and_then(|valor| valor.strip_prefix("Bearer "))
+            .ok_or_else(|| ApiError::Unauthorized("Cabeçalho Authorization inválido".into()))?,
+        None => return Ok(None),
+    };
+
+    let row = sqlx::query!(
+        r#"
+        SELECT T.id, T.usuario_id, U.papel as "papel: Papel", U.unidade_id, U.municipio
+        FROM token_acesso as T JOIN usuario as U ON U.id = T.usuario_id
+        WHERE T.token_hash = $1 AND T.revogado_em IS NULL AND T.expira_em > now()
+        "#,
+        hash_token(token.trim())
+    )
+    .fetch_optional(pool)
+    .await?
+    .ok_or_else(|| ApiError::Unauthorized("Token inválido, expirado ou revogado".into()))?;
+
+    Ok(Some(UsuarioAutenticado {
+        id: row.usuario_id,
+        token_id: row.id,
+        papel: row.papel,
+        unidade_id: row.unidade_id,
+        municipio: row.municipio,
+    }))
+}
+
+// O cadastro fica aberto apenas enquanto não existe nenhum usuário, para criar o primeiro;
+// o handler confere de novo sob trava, porque dois cadastros podem passar juntos por aqui
+async fn cadastro_inicial(req: &ServiceRequest, pool: &PgPool) -> Result<bool, ApiError> {
+    if req.path() != "/usuarios" || req.method() != Method::POST {
+        return Ok(false);
+    }
+
+    let row = sqlx::query!(r#"SELECT EXISTS (SELECT 1 FROM usuario) as "existe!""#)
+        .fetch_one(pool)
+        .await?;
+
+    Ok(!row.existe)
+}
+
+/// Middleware que exige um token Bearer válido em todas as rotas, exceto as públicas.
+pub struct Autenticacao;
+
+impl<S, B> Transform<S, ServiceRequest> for Autenticacao
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = actix_web::Error> + 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<B>;
+    type Error = actix_web::Error;
+    type Transform = AutenticacaoMiddleware<S>;
+    type InitError = ();
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ready(Ok(AutenticacaoMiddleware {
+            service: Rc::new(service),
+        }))
+    }
+}
+
+pub struct AutenticacaoMiddleware<S> {
+    service: Rc<S>,
+}
+
+impl<S, B> Service<ServiceRequest> for AutenticacaoMiddleware<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = actix_web::Error> + 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<B>;
+    type Error = actix_web::Error;
+    #[allow(clippy::type_complexity)]
+    type Future = Pin<Box<dyn Future<Output = Result<Self::Response, Self::Error>>>>;
+
+    fn poll_ready(&self, ctx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.service.poll_ready(ctx)
+    }
+
+    fn call(&self, req: ServiceRequest) -> Self::Future {
+        let service = Rc::clone(&self.service);
+
+        Box::pin(async move {
+            if rota_publica(&req) {
+                return service.call(req).await;
+            }
+
+            let pool = req
+                .app_data::<web::Data<PgPool>>()
+                .cloned()
+                .ok_or_else(|| ApiError::Internal(anyhow::anyhow!("Pool de conexões não configurado")))?;
+
+            match autenticar(&req, &pool).await? {
+                Some(usuario) => {
+                    req.extensions_mut().insert(usuario);
+                }
+                None if cadastro_inicial(&req, &pool).await? => {}
+                None => return Err(ApiError::Unauthorized("Autenticação necessária".into()).into()),
+            }
+
+            service.call(req).await
+        })
+    }
+}
@@ -0,0 +1,276 @@
+//! src/authorization.rs
+use std::future::{ready, Future, Ready};
+use std::pin::Pin;
+use std::rc::Rc;
+use std::task::{Context, Poll};
+
+use actix_web::dev::{Service, ServiceRequest, ServiceResponse, Transform};
+use actix_web::http::Method;
+use actix_web::{web, HttpMessage};
+use sqlx::{types::Uuid, PgPool};
+
+use crate::authentication::UsuarioAutenticado;
+use crate::domain::Papel;
+use crate::error::ApiError;
+
+use Papel::*;
+
+const TODOS: &[Papel] = &[Admin, ReguladorEstadual, GestorMunicipal, FuncionarioUnidade];
+const SOMENTE_ADMIN: &[Papel] = &[Admin];
+const UNIDADE: &[Papel] = &[Admin, FuncionarioUnidade];
+const REGULACAO: &[Papel] = &[Admin, ReguladorEstadual];
+const ASSISTENCIA: &[Papel] = &[Admin, ReguladorEstadual, FuncionarioUnidade];
+
+/// Papéis autorizados em cada rota. Leituras ficam abertas a todos os papéis e são
+/// restringidas pelo escopo do usuário; escritas não listadas ficam só com o admin.
+fn papeis_permitidos(metodo: &Method, rota: &str) -> &'static [Papel] {
+    match (metodo.as_str(), rota) {
+        (_, rota) if rota.starts_with("/transferencias") || rota.starts_with("/fila") => REGULACAO,
+        ("POST", "/leitos") | ("POST", "/leitos/{id}/transicoes") | ("POST", "/importacoes/leitos") => UNIDADE,
+        ("PUT", "/leitos/{id}") | ("PATCH", "/leitos/{id}") | ("DELETE", "/leitos/{id}") => UNIDADE,
+        ("POST", "/pacientes") | ("POST", "/pacientes/{id}/covid") => UNIDADE,
+        ("PUT", "/pacientes/{id}") | ("PATCH", "/pacientes/{id}") | ("DELETE", "/pacientes/{id}") => UNIDADE,
+        ("POST", "/pacientes/{id}/condicoes") | ("DELETE", "/pacientes/{id}/condicoes/{condicao_id}") => UNIDADE,
+        ("POST", "/pacientes/{id}/internar") | ("POST", "/internacoes/{id}/alta") => ASSISTENCIA,
+        ("GET", "/auditoria") | ("GET", "/pacientes/{id}/dados-pessoais") => SOMENTE_ADMIN,
+        ("GET", _) | ("POST", "/logout") => TODOS,
+        _ => SOMENTE_ADMIN,
+    }
+}
+
+/// Parte dos dados que o usuário pode ver ou alterar.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Escopo {
+    Todos,
+    Municipio(String),
+    Unidade(Uuid),
+}
+
+impl Escopo {
+    /// Filtro por unidade para as consultas de listagem
+    pub fn unidade_id(&self) -> Option<Uuid> {
+        match self {
+            Escopo::Unidade(id) => Some(*id),
+            _ => None,
+        }
+    }
+
+    /// Filtro por município para as consultas de listagem
+    pub fn municipio(&self) -> Option<String> {
+        match self {
+            Escopo::Municipio(municipio) => Some(municipio.clone()),
+            _ => None,
+        }
+    }
+
+    fn permite(&self, localizacao: &Localizacao) -> bool {
+        match self {
+            Escopo::Todos => true,
+            Escopo::Municipio(municipio) => localizacao
+                .municipio
+                .as_ref()
+                .is_some_and(|proprio| proprio.to_lowercase() == municipio.to_lowercase()),
+            Escopo::Unidade(id) => localizacao.unidade_id == Some(*id),
+        }
+    }
+}
+
+impl UsuarioAutenticado {
+    pub fn escopo(&self) -> Escopo {
+        match (self.papel, self.unidade_id, &self.municipio) {
+            (FuncionarioUnidade, Some(unidade_id), _) => Escopo::Unidade(unidade_id),
+            (GestorMunicipal, _, Some(municipio)) => Escopo::Municipio(municipio.clone()),
+            _ => Escopo::Todos,
+        }
+    }
+}
+
+/// Tipos de recurso cuja unidade pode ser descoberta a partir do id.
+#[derive(Debug, Clone, Copy)]
+pub enum Recurso {
+    Unidade,
+    Leito,
+    Paciente,
+    Internacao,
+}
+
+fn recurso_da_rota(rota: &str) -> Option<Recurso> {
+    match rota {
+        "/unidades/{id}" | "/unidades/{id}/leitos" | "/unidades/{id}/pacientes" | "/unidades/{id}/resumo"
+        | "/pacientes/covid/{id}" => Some(Recurso::Unidade),
+        "/leitos/{id}" | "/leitos/{id}/transicoes" | "/leitos/{id}/paciente" => Some(Recurso::Leito),
+        "/pacientes/{id}" | "/pacientes/{id}/internar" | "/pacientes/{id}/internacoes"
+        | "/pacientes/{id}/covid" | "/pacientes/{id}/condicoes"
+        | "/pacientes/{id}/condicoes/{condicao_id}" => Some(Recurso::Paciente),
+        "/internacoes/{id}" | "/internacoes/{id}/alta" => Some(Recurso::Internacao),
+        _ => None,
+    }
+}
+
+// O roteamento ainda não aconteceu no middleware, então o `{id}` é lido comparando
+// os segmentos do padrão da rota com os do caminho
+fn id_do_caminho(rota: &str, caminho: &str) -> Option<Uuid> {
+    rota.split('/')
+        .zip(caminho.split('/'))
+        .find(|(padrao, _)| *padrao == "{id}")
+        .and_then(|(_, valor)| Uuid::parse_str(valor).ok())
+}
+
+// Unidade do recurso; fica vazia para o paciente cadastrado pelo admin e nunca internado
+struct Localizacao {
+    unidade_id: Option<Uuid>,
+    municipio: Option<String>,
+}
+
+// Unidade a que o recurso pertence; `None` se o recurso não existe
+async fn localizar(pool: &PgPool, recurso: Recurso, id: Uuid) -> Result<Option<Localizacao>, ApiError> {
+    let localizacao = match recurso {
+        Recurso::Unidade => sqlx::query!("SELECT id, municipio FROM unidadeSaude WHERE id = $1", id)
+            .fetch_optional(pool)
+            .await?
+            .map(|row| Localizacao { unidade_id: Some(row.id), municipio: Some(row.municipio) }),
+        Recurso::Leito => sqlx::query!(
+            r#"
+            SELECT U.id, U.municipio
+            FROM leito as L JOIN unidadeSaude as U ON U.id = L.unidade_id
+            WHERE L.id = $1
+            "#,
+            id
+        )
+        .fetch_optional(pool)
+        .await?
+        .map(|row| Localizacao { unidade_id: Some(row.id), municipio: Some(row.municipio) }),
+        // a unidade da internação mais recente ou, sem internações, a do cadastro
+        Recurso::Paciente => sqlx::query!(
+            r#"
+            SELECT U.id as "id?", U.municipio as "municipio?"
+            FROM unidade_do_paciente as PU
+            LEFT JOIN unidadeSaude as U ON U.id = PU.unidade_id
+            WHERE PU.paciente_id = $1
+            "#,
+            id
+        )
+        .fetch_optional(pool)
+        .await?
+        .map(|row| Localizacao { unidade_id: row.id, municipio: row.municipio }),
+        Recurso::Internacao => sqlx::query!(
+            r#"
+            SELECT U.id, U.municipio
+            FROM internacao as I
+            JOIN leito as L ON L.id = I.leito_id
+            JOIN unidadeSaude as U ON U.id = L.unidade_id
+            WHERE I.id = $1
+            "#,
+            id
+        )
+        .fetch_optional(pool)
+        .await?
+        .map(|row| Localizacao { unidade_id: Some(row.id), municipio: Some(row.municipio) }),
+    };
+
+    Ok(localizacao)
+}
+
+/// Recusa com 403 o acesso a um recurso fora do escopo do usuário. Recursos inexistentes
+/// passam, para que o handler responda 404.
+pub async fn exigir_escopo(
+    usuario: &UsuarioAutenticado,
+    pool: &PgPool,
+    recurso: Recurso,
+    id: Uuid
+) -> Result<(), ApiError> {
+    let escopo = usuario.escopo();
+    if escopo == Escopo::Todos {
+        return Ok(());
+    }
+
+    match localizar(pool, recurso, id).await? {
+        Some(localizacao) if !escopo.permite(&localizacao) => Err(ApiError::Forbidden(format!(
+            "{:?} {} fora do escopo do usuário",
+            recurso, id
+        ))),
+        _ => Ok(()),
+    }
+}
+
+async fn autorizar(req: &ServiceRequest, usuario: &UsuarioAutenticado) -> Result<(), ApiError> {
+    // rotas inexistentes seguem para a resposta 404
+    let rota = match req.match_pattern() {
+        Some(rota) => rota,
+        None => return Ok(()),
+    };
+
+    if !papeis_permitidos(req.method(), &rota).contains(&usuario.papel) {
+        return Err(ApiError::Forbidden(format!(
+            "O papel {} não tem acesso a {} {}",
+            usuario.papel,
+            req.method(),
+            rota
+        )));
+    }
+
+    if let (Some(recurso), Some(id)) = (recurso_da_rota(&rota), id_do_caminho(&rota, req.path())) {
+        let pool = req
+            .app_data::<web::Data<PgPool>>()
+            .cloned()
+            .ok_or_else(|| ApiError::Internal(anyhow::anyhow!("Pool de conexões não configurado")))?;
+        exigir_escopo(usuario, &pool, recurso, id).await?;
+    }
+
+    Ok(())
+}
+
+/// Middleware que aplica a tabela de papéis por rota e o escopo dos recursos endereçados
+/// pelo caminho. Deve rodar depois de `Autenticacao`.
+pub struct Autorizacao;
+
+impl<S, B> Transform<S, ServiceRequest> for Autorizacao
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = actix_web::Error> + 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<B>;
+    type Error = actix_web::Error;
+    type Transform = AutorizacaoMiddleware<S>;
+    type InitError = ();
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ready(Ok(AutorizacaoMiddleware {
+            service: Rc::new(service),
+        }))
+    }
+}
+
+pub struct AutorizacaoMiddleware<S> {
+    service: Rc<S>,
+}
+
+impl<S, B> Service<ServiceRequest> for AutorizacaoMiddleware<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = actix_web::Error> + 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<B>;
+    type Error = actix_web::Error;
+    #[allow(clippy::type_complexity)]
+    type Future = Pin<Box<dyn Future<Output = Result<Self::Response, Self::Error>>>>;
+
+    fn poll_ready(&self, ctx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.service.poll_ready(ctx)
+    }
+
+    fn call(&self, req: ServiceRequest) -> Self::Future {
+        let service = Rc::clone(&self.service);
+
+        Box::pin(async move {
+            // sem usuário só chegam aqui as rotas públicas e o cadastro inicial
+            let usuario = req.extensions().get::<UsuarioAutenticado>().cloned();
+            if let Some(usuario) = usuario {
+                autorizar(&req, &usuario).await?;
+            }
+
+            service.call(req).await
+        })
+    }
+}
@@ -0,0 +1,97 @@
+//! src/concurrency.rs
+use std::future::{ready, Ready};
+
+use actix_web::dev::Payload;
+use actix_web::http::header::{HeaderName, ETAG, IF_MATCH, IF_NONE_MATCH};
+use actix_web::{FromRequest, HttpRequest, HttpResponse};
+use serde::Serialize;
+
+use crate::error::ApiError;
+
+/// Recurso acompanhado da versão gravada no banco, que vira o `ETag` da resposta.
+pub struct Versionado<T> {
+    pub recurso: T,
+    pub versao: i32,
+}
+
+/// `ETag` forte de uma versão: o número entre aspas.
+pub fn etag(versao: i32) -> String {
+    format!("\"{}\"", versao)
+}
+
+/// Resposta 200 com o recurso no corpo e a versão no `ETag`.
+pub fn com_etag<T: Serialize>(versionado: &Versionado<T>) -> HttpResponse {
+    HttpResponse::Ok()
+        .insert_header((ETAG, etag(versionado.versao)))
+        .json(&versionado.recurso)
+}
+
+/// Cabeçalhos condicionais da requisição. A extração nunca falha: cada handler confere
+/// as condições depois de carregar o recurso, para que um id inexistente continue
+/// respondendo 404.
+#[derive(Debug, Clone, Default)]
+pub struct PreCondicao {
+    if_match: Option<String>,
+    if_none_match: Option<String>,
+}
+
+impl PreCondicao {
+    /// Exige `If-Match` com a versão atual nas escritas: sem o cabeçalho responde 428;
+    /// com outra versão, 412. `*` aceita qualquer versão do recurso existente.
+    pub fn exigir(&self, versao: i32) -> Result<(), ApiError> {
+        let if_match = self.if_match.as_deref().ok_or_else(|| {
+            ApiError::PreconditionRequired("Informe o ETag do recurso no cabeçalho If-Match".into())
+        })?;
+
+        // If-Match usa a comparação forte: ETags fracos nunca casam
+        let atual = etag(versao);
+        if tags(if_match).any(|tag| tag == "*" || tag == atual) {
+            Ok(())
+        } else {
+            Err(ApiError::PreconditionFailed(format!(
+                "O recurso foi alterado; a versão atual é {}",
+                atual
+            )))
+        }
+    }
+
+    /// Responde 304 sem corpo quando o `If-None-Match` já tem a versão atual; senão, 200
+    /// com o recurso.
+    pub fn responder<T: Serialize>(&self, versionado: &Versionado<T>) -> HttpResponse {
+        let atual = etag(versionado.versao);
+        let nao_modificado = self.if_none_match.as_deref().is_some_and(|if_none_match| {
+            // If-None-Match usa a comparação fraca: o prefixo W/ é ignorado
+            tags(if_none_match).any(|tag| tag == "*" || tag.trim_start_matches("W/") == atual)
+        });
+
+        if nao_modificado {
+            HttpResponse::NotModified().insert_header((ETAG, atual)).finish()
+        } else {
+            com_etag(versionado)
+        }
+    }
+}
+
+fn tags(cabecalho: &str) -> impl Iterator<Item = &str> {
+    cabecalho.split(',').map(str::trim).filter(|tag| !tag.is_empty())
+}
+
+fn cabecalho(req: &HttpRequest, nome: HeaderName) -> Option<String> {
+    req.headers()
+        .get(nome)
+        .and_then(|valor| valor.to_str().ok())
+        .map(String::from)
+}
+
+impl FromRequest for PreCondicao {
+    type Config = ();
+    type Error = actix_web::Error;
+    type Future = Ready<Result<Self, Self::Error>>;
+
+    fn from_request(req: &HttpRequest, _: &mut Payload) -> Self::Future {
+        ready(Ok(PreCondicao {
+            if_match: cabecalho(req, IF_MATCH),
+            if_none_match: cabecalho(req, IF_NONE_MATCH),
+        }))
+    }
+}
@@ -12,6 +12,9 @@ pub struct Settings {
 pub struct ApplicationSettings {
     pub port: u16,
     pub host: String,
+    /// Validade dos tokens emitidos em `POST /login`
+    #[serde(deserialize_with = "deserialize_number_from_string")]
+    pub token_validade_minutos: i64,
 }
 
 #[derive(serde::Deserialize, Clone)]
@@ -0,0 +1,20 @@
+//! src/domain/entidade_auditada.rs
+use serde::{Deserialize, Serialize};
+
+/// Tabela cujas escritas ficam registradas na auditoria, persistida no tipo `entidade_auditada`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, sqlx::Type)]
+#[sqlx(type_name = "entidade_auditada", rename_all = "snake_case")]
+pub enum EntidadeAuditada {
+    Unidade,
+    Leito,
+    Paciente,
+    RegistroCovid,
+    /// Registrada sob o id do paciente
+    PacienteCondicao,
+}
+
+impl std::fmt::Display for EntidadeAuditada {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        std::fmt::Debug::fmt(self, f)
+    }
+}
@@ -0,0 +1,20 @@
+//! src/domain/mod.rs
+mod entidade_auditada;
+mod motivo_alta;
+mod operacao_auditada;
+mod papel;
+mod situacao_covid;
+mod situacao_leito;
+mod situacao_transferencia;
+mod tipo_leito;
+mod tipo_teste_covid;
+
+pub use entidade_auditada::*;
+pub use motivo_alta::*;
+pub use operacao_auditada::*;
+pub use papel::*;
+pub use situacao_covid::*;
+pub use situacao_leito::*;
+pub use situacao_transferencia::*;
+pub use tipo_leito::*;
+pub use tipo_teste_covid::*;
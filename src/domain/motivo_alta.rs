@@ -0,0 +1,12 @@
+//! src/domain/motivo_alta.rs
+use serde::{Deserialize, Serialize};
+
+/// Motivo de encerramento de uma internação, persistido no tipo `motivo_alta` do Postgres.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, sqlx::Type)]
+#[sqlx(type_name = "motivo_alta", rename_all = "snake_case")]
+pub enum MotivoAlta {
+    AltaMedica,
+    Transferencia,
+    Obito,
+    Evasao,
+}
@@ -0,0 +1,17 @@
+//! src/domain/operacao_auditada.rs
+use serde::{Deserialize, Serialize};
+
+/// Tipo de escrita registrada na auditoria, persistido no tipo `operacao_auditada`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, sqlx::Type)]
+#[sqlx(type_name = "operacao_auditada", rename_all = "snake_case")]
+pub enum OperacaoAuditada {
+    Insercao,
+    Atualizacao,
+    Remocao,
+}
+
+impl std::fmt::Display for OperacaoAuditada {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        std::fmt::Debug::fmt(self, f)
+    }
+}
@@ -0,0 +1,20 @@
+//! src/domain/papel.rs
+use serde::{Deserialize, Serialize};
+
+/// Papel de acesso do usuário, persistido no tipo `papel_usuario` do Postgres.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, sqlx::Type)]
+#[sqlx(type_name = "papel_usuario", rename_all = "snake_case")]
+pub enum Papel {
+    Admin,
+    ReguladorEstadual,
+    /// Lê os dados das unidades do seu município
+    GestorMunicipal,
+    /// Mantém leitos e pacientes da sua unidade
+    FuncionarioUnidade,
+}
+
+impl std::fmt::Display for Papel {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        std::fmt::Debug::fmt(self, f)
+    }
+}
@@ -0,0 +1,18 @@
+//! src/domain/situacao_covid.rs
+use serde::{Deserialize, Serialize};
+
+/// Situação clínica de COVID-19 do paciente, persistida no tipo `situacao_covid` do Postgres.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, sqlx::Type)]
+#[sqlx(type_name = "situacao_covid", rename_all = "snake_case")]
+pub enum SituacaoCovid {
+    Suspeito,
+    Confirmado,
+    Descartado,
+    Recuperado,
+}
+
+impl std::fmt::Display for SituacaoCovid {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        std::fmt::Debug::fmt(self, f)
+    }
+}
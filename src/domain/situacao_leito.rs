@@ -0,0 +1,58 @@
+//! src/domain/situacao_leito.rs
+use serde::{Deserialize, Serialize};
+
+/// Situação operacional de um leito, persistida no tipo `situacao_leito` do Postgres.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, sqlx::Type)]
+#[sqlx(type_name = "situacao_leito", rename_all = "snake_case")]
+pub enum SituacaoLeito {
+    Livre,
+    Ocupado,
+    Reservado,
+    EmHigienizacao,
+    Bloqueado,
+    EmManutencao,
+}
+
+impl SituacaoLeito {
+    /// Tabela de transições permitidas a partir desta situação.
+    ///
+    /// Um leito ocupado sempre passa pela higienização antes de voltar a ser oferecido,
+    /// por isso não existe, por exemplo, `Ocupado -> Reservado` ou `Ocupado -> Livre`.
+    pub fn destinos_permitidos(self) -> &'static [SituacaoLeito] {
+        use SituacaoLeito::*;
+        match self {
+            Livre => &[Reservado, Ocupado, EmHigienizacao, Bloqueado, EmManutencao],
+            Reservado => &[Livre, Ocupado, Bloqueado],
+            Ocupado => &[EmHigienizacao],
+            EmHigienizacao => &[Livre, EmManutencao, Bloqueado],
+            Bloqueado => &[Livre, EmHigienizacao, EmManutencao],
+            EmManutencao => &[EmHigienizacao, Bloqueado],
+        }
+    }
+
+    pub fn pode_transicionar_para(self, destino: SituacaoLeito) -> bool {
+        self.destinos_permitidos().contains(&destino)
+    }
+
+    /// Valida a transição `self -> destino`, devolvendo a nova situação.
+    pub fn transicionar_para(self, destino: SituacaoLeito) -> Result<SituacaoLeito, TransicaoInvalida> {
+        if self.pode_transicionar_para(destino) {
+            Ok(destino)
+        } else {
+            Err(TransicaoInvalida { origem: self, destino })
+        }
+    }
+}
+
+impl std::fmt::Display for SituacaoLeito {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        std::fmt::Debug::fmt(self, f)
+    }
+}
+
+#[derive(Debug, thiserror::Error)]
+#[error("Transição de {origem} para {destino} não permitida")]
+pub struct TransicaoInvalida {
+    pub origem: SituacaoLeito,
+    pub destino: SituacaoLeito,
+}
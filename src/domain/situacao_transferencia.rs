@@ -0,0 +1,58 @@
+//! src/domain/situacao_transferencia.rs
+use serde::{Deserialize, Serialize};
+
+/// Etapa de uma transferência de paciente, persistida no tipo `situacao_transferencia` do Postgres.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, sqlx::Type)]
+#[sqlx(type_name = "situacao_transferencia", rename_all = "snake_case")]
+pub enum SituacaoTransferencia {
+    Solicitada,
+    Aceita,
+    EmTransito,
+    Concluida,
+    Recusada,
+}
+
+impl SituacaoTransferencia {
+    /// Tabela de transições permitidas a partir desta etapa.
+    ///
+    /// A recusa só é possível antes de o paciente sair da origem; uma vez em trânsito,
+    /// a transferência precisa ser concluída.
+    pub fn destinos_permitidos(self) -> &'static [SituacaoTransferencia] {
+        use SituacaoTransferencia::*;
+        match self {
+            Solicitada => &[Aceita, Recusada],
+            Aceita => &[EmTransito, Recusada],
+            EmTransito => &[Concluida],
+            Concluida | Recusada => &[],
+        }
+    }
+
+    pub fn pode_transicionar_para(self, destino: SituacaoTransferencia) -> bool {
+        self.destinos_permitidos().contains(&destino)
+    }
+
+    /// Valida a transição `self -> destino`, devolvendo a nova etapa.
+    pub fn transicionar_para(
+        self,
+        destino: SituacaoTransferencia,
+    ) -> Result<SituacaoTransferencia, TransicaoTransferenciaInvalida> {
+        if self.pode_transicionar_para(destino) {
+            Ok(destino)
+        } else {
+            Err(TransicaoTransferenciaInvalida { origem: self, destino })
+        }
+    }
+}
+
+impl std::fmt::Display for SituacaoTransferencia {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        std::fmt::Debug::fmt(self, f)
+    }
+}
+
+#[derive(Debug, thiserror::Error)]
+#[error("Transferência não pode passar de {origem} para {destino}")]
+pub struct TransicaoTransferenciaInvalida {
+    pub origem: SituacaoTransferencia,
+    pub destino: SituacaoTransferencia,
+}
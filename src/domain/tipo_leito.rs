@@ -0,0 +1,23 @@
+//! src/domain/tipo_leito.rs
+use serde::{Deserialize, Serialize};
+
+/// Classificação do leito, persistida no tipo `tipo_leito` do Postgres.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, sqlx::Type)]
+#[sqlx(type_name = "tipo_leito", rename_all = "snake_case")]
+pub enum TipoLeito {
+    UtiAdulto,
+    UtiPediatrica,
+    UtiNeonatal,
+    /// Unidade de Cuidados Intermediários
+    Uci,
+    EnfermariaClinica,
+    EnfermariaCirurgica,
+    IsolamentoRespiratorio,
+    SemiIntensiva,
+}
+
+impl std::fmt::Display for TipoLeito {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        std::fmt::Debug::fmt(self, f)
+    }
+}
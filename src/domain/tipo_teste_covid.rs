@@ -0,0 +1,17 @@
+//! src/domain/tipo_teste_covid.rs
+use serde::{Deserialize, Serialize};
+
+/// Método do teste de COVID-19, persistido no tipo `tipo_teste_covid` do Postgres.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, sqlx::Type)]
+#[sqlx(type_name = "tipo_teste_covid", rename_all = "snake_case")]
+pub enum TipoTesteCovid {
+    RtPcr,
+    Antigeno,
+    Sorologia,
+}
+
+impl std::fmt::Display for TipoTesteCovid {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        std::fmt::Debug::fmt(self, f)
+    }
+}
@@ -0,0 +1,247 @@
+//! src/error.rs
+use actix_web::error::{JsonPayloadError, PathError, QueryPayloadError};
+use actix_web::http::header::WWW_AUTHENTICATE;
+use actix_web::http::StatusCode;
+use actix_web::{HttpRequest, HttpResponse, ResponseError};
+use serde::{Deserialize, Serialize};
+use sqlx::postgres::PgDatabaseError;
+use crate::domain::{TransicaoInvalida, TransicaoTransferenciaInvalida};
+
+// Códigos SQLSTATE do Postgres tratados de forma específica
+const UNIQUE_VIOLATION: &str = "23505";
+const FOREIGN_KEY_VIOLATION: &str = "23503";
+
+/// Corpo JSON devolvido em todas as respostas de erro da API.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ErrorBody {
+    pub code: String,
+    pub message: String,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub fields: Vec<FieldError>,
+}
+
+/// Detalhe de um campo específico que causou o erro.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FieldError {
+    pub field: String,
+    pub message: String,
+}
+
+impl FieldError {
+    pub fn new(field: impl Into<String>, message: impl Into<String>) -> Self {
+        Self {
+            field: field.into(),
+            message: message.into(),
+        }
+    }
+}
+
+#[derive(thiserror::Error)]
+pub enum ApiError {
+    #[error("{0}")]
+    NotFound(String),
+    #[error("{0}")]
+    Unauthorized(String),
+    #[error("{0}")]
+    Forbidden(String),
+    #[error("{0}")]
+    PreconditionFailed(String),
+    #[error("{0}")]
+    PreconditionRequired(String),
+    #[error("{message}")]
+    Conflict {
+        message: String,
+        fields: Vec<FieldError>,
+    },
+    #[error("{message}")]
+    UnprocessableEntity {
+        message: String,
+        fields: Vec<FieldError>,
+    },
+    #[error("{message}")]
+    Validation {
+        message: String,
+        fields: Vec<FieldError>,
+    },
+    #[error("Erro interno do servidor")]
+    Internal(#[source] anyhow::Error),
+}
+
+impl ApiError {
+    pub fn validation(message: impl Into<String>, fields: Vec<FieldError>) -> Self {
+        ApiError::Validation {
+            message: message.into(),
+            fields,
+        }
+    }
+
+    pub fn code(&self) -> &'static str {
+        match self {
+            ApiError::NotFound(_) => "not_found",
+            ApiError::Unauthorized(_) => "unauthorized",
+            ApiError::Forbidden(_) => "forbidden",
+            ApiError::PreconditionFailed(_) => "precondition_failed",
+            ApiError::PreconditionRequired(_) => "precondition_required",
+            ApiError::Conflict { .. } => "conflict",
+            ApiError::UnprocessableEntity { .. } => "unprocessable_entity",
+            ApiError::Validation { .. } => "validation_error",
+            ApiError::Internal(_) => "internal_error",
+        }
+    }
+
+    pub fn fields(&self) -> &[FieldError] {
+        match self {
+            ApiError::Conflict { fields, .. }
+            | ApiError::UnprocessableEntity { fields, .. }
+            | ApiError::Validation { fields, .. } => fields,
+            _ => &[],
+        }
+    }
+}
+
+impl std::fmt::Debug for ApiError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        error_chain_fmt(self, f)
+    }
+}
+
+impl ResponseError for ApiError {
+    fn status_code(&self) -> StatusCode {
+        match self {
+            ApiError::NotFound(_) => StatusCode::NOT_FOUND,
+            ApiError::Unauthorized(_) => StatusCode::UNAUTHORIZED,
+            ApiError::Forbidden(_) => StatusCode::FORBIDDEN,
+            ApiError::PreconditionFailed(_) => StatusCode::PRECONDITION_FAILED,
+            ApiError::PreconditionRequired(_) => StatusCode::PRECONDITION_REQUIRED,
+            ApiError::Conflict { .. } => StatusCode::CONFLICT,
+            ApiError::UnprocessableEntity { .. } => StatusCode::UNPROCESSABLE_ENTITY,
+            ApiError::Validation { .. } => StatusCode::BAD_REQUEST,
+            ApiError::Internal(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+
+    fn error_response(&self) -> HttpResponse {
+        let mut response = HttpResponse::build(self.status_code());
+        if let ApiError::Unauthorized(_) = self {
+            response.insert_header((WWW_AUTHENTICATE, "Bearer"));
+        }
+        response.json(ErrorBody {
+            code: self.code().into(),
+            message: self.to_string(),
+            fields: self.fields().to_vec(),
+        })
+    }
+}
+
+impl From<sqlx::Error> for ApiError {
+    fn from(e: sqlx::Error) -> Self {
+        if let sqlx::Error::RowNotFound = e {
+            return ApiError::NotFound("Registro não encontrado".into());
+        }
+
+        let pg_error = e
+            .as_database_error()
+            .and_then(|db| db.try_downcast_ref::<PgDatabaseError>());
+        let error = match pg_error {
+            Some(pg) if pg.code() == UNIQUE_VIOLATION => ApiError::Conflict {
+                message: "Já existe um registro com este valor".into(),
+                fields: field_from_detail(pg.detail(), "valor já cadastrado"),
+            },
+            Some(pg) if pg.code() == FOREIGN_KEY_VIOLATION => ApiError::UnprocessableEntity {
+                message: "Registro referenciado não existe ou possui dependentes".into(),
+                fields: field_from_detail(pg.detail(), "referência inválida"),
+            },
+            _ => ApiError::Internal(e.into()),
+        };
+
+        // registra a cadeia de causas no span do handler em vez do stderr
+        match &error {
+            ApiError::Internal(_) => tracing::error!(error.cause_chain = ?error, "Falha ao executar consulta"),
+            _ => tracing::warn!(error.cause_chain = %error, "Consulta rejeitada pelo banco"),
+        }
+        error
+    }
+}
+
+impl From<TransicaoInvalida> for ApiError {
+    fn from(e: TransicaoInvalida) -> Self {
+        let permitidas: Vec<String> = e.origem.destinos_permitidos().iter().map(|s| s.to_string()).collect();
+        ApiError::Conflict {
+            message: e.to_string(),
+            fields: vec![FieldError::new(
+                "situacao",
+                format!("a partir de {} são permitidas: {}", e.origem, permitidas.join(", ")),
+            )],
+        }
+    }
+}
+
+impl From<TransicaoTransferenciaInvalida> for ApiError {
+    fn from(e: TransicaoTransferenciaInvalida) -> Self {
+        ApiError::Conflict {
+            message: e.to_string(),
+            fields: vec![FieldError::new("situacao", format!("transferência está {}", e.origem))],
+        }
+    }
+}
+
+/// Converte falhas de desserialização do corpo JSON em erros de validação (400).
+pub fn json_error_handler(err: JsonPayloadError, _req: &HttpRequest) -> actix_web::Error {
+    let fields = match &err {
+        JsonPayloadError::Deserialize(e) => missing_field(&e.to_string())
+            .map(|field| vec![FieldError::new(field, "campo obrigatório")])
+            .unwrap_or_default(),
+        _ => Vec::new(),
+    };
+    ApiError::validation(format!("Corpo da requisição inválido: {}", err), fields).into()
+}
+
+/// Converte identificadores de rota malformados (ex.: `/leitos/abc`) em erros de validação (400).
+pub fn path_error_handler(err: PathError, req: &HttpRequest) -> actix_web::Error {
+    let fields = req
+        .match_info()
+        .iter()
+        .map(|(name, value)| FieldError::new(name, format!("identificador inválido: '{}'", value)))
+        .collect();
+    ApiError::validation(format!("Parâmetro de rota inválido: {}", err), fields).into()
+}
+
+/// Converte parâmetros de consulta inválidos (ex.: `?tipo=xyz`) em erros de validação (400).
+pub fn query_error_handler(err: QueryPayloadError, _req: &HttpRequest) -> actix_web::Error {
+    ApiError::validation(format!("Parâmetros de consulta inválidos: {}", err), Vec::new()).into()
+}
+
+/// Resposta padrão para rotas inexistentes, no mesmo formato dos demais erros.
+pub async fn route_not_found() -> Result<HttpResponse, ApiError> {
+    Err(ApiError::NotFound("Rota não encontrada".into()))
+}
+
+// Extrai o nome da coluna de mensagens como "Key (email)=(x) already exists."
+fn field_from_detail(detail: Option<&str>, message: &str) -> Vec<FieldError> {
+    detail
+        .and_then(|d| d.strip_prefix("Key ("))
+        .and_then(|d| d.split(')').next())
+        .map(|field| vec![FieldError::new(field, message)])
+        .unwrap_or_default()
+}
+
+// Extrai o nome do campo de mensagens do serde como "missing field `email` at line 1"
+pub(crate) fn missing_field(message: &str) -> Option<String> {
+    message
+        .strip_prefix("missing field `")
+        .and_then(|m| m.split('`').next())
+        .map(String::from)
+}
+
+pub fn error_chain_fmt(
+    e: &impl std::error::Error,
+    f: &mut std::fmt::Formatter<'_>,
+) -> std::fmt::Result {
+    writeln!(f, "{}\n", e)?;
+    let mut current = e.source();
+    while let Some(cause) = current {
+        writeln!(f, "Caused by:\n\t{}", cause)?;
+        current = cause.source();
+    }
+    Ok(())
+}
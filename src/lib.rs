@@ -1,5 +1,15 @@
 //! src/lib.rs
+pub mod audit;
+pub mod authentication;
+pub mod authorization;
+pub mod concurrency;
 pub mod configuration;
+pub mod domain;
+pub mod error;
+pub mod patch;
+pub mod query;
 pub mod routes;
+pub mod spreadsheet;
 pub mod startup;
-pub mod telemetry;
\ No newline at end of file
+pub mod telemetry;
+pub mod validation;
\ No newline at end of file
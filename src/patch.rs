@@ -0,0 +1,66 @@
+//! src/patch.rs
+use serde::{de::DeserializeOwned, Serialize};
+use serde_json::Value;
+
+use crate::error::{missing_field, ApiError, FieldError};
+
+/// Tipo de mídia do JSON Merge Patch (RFC 7396), aceito nas rotas `PATCH`.
+pub const MERGE_PATCH: &str = "application/merge-patch+json";
+
+/// Aplica um JSON Merge Patch ao documento: `null` remove o campo, objetos são mesclados
+/// campo a campo e qualquer outro valor substitui o anterior.
+pub fn aplicar_merge_patch(alvo: &mut Value, patch: &Value) {
+    let patch = match patch {
+        Value::Object(patch) => patch,
+        _ => {
+            *alvo = patch.clone();
+            return;
+        }
+    };
+    if !alvo.is_object() {
+        *alvo = Value::Object(Default::default());
+    }
+    if let Value::Object(alvo) = alvo {
+        for (campo, valor) in patch {
+            if valor.is_null() {
+                alvo.remove(campo);
+            } else {
+                aplicar_merge_patch(alvo.entry(campo.clone()).or_insert(Value::Null), valor);
+            }
+        }
+    }
+}
+
+/// Mescla o patch na representação atual do recurso e lê o resultado como o payload de
+/// escrita, que depois passa pelas mesmas validações de um `PUT`. Campos nulos na
+/// representação atual contam como ausentes.
+pub fn mesclar<A, T>(atual: &A, patch: &Value) -> Result<T, ApiError>
+where
+    A: Serialize,
+    T: DeserializeOwned,
+{
+    if !patch.is_object() {
+        return Err(ApiError::validation("O patch deve ser um objeto JSON", Vec::new()));
+    }
+
+    let mut documento = serde_json::to_value(atual).map_err(|e| ApiError::Internal(e.into()))?;
+    if let Value::Object(campos) = &mut documento {
+        let nulos: Vec<String> = campos
+            .iter()
+            .filter(|(_, valor)| valor.is_null())
+            .map(|(campo, _)| campo.clone())
+            .collect();
+        for campo in nulos {
+            campos.remove(&campo);
+        }
+    }
+    aplicar_merge_patch(&mut documento, patch);
+
+    // lido a partir do texto porque os desserializadores de uuid pegam `&str` emprestado
+    serde_json::from_str(&documento.to_string()).map_err(|e| {
+        let fields = missing_field(&e.to_string())
+            .map(|field| vec![FieldError::new(field, "campo obrigatório")])
+            .unwrap_or_default();
+        ApiError::validation(format!("Patch inválido: {}", e), fields)
+    })
+}
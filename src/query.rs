@@ -0,0 +1,289 @@
+//! src/query.rs
+use std::sync::Arc;
+
+use futures::channel::mpsc;
+use futures::{SinkExt, StreamExt};
+use serde::{Deserialize, Serialize};
+use sqlx::postgres::{PgArguments, PgRow};
+use sqlx::{types::Uuid, Arguments, Encode, FromRow, PgPool, Postgres, Row, Type};
+
+use crate::error::{ApiError, FieldError};
+
+pub const LIMITE_PADRAO: i64 = 50;
+pub const LIMITE_MAXIMO: i64 = 200;
+
+/// Parâmetros de paginação e ordenação aceitos por todas as listagens.
+#[derive(Debug, Default, Deserialize)]
+pub struct Paginacao {
+    pub limit: Option<i64>,
+    /// `next_cursor` devolvido na página anterior
+    pub cursor: Option<String>,
+    /// Campo de ordenação; prefixado com `-`, em ordem decrescente
+    pub ordenar: Option<String>,
+}
+
+/// Envelope das respostas de listagem.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Pagina<T> {
+    pub itens: Vec<T>,
+    /// Registros que atendem aos filtros, somando todas as páginas
+    pub total: i64,
+    /// Ausente na última página
+    pub next_cursor: Option<String>,
+}
+
+/// Campo pelo qual uma listagem pode ser ordenada.
+pub struct Ordenacao {
+    pub campo: &'static str,
+    pub expressao: &'static str,
+    /// Tipo Postgres da expressão, usado para reconstruir o valor guardado no cursor
+    pub tipo: &'static str,
+}
+
+// Última linha entregue; a página seguinte começa logo depois dela
+#[derive(Serialize, Deserialize)]
+struct Cursor {
+    ordenar: String,
+    valor: String,
+    id: Uuid,
+}
+
+impl Cursor {
+    fn codificar(&self) -> String {
+        hex::encode(serde_json::to_vec(self).expect("Cursor sempre é serializável"))
+    }
+
+    fn decodificar(texto: &str) -> Option<Cursor> {
+        let bytes = hex::decode(texto).ok()?;
+        serde_json::from_slice(&bytes).ok()
+    }
+}
+
+/// Linhas de uma consulta entregues aos poucos, conforme são consumidas.
+pub type Linhas<T> = mpsc::Receiver<Result<T, ApiError>>;
+
+// Linhas lidas do banco à frente do consumidor
+const LINHAS_EM_ESPERA: usize = 64;
+
+/// Executa a consulta numa tarefa própria e entrega as linhas por um canal de capacidade
+/// limitada, sem carregar o resultado inteiro na memória. A leitura para no primeiro erro
+/// ou quando o receptor é descartado, como na desconexão do cliente.
+pub fn transmitir<T>(pool: &PgPool, sql: String, argumentos: PgArguments) -> Linhas<T>
+where
+    T: for<'r> FromRow<'r, PgRow> + Send + Unpin + 'static,
+{
+    let (mut envio, recepcao) = mpsc::channel(LINHAS_EM_ESPERA);
+    let pool = pool.clone();
+    actix_web::rt::spawn(async move {
+        let mut rows = sqlx::query_with(&sql, argumentos).fetch(&pool);
+        while let Some(row) = rows.next().await {
+            let linha = row.and_then(|row| T::from_row(&row)).map_err(ApiError::from);
+            let falhou = linha.is_err();
+            if envio.send(linha).await.is_err() || falhou {
+                break;
+            }
+        }
+    });
+    recepcao
+}
+
+fn resolver_ordenacao<'o>(
+    ordenacoes: &'o [Ordenacao],
+    ordenar: &str,
+) -> Result<(&'o Ordenacao, bool), ApiError> {
+    let (campo, descendente) = match ordenar.strip_prefix('-') {
+        Some(campo) => (campo, true),
+        None => (ordenar, false),
+    };
+    let ordenacao = ordenacoes.iter().find(|o| o.campo == campo).ok_or_else(|| {
+        let campos: Vec<&str> = ordenacoes.iter().map(|o| o.campo).collect();
+        ApiError::validation(
+            format!("Ordenação inválida; use um de: {}", campos.join(", ")),
+            vec![FieldError::new("ordenar", "campo desconhecido")],
+        )
+    })?;
+    Ok((ordenacao, descendente))
+}
+
+type Argumento = Arc<dyn Fn(&mut PgArguments) + Send + Sync>;
+
+/// Consulta de listagem com filtros opcionais, ordenação e paginação por cursor (keyset).
+///
+/// Os filtros são acumulados com `AND` e reaplicados tanto na contagem do total quanto na
+/// busca da página.
+pub struct Listagem {
+    select: &'static str,
+    from: &'static str,
+    id: &'static str,
+    condicoes: Vec<String>,
+    argumentos: Vec<Argumento>,
+}
+
+impl Listagem {
+    /// `select` traz as colunas lidas pelo `FromRow`; `id` é a chave única que desempata
+    /// a ordenação.
+    pub fn new(select: &'static str, from: &'static str, id: &'static str) -> Self {
+        Listagem {
+            select,
+            from,
+            id,
+            condicoes: Vec::new(),
+            argumentos: Vec::new(),
+        }
+    }
+
+    /// Acrescenta a condição quando o valor foi informado; `{}` marca onde o valor entra.
+    pub fn filtro<T>(mut self, condicao: &str, valor: Option<T>) -> Self
+    where
+        T: for<'q> Encode<'q, Postgres> + Type<Postgres> + Clone + Send + Sync + 'static,
+    {
+        if let Some(valor) = valor {
+            let marcador = self.argumento(valor);
+            self.condicoes.push(condicao.replace("{}", &marcador));
+        }
+        self
+    }
+
+    /// Acrescenta uma condição sem valor.
+    pub fn condicao(mut self, condicao: &str) -> Self {
+        self.condicoes.push(condicao.to_string());
+        self
+    }
+
+    fn argumento<T>(&mut self, valor: T) -> String
+    where
+        T: for<'q> Encode<'q, Postgres> + Type<Postgres> + Clone + Send + Sync + 'static,
+    {
+        self.argumentos.push(Arc::new(move |argumentos| argumentos.add(valor.clone())));
+        format!("${}", self.argumentos.len())
+    }
+
+    fn argumentos(&self) -> PgArguments {
+        let mut argumentos = PgArguments::default();
+        for argumento in &self.argumentos {
+            argumento(&mut argumentos);
+        }
+        argumentos
+    }
+
+    fn clausula_where(&self) -> String {
+        if self.condicoes.is_empty() {
+            String::new()
+        } else {
+            format!("WHERE {}", self.condicoes.join(" AND "))
+        }
+    }
+
+    /// Busca uma página. A primeira ordenação da lista é a padrão.
+    pub async fn paginar<T>(
+        mut self,
+        pool: &PgPool,
+        ordenacoes: &[Ordenacao],
+        paginacao: &Paginacao,
+    ) -> Result<Pagina<T>, ApiError>
+    where
+        T: for<'r> FromRow<'r, PgRow> + Send + Unpin,
+    {
+        let limite = paginacao.limit.unwrap_or(LIMITE_PADRAO);
+        if !(1..=LIMITE_MAXIMO).contains(&limite) {
+            return Err(ApiError::validation(
+                format!("O limite deve estar entre 1 e {}", LIMITE_MAXIMO),
+                vec![FieldError::new("limit", "fora do intervalo")],
+            ));
+        }
+
+        let ordenar = paginacao.ordenar.clone().unwrap_or_else(|| ordenacoes[0].campo.to_string());
+        let (ordenacao, descendente) = resolver_ordenacao(ordenacoes, &ordenar)?;
+
+        // o total ignora o cursor: é o mesmo em todas as páginas
+        let total: i64 = sqlx::query_scalar_with(
+            &format!("SELECT count(*) FROM {} {}", self.from, self.clausula_where()),
+            self.argumentos(),
+        )
+        .fetch_one(pool)
+        .await?;
+
+        if let Some(texto) = &paginacao.cursor {
+            // um cursor só vale para a ordenação que o gerou
+            let cursor = Cursor::decodificar(texto)
+                .filter(|cursor| cursor.ordenar == ordenar)
+                .ok_or_else(|| ApiError::validation(
+                    "Cursor inválido para esta listagem",
+                    vec![FieldError::new("cursor", "use o next_cursor da página anterior, com a mesma ordenação")],
+                ))?;
+            let valor = self.argumento(cursor.valor);
+            let id = self.argumento(cursor.id);
+            self.condicoes.push(format!(
+                "({}, {}) {} ({}::{}, {})",
+                ordenacao.expressao,
+                self.id,
+                if descendente { "<" } else { ">" },
+                valor,
+                ordenacao.tipo,
+                id
+            ));
+        }
+
+        let direcao = if descendente { "DESC" } else { "ASC" };
+        let sql = format!(
+            "SELECT {select}, ({ordem})::text as cursor_valor, {id} as cursor_id FROM {from} {where_} \
+             ORDER BY {ordem} {direcao}, {id} {direcao} LIMIT {limite}",
+            select = self.select,
+            ordem = ordenacao.expressao,
+            id = self.id,
+            from = self.from,
+            where_ = self.clausula_where(),
+            direcao = direcao,
+            // uma linha a mais indica que existe próxima página
+            limite = limite + 1
+        );
+
+        let mut rows = sqlx::query_with(&sql, self.argumentos()).fetch_all(pool).await?;
+
+        let mut next_cursor = None;
+        if rows.len() as i64 > limite {
+            rows.truncate(limite as usize);
+            let ultima = rows.last().expect("A página tem ao menos uma linha");
+            next_cursor = Some(
+                Cursor {
+                    ordenar,
+                    valor: ultima.try_get("cursor_valor")?,
+                    id: ultima.try_get("cursor_id")?,
+                }
+                .codificar(),
+            );
+        }
+
+        let itens = rows.iter().map(T::from_row).collect::<Result<Vec<T>, _>>()?;
+
+        Ok(Pagina { itens, total, next_cursor })
+    }
+
+    /// Todas as linhas que atendem aos filtros, na ordenação pedida, sem limite nem
+    /// cursor; usada nas exportações.
+    pub fn exportar<T>(
+        self,
+        pool: &PgPool,
+        ordenacoes: &[Ordenacao],
+        paginacao: &Paginacao,
+    ) -> Result<Linhas<T>, ApiError>
+    where
+        T: for<'r> FromRow<'r, PgRow> + Send + Unpin + 'static,
+    {
+        let ordenar = paginacao.ordenar.as_deref().unwrap_or(ordenacoes[0].campo);
+        let (ordenacao, descendente) = resolver_ordenacao(ordenacoes, ordenar)?;
+
+        let direcao = if descendente { "DESC" } else { "ASC" };
+        let sql = format!(
+            "SELECT {select} FROM {from} {where_} ORDER BY {ordem} {direcao}, {id} {direcao}",
+            select = self.select,
+            from = self.from,
+            where_ = self.clausula_where(),
+            ordem = ordenacao.expressao,
+            id = self.id,
+            direcao = direcao
+        );
+
+        Ok(transmitir(pool, sql, self.argumentos()))
+    }
+}
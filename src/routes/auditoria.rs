@@ -0,0 +1,70 @@
+use serde::{Serialize, Deserialize};
+use actix_web::{web, HttpResponse};
+use chrono::{DateTime, Utc};
+use serde_json::Value as JsonValue;
+use sqlx::{PgPool, types::Uuid};
+use super::serializers::my_uuid;
+use crate::domain::{EntidadeAuditada, OperacaoAuditada};
+use crate::error::ApiError;
+
+#[derive(Serialize, Deserialize)]
+pub struct RegistroAuditoria {
+    pub id: i64,
+    /// Ausente nas escritas feitas fora da API
+    pub usuario_id: Option<Uuid>,
+    pub rota: Option<String>,
+    pub entidade: EntidadeAuditada,
+    #[serde(with = "my_uuid")]
+    pub entidade_id: Uuid,
+    pub operacao: OperacaoAuditada,
+    /// Na atualização, só as colunas alteradas
+    pub antes: Option<JsonValue>,
+    pub depois: Option<JsonValue>,
+    pub ocorrido_em: DateTime<Utc>
+}
+
+#[derive(Deserialize, Debug)]
+pub struct FiltroAuditoria {
+    pub entidade: Option<EntidadeAuditada>,
+    pub id: Option<Uuid>
+}
+
+#[tracing::instrument(name = "Consultar auditoria", skip(pool))]
+pub async fn get_auditoria(
+    filtro: web::Query<FiltroAuditoria>,
+    pool: web::Data<PgPool>
+) -> Result<HttpResponse, ApiError> {
+
+    let rows = sqlx::query!(
+        r#"
+        SELECT id, usuario_id, rota, entidade as "entidade: EntidadeAuditada", entidade_id,
+               operacao as "operacao: OperacaoAuditada", antes, depois, ocorrido_em
+        FROM auditoria
+        WHERE ($1::entidade_auditada IS NULL OR entidade = $1)
+          AND ($2::uuid IS NULL OR entidade_id = $2)
+        ORDER BY id
+        "#,
+        filtro.entidade as Option<EntidadeAuditada>,
+        filtro.id
+    )
+    .fetch_all(pool.get_ref())
+    .await?;
+
+    let mut registros: Vec<RegistroAuditoria> = Vec::new();
+    for row in rows {
+        let registro = RegistroAuditoria {
+            id: row.id,
+            usuario_id: row.usuario_id,
+            rota: row.rota,
+            entidade: row.entidade,
+            entidade_id: row.entidade_id,
+            operacao: row.operacao,
+            antes: row.antes,
+            depois: row.depois,
+            ocorrido_em: row.ocorrido_em
+        };
+        registros.push(registro);
+    }
+
+    Ok(HttpResponse::Ok().json(registros))
+}
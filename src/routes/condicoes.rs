@@ -0,0 +1,381 @@
+use serde::{Serialize, Deserialize};
+use actix_web::{web, HttpResponse};
+use chrono::{DateTime, Utc};
+use sqlx::{Executor, PgPool, Postgres, Transaction, types::Uuid};
+use super::serializers::my_uuid;
+use crate::audit::Auditoria;
+use crate::error::{ApiError, FieldError};
+use crate::validation::{Validacao, Validar};
+
+/// Agravo do catálogo de notificação, indicando se o paciente precisa de leito de isolamento.
+#[derive(Serialize, Deserialize)]
+pub struct CondicaoNotificavel {
+    #[serde(with = "my_uuid")]
+    pub id: Uuid,
+    pub nome: String,
+    pub exige_isolamento: bool
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct CondicaoData {
+    pub nome: String,
+    #[serde(default)]
+    pub exige_isolamento: bool
+}
+
+impl Validar for CondicaoData {
+    fn regras(&self, validacao: &mut Validacao) {
+        validacao.texto("nome", &self.nome);
+    }
+}
+
+impl Validar for CondicaoNotificavel {
+    fn regras(&self, validacao: &mut Validacao) {
+        validacao.texto("nome", &self.nome);
+    }
+}
+
+/// Condição registrada para um paciente.
+#[derive(Serialize, Deserialize)]
+pub struct CondicaoPaciente {
+    #[serde(with = "my_uuid")]
+    pub condicao_id: Uuid,
+    pub nome: String,
+    pub exige_isolamento: bool,
+    pub registrado_em: DateTime<Utc>
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct VinculoCondicaoData {
+    #[serde(with = "my_uuid")]
+    pub condicao_id: Uuid
+}
+
+/// Parâmetros de `DELETE /pacientes/{id}/condicoes/{condicao_id}`.
+#[derive(Debug, Deserialize)]
+pub struct CondicaoDoPaciente {
+    pub id: Uuid,
+    pub condicao_id: Uuid
+}
+
+/// Condições do paciente, da mais antiga à mais recente.
+pub(crate) async fn listar_condicoes_paciente<'c, E>(
+    executor: E,
+    paciente_id: Uuid
+) -> Result<Vec<CondicaoPaciente>, ApiError>
+where
+    E: Executor<'c, Database = Postgres>,
+{
+    let rows = sqlx::query!(
+        r#"
+        SELECT C.id, C.nome, C.exige_isolamento, PC.registrado_em
+        FROM paciente_condicao as PC JOIN condicao_notificavel as C ON C.id = PC.condicao_id
+        WHERE PC.paciente_id = $1
+        ORDER BY PC.registrado_em, C.nome
+        "#,
+        paciente_id
+    )
+    .fetch_all(executor)
+    .await?;
+
+    let condicoes = rows
+        .into_iter()
+        .map(|row| CondicaoPaciente {
+            condicao_id: row.id,
+            nome: row.nome,
+            exige_isolamento: row.exige_isolamento,
+            registrado_em: row.registrado_em
+        })
+        .collect();
+
+    Ok(condicoes)
+}
+
+/// Recusa a internação de paciente com condição que exige isolamento em leito que não o
+/// permite (sem pressão negativa e fora do tipo isolamento respiratório).
+pub(crate) async fn exigir_leito_de_isolamento(
+    transaction: &mut Transaction<'_, Postgres>,
+    paciente_id: Uuid,
+    leito_id: Uuid,
+    campo: &str
+) -> Result<(), ApiError> {
+
+    let row = sqlx::query!(
+        r#"
+        SELECT permite_isolamento(L.tipo, L.isolamento_pressao_negativa) as "permite!",
+               (SELECT string_agg(C.nome, ', ' ORDER BY C.nome)
+                FROM paciente_condicao as PC JOIN condicao_notificavel as C ON C.id = PC.condicao_id
+                WHERE PC.paciente_id = $1 AND C.exige_isolamento) as condicoes
+        FROM leito as L
+        WHERE L.id = $2
+        "#,
+        paciente_id,
+        leito_id
+    )
+    .fetch_optional(&mut *transaction)
+    .await?;
+
+    match row {
+        Some(row) if !row.permite => match row.condicoes {
+            Some(condicoes) => Err(ApiError::Conflict {
+                message: format!("Paciente com {} exige leito de isolamento", condicoes),
+                fields: vec![FieldError::new(campo, "leito sem isolamento")],
+            }),
+            None => Ok(()),
+        },
+        _ => Ok(()),
+    }
+}
+
+async fn buscar_paciente(pool: &PgPool, paciente_id: Uuid) -> Result<(), ApiError> {
+    sqlx::query!("SELECT id FROM paciente WHERE id = $1", paciente_id)
+        .fetch_optional(pool)
+        .await?
+        .ok_or_else(|| ApiError::NotFound(format!("Paciente {} não encontrado", paciente_id)))?;
+    Ok(())
+}
+
+#[tracing::instrument(name = "Cadastrar condição notificável", skip(condicao, pool))]
+pub async fn create_condicao(
+    condicao: web::Json<CondicaoData>,
+    pool: web::Data<PgPool>
+) -> Result<HttpResponse, ApiError> {
+
+    condicao.validar()?;
+
+    let row = sqlx::query!(
+        r#"
+        INSERT INTO condicao_notificavel (id, nome, exige_isolamento)
+        VALUES ($1, $2, $3)
+        RETURNING id, nome, exige_isolamento
+        "#,
+        Uuid::new_v4(),
+        condicao.nome.trim(),
+        condicao.exige_isolamento
+    )
+    .fetch_one(pool.get_ref())
+    .await?;
+
+    let condicao = CondicaoNotificavel {
+        id: row.id,
+        nome: row.nome,
+        exige_isolamento: row.exige_isolamento
+    };
+
+    Ok(HttpResponse::Ok().json(&condicao))
+}
+
+#[tracing::instrument(name = "Listar condições notificáveis", skip(pool))]
+pub async fn get_condicoes(
+    pool: web::Data<PgPool>
+) -> Result<HttpResponse, ApiError> {
+
+    let rows = sqlx::query!(
+        r#"
+        SELECT id, nome, exige_isolamento
+        FROM condicao_notificavel
+        ORDER BY nome
+        "#
+    )
+    .fetch_all(pool.get_ref())
+    .await?;
+
+    let condicoes: Vec<CondicaoNotificavel> = rows
+        .into_iter()
+        .map(|row| CondicaoNotificavel {
+            id: row.id,
+            nome: row.nome,
+            exige_isolamento: row.exige_isolamento
+        })
+        .collect();
+
+    Ok(HttpResponse::Ok().json(condicoes))
+}
+
+/// Passar a exigir isolamento não afeta internações já abertas, só as próximas.
+#[tracing::instrument(name = "Atualizar condição notificável", skip(condicao, pool))]
+pub async fn update_condicao(
+    condicao: web::Json<CondicaoNotificavel>,
+    pool: web::Data<PgPool>
+) -> Result<HttpResponse, ApiError> {
+
+    condicao.validar()?;
+
+    let result = sqlx::query!(
+        r#"
+        UPDATE condicao_notificavel
+        SET nome = $1, exige_isolamento = $2
+        WHERE id = $3
+        "#,
+        condicao.nome.trim(),
+        condicao.exige_isolamento,
+        condicao.id
+    )
+    .execute(pool.get_ref())
+    .await?;
+
+    if result.rows_affected() == 0 {
+        return Err(ApiError::NotFound(format!("Condição {} não encontrada", condicao.id)));
+    }
+
+    Ok(HttpResponse::Ok().finish())
+}
+
+/// Condições ainda vinculadas a pacientes não podem ser removidas do catálogo.
+#[tracing::instrument(name = "Deletar condição notificável", skip(pool))]
+pub async fn delete_condicao(
+    id: web::Path<Uuid>,
+    pool: web::Data<PgPool>
+) -> Result<HttpResponse, ApiError> {
+
+    let id = id.into_inner();
+    let mut transaction = pool.begin().await?;
+
+    sqlx::query!("SELECT id FROM condicao_notificavel WHERE id = $1 FOR UPDATE", id)
+        .fetch_optional(&mut transaction)
+        .await?
+        .ok_or_else(|| ApiError::NotFound(format!("Condição {} não encontrada", id)))?;
+
+    let pacientes = sqlx::query_scalar!(
+        r#"SELECT count(*) as "total!" FROM paciente_condicao WHERE condicao_id = $1"#,
+        id
+    )
+    .fetch_one(&mut transaction)
+    .await?;
+
+    if pacientes > 0 {
+        return Err(ApiError::Conflict {
+            message: format!("Condição {} está registrada para {} paciente(s)", id, pacientes),
+            fields: Vec::new(),
+        });
+    }
+
+    sqlx::query!("DELETE FROM condicao_notificavel WHERE id = $1", id)
+        .execute(&mut transaction)
+        .await?;
+
+    transaction.commit().await?;
+
+    Ok(HttpResponse::Ok().finish())
+}
+
+/// Uma condição que exige isolamento não pode ser registrada para quem já está internado
+/// em leito sem isolamento; o paciente precisa ser transferido antes.
+#[tracing::instrument(name = "Registrar condição do paciente", skip(vinculo, pool, auditoria))]
+pub async fn create_condicao_paciente(
+    id: web::Path<Uuid>,
+    vinculo: web::Json<VinculoCondicaoData>,
+    pool: web::Data<PgPool>,
+    auditoria: Auditoria,
+) -> Result<HttpResponse, ApiError> {
+
+    let paciente_id = id.into_inner();
+    let mut transaction = auditoria.begin(pool.get_ref()).await?;
+
+    sqlx::query!("SELECT id FROM paciente WHERE id = $1 FOR UPDATE", paciente_id)
+        .fetch_optional(&mut transaction)
+        .await?
+        .ok_or_else(|| ApiError::NotFound(format!("Paciente {} não encontrado", paciente_id)))?;
+
+    let condicao = sqlx::query!(
+        "SELECT nome, exige_isolamento FROM condicao_notificavel WHERE id = $1",
+        vinculo.condicao_id
+    )
+    .fetch_optional(&mut transaction)
+    .await?
+    .ok_or_else(|| ApiError::UnprocessableEntity {
+        message: format!("Condição {} não existe", vinculo.condicao_id),
+        fields: vec![FieldError::new("condicao_id", "referência inválida")],
+    })?;
+
+    if condicao.exige_isolamento {
+        let leito_atual = sqlx::query!(
+            r#"
+            SELECT L.id, permite_isolamento(L.tipo, L.isolamento_pressao_negativa) as "permite!"
+            FROM internacao as I JOIN leito as L ON L.id = I.leito_id
+            WHERE I.paciente_id = $1 AND I.alta_em IS NULL
+            "#,
+            paciente_id
+        )
+        .fetch_optional(&mut transaction)
+        .await?;
+
+        if let Some(leito) = leito_atual.filter(|leito| !leito.permite) {
+            return Err(ApiError::UnprocessableEntity {
+                message: format!(
+                    "{} exige isolamento e o paciente está internado no leito {}, sem isolamento",
+                    condicao.nome, leito.id
+                ),
+                fields: vec![FieldError::new("condicao_id", "paciente em leito sem isolamento")],
+            });
+        }
+    }
+
+    let inserida = sqlx::query!(
+        r#"
+        INSERT INTO paciente_condicao (paciente_id, condicao_id)
+        VALUES ($1, $2)
+        ON CONFLICT DO NOTHING
+        "#,
+        paciente_id,
+        vinculo.condicao_id
+    )
+    .execute(&mut transaction)
+    .await?;
+
+    if inserida.rows_affected() == 0 {
+        return Err(ApiError::Conflict {
+            message: format!("Condição {} já registrada para o paciente", vinculo.condicao_id),
+            fields: vec![FieldError::new("condicao_id", "já registrada")],
+        });
+    }
+
+    let condicoes = listar_condicoes_paciente(&mut transaction, paciente_id).await?;
+    transaction.commit().await?;
+
+    Ok(HttpResponse::Ok().json(condicoes))
+}
+
+#[tracing::instrument(name = "Listar condições do paciente", skip(pool))]
+pub async fn get_condicoes_paciente(
+    id: web::Path<Uuid>,
+    pool: web::Data<PgPool>
+) -> Result<HttpResponse, ApiError> {
+
+    let paciente_id = id.into_inner();
+    buscar_paciente(pool.get_ref(), paciente_id).await?;
+
+    let condicoes = listar_condicoes_paciente(pool.get_ref(), paciente_id).await?;
+
+    Ok(HttpResponse::Ok().json(condicoes))
+}
+
+#[tracing::instrument(name = "Remover condição do paciente", skip(pool, auditoria))]
+pub async fn delete_condicao_paciente(
+    caminho: web::Path<CondicaoDoPaciente>,
+    pool: web::Data<PgPool>,
+    auditoria: Auditoria,
+) -> Result<HttpResponse, ApiError> {
+
+    let caminho = caminho.into_inner();
+    let mut transaction = auditoria.begin(pool.get_ref()).await?;
+
+    let result = sqlx::query!(
+        "DELETE FROM paciente_condicao WHERE paciente_id = $1 AND condicao_id = $2",
+        caminho.id,
+        caminho.condicao_id
+    )
+    .execute(&mut transaction)
+    .await?;
+
+    if result.rows_affected() == 0 {
+        return Err(ApiError::NotFound(format!(
+            "Condição {} não registrada para o paciente {}",
+            caminho.condicao_id, caminho.id
+        )));
+    }
+
+    transaction.commit().await?;
+
+    Ok(HttpResponse::Ok().finish())
+}
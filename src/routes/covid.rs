@@ -0,0 +1,171 @@
+use serde::{Serialize, Deserialize};
+use actix_web::{web, HttpResponse};
+use chrono::{DateTime, NaiveDate, Utc};
+use sqlx::{Executor, PgPool, Postgres, Transaction, types::Uuid};
+use super::serializers::my_uuid;
+use crate::audit::Auditoria;
+use crate::error::ApiError;
+use crate::domain::{SituacaoCovid, TipoTesteCovid};
+use crate::validation::{Validacao, Validar};
+
+/// Registro da situação de COVID-19 de um paciente, com o teste que a embasou, se houver.
+#[derive(Serialize, Deserialize)]
+pub struct RegistroCovid {
+    #[serde(with = "my_uuid")]
+    pub id: Uuid,
+    #[serde(with = "my_uuid")]
+    pub paciente_id: Uuid,
+    pub situacao: SituacaoCovid,
+    pub tipo_teste: Option<TipoTesteCovid>,
+    pub coletado_em: Option<NaiveDate>,
+    pub resultado_em: Option<NaiveDate>,
+    pub inicio_sintomas_em: Option<NaiveDate>,
+    pub registrado_em: DateTime<Utc>
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct RegistroCovidData {
+    pub situacao: SituacaoCovid,
+    #[serde(default)]
+    pub tipo_teste: Option<TipoTesteCovid>,
+    #[serde(default)]
+    pub coletado_em: Option<NaiveDate>,
+    #[serde(default)]
+    pub resultado_em: Option<NaiveDate>,
+    #[serde(default)]
+    pub inicio_sintomas_em: Option<NaiveDate>
+}
+
+impl Validar for RegistroCovidData {
+    fn regras(&self, validacao: &mut Validacao) {
+        let hoje = Utc::today().naive_utc();
+        let teste_informado = self.coletado_em.is_some() || self.resultado_em.is_some();
+        validacao
+            .regra("tipo_teste", !teste_informado || self.tipo_teste.is_some(), "obrigatório quando há coleta ou resultado")
+            .regra("coletado_em", self.coletado_em.is_none_or(|data| data <= hoje), "data futura")
+            .regra("resultado_em", self.resultado_em.is_none_or(|data| data <= hoje), "data futura")
+            .regra("inicio_sintomas_em", self.inicio_sintomas_em.is_none_or(|data| data <= hoje), "data futura");
+        if let (Some(coleta), Some(resultado)) = (self.coletado_em, self.resultado_em) {
+            validacao.regra("resultado_em", resultado >= coleta, "anterior à coleta");
+        }
+    }
+}
+
+/// Acrescenta um registro ao histórico de COVID-19 do paciente; o mais recente passa a
+/// ser a situação atual.
+pub(crate) async fn registrar_covid(
+    transaction: &mut Transaction<'_, Postgres>,
+    paciente_id: Uuid,
+    registro: &RegistroCovidData
+) -> Result<RegistroCovid, ApiError> {
+
+    let row = sqlx::query!(
+        r#"
+        INSERT INTO registro_covid (id, paciente_id, situacao, tipo_teste, coletado_em, resultado_em, inicio_sintomas_em)
+        VALUES ($1, $2, $3, $4, $5, $6, $7)
+        RETURNING id, paciente_id, situacao as "situacao: SituacaoCovid", tipo_teste as "tipo_teste: TipoTesteCovid",
+                  coletado_em, resultado_em, inicio_sintomas_em, registrado_em
+        "#,
+        Uuid::new_v4(),
+        paciente_id,
+        registro.situacao as SituacaoCovid,
+        registro.tipo_teste as Option<TipoTesteCovid>,
+        registro.coletado_em,
+        registro.resultado_em,
+        registro.inicio_sintomas_em
+    )
+    .fetch_one(&mut *transaction)
+    .await?;
+
+    Ok(RegistroCovid {
+        id: row.id,
+        paciente_id: row.paciente_id,
+        situacao: row.situacao,
+        tipo_teste: row.tipo_teste,
+        coletado_em: row.coletado_em,
+        resultado_em: row.resultado_em,
+        inicio_sintomas_em: row.inicio_sintomas_em,
+        registrado_em: row.registrado_em
+    })
+}
+
+/// Histórico de COVID-19 do paciente, do registro mais antigo ao mais recente.
+pub(crate) async fn listar_registros_covid<'c, E>(
+    executor: E,
+    paciente_id: Uuid
+) -> Result<Vec<RegistroCovid>, ApiError>
+where
+    E: Executor<'c, Database = Postgres>,
+{
+    let rows = sqlx::query!(
+        r#"
+        SELECT id, paciente_id, situacao as "situacao: SituacaoCovid", tipo_teste as "tipo_teste: TipoTesteCovid",
+               coletado_em, resultado_em, inicio_sintomas_em, registrado_em
+        FROM registro_covid
+        WHERE paciente_id = $1
+        ORDER BY registrado_em, id
+        "#,
+        paciente_id
+    )
+    .fetch_all(executor)
+    .await?;
+
+    let registros = rows
+        .into_iter()
+        .map(|row| RegistroCovid {
+            id: row.id,
+            paciente_id: row.paciente_id,
+            situacao: row.situacao,
+            tipo_teste: row.tipo_teste,
+            coletado_em: row.coletado_em,
+            resultado_em: row.resultado_em,
+            inicio_sintomas_em: row.inicio_sintomas_em,
+            registrado_em: row.registrado_em
+        })
+        .collect();
+
+    Ok(registros)
+}
+
+#[tracing::instrument(name = "Registrar situação de covid", skip(registro, pool, auditoria))]
+pub async fn create_registro_covid(
+    id: web::Path<Uuid>,
+    registro: web::Json<RegistroCovidData>,
+    pool: web::Data<PgPool>,
+    auditoria: Auditoria,
+) -> Result<HttpResponse, ApiError> {
+
+    registro.validar()?;
+
+    let paciente_id = id.into_inner();
+    let mut transaction = auditoria.begin(pool.get_ref()).await?;
+
+    sqlx::query!("SELECT id FROM paciente WHERE id = $1 FOR UPDATE", paciente_id)
+        .fetch_optional(&mut transaction)
+        .await?
+        .ok_or_else(|| ApiError::NotFound(format!("Paciente {} não encontrado", paciente_id)))?;
+
+    let registro = registrar_covid(&mut transaction, paciente_id, &registro).await?;
+
+    transaction.commit().await?;
+
+    Ok(HttpResponse::Ok().json(&registro))
+}
+
+#[tracing::instrument(name = "Listar registros de covid do paciente", skip(pool))]
+pub async fn get_registros_covid(
+    id: web::Path<Uuid>,
+    pool: web::Data<PgPool>
+) -> Result<HttpResponse, ApiError> {
+
+    let paciente_id = id.into_inner();
+
+    sqlx::query!("SELECT id FROM paciente WHERE id = $1", paciente_id)
+        .fetch_optional(pool.get_ref())
+        .await?
+        .ok_or_else(|| ApiError::NotFound(format!("Paciente {} não encontrado", paciente_id)))?;
+
+    let registros = listar_registros_covid(pool.get_ref(), paciente_id).await?;
+
+    Ok(HttpResponse::Ok().json(registros))
+}
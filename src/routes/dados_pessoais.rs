@@ -0,0 +1,268 @@
+use serde::{Serialize, Deserialize};
+use actix_web::{web, HttpResponse};
+use chrono::{DateTime, Utc};
+use sqlx::{PgPool, types::Uuid};
+use crate::audit::Auditoria;
+use crate::error::ApiError;
+use crate::domain::{EntidadeAuditada, MotivoAlta, OperacaoAuditada, SituacaoCovid, SituacaoTransferencia, TipoLeito};
+use super::condicoes::listar_condicoes_paciente;
+use super::covid::listar_registros_covid;
+use super::{CondicaoPaciente, EntradaFila, Internacao, Paciente, RegistroAuditoria, RegistroCovid, Transferencia};
+
+/// Tudo o que o sistema guarda sobre um paciente, para atender pedidos de acesso da LGPD.
+#[derive(Serialize, Deserialize)]
+pub struct DadosPessoais {
+    pub paciente: Paciente,
+    pub anonimizado_em: Option<DateTime<Utc>>,
+    pub internacoes: Vec<Internacao>,
+    pub registros_covid: Vec<RegistroCovid>,
+    pub condicoes: Vec<CondicaoPaciente>,
+    pub transferencias: Vec<Transferencia>,
+    pub fila_regulacao: Vec<EntradaFila>,
+    pub auditoria: Vec<RegistroAuditoria>,
+    pub exportado_em: DateTime<Utc>
+}
+
+#[tracing::instrument(name = "Exportar dados pessoais do paciente", skip(pool))]
+pub async fn get_dados_pessoais(
+    id: web::Path<Uuid>,
+    pool: web::Data<PgPool>
+) -> Result<HttpResponse, ApiError> {
+
+    let id = id.into_inner();
+
+    // uma transação só, para que a exportação seja uma fotografia consistente
+    let mut transaction = pool.begin().await?;
+    sqlx::query!("SET TRANSACTION ISOLATION LEVEL REPEATABLE READ")
+        .execute(&mut transaction)
+        .await?;
+
+    let row = sqlx::query!(
+        r#"
+        SELECT P.id, P.nome, P.sexo, P.idade, P.email, P.telefone, CV.situacao as "situacao_covid?: SituacaoCovid",
+               P.anonimizado_em, I.leito_id as "leito_id?"
+        FROM paciente as P LEFT JOIN internacao as I ON I.paciente_id = P.id AND I.alta_em IS NULL
+        LEFT JOIN situacao_covid_atual as CV ON CV.paciente_id = P.id
+        WHERE P.id = $1
+        "#,
+        id
+    )
+    .fetch_optional(&mut transaction)
+    .await?
+    .ok_or_else(|| ApiError::NotFound(format!("Paciente {} não encontrado", id)))?;
+
+    let paciente = Paciente {
+        id: row.id,
+        nome: row.nome,
+        sexo: row.sexo,
+        idade: row.idade,
+        email: row.email,
+        telefone: row.telefone,
+        situacao_covid: row.situacao_covid,
+        leito_id: row.leito_id
+    };
+
+    let internacoes = sqlx::query!(
+        r#"
+        SELECT id, paciente_id, leito_id, admitido_em, alta_em, motivo_alta as "motivo_alta: MotivoAlta"
+        FROM internacao
+        WHERE paciente_id = $1
+        ORDER BY admitido_em
+        "#,
+        id
+    )
+    .fetch_all(&mut transaction)
+    .await?
+    .into_iter()
+    .map(|row| Internacao {
+        id: row.id,
+        paciente_id: row.paciente_id,
+        leito_id: row.leito_id,
+        admitido_em: row.admitido_em,
+        alta_em: row.alta_em,
+        motivo_alta: row.motivo_alta
+    })
+    .collect();
+
+    let transferencias = sqlx::query!(
+        r#"
+        SELECT id, paciente_id, internacao_origem_id, leito_origem_id, unidade_destino_id,
+               leito_destino_id, internacao_destino_id, situacao as "situacao: SituacaoTransferencia",
+               solicitado_por, aceito_por, motivo_recusa, solicitada_em, aceita_em, iniciada_em, finalizada_em
+        FROM transferencia
+        WHERE paciente_id = $1
+        ORDER BY solicitada_em
+        "#,
+        id
+    )
+    .fetch_all(&mut transaction)
+    .await?
+    .into_iter()
+    .map(|row| Transferencia {
+        id: row.id,
+        paciente_id: row.paciente_id,
+        internacao_origem_id: row.internacao_origem_id,
+        leito_origem_id: row.leito_origem_id,
+        unidade_destino_id: row.unidade_destino_id,
+        leito_destino_id: row.leito_destino_id,
+        internacao_destino_id: row.internacao_destino_id,
+        situacao: row.situacao,
+        solicitado_por: row.solicitado_por,
+        aceito_por: row.aceito_por,
+        motivo_recusa: row.motivo_recusa,
+        solicitada_em: row.solicitada_em,
+        aceita_em: row.aceita_em,
+        iniciada_em: row.iniciada_em,
+        finalizada_em: row.finalizada_em
+    })
+    .collect();
+
+    let fila_regulacao = sqlx::query!(
+        r#"
+        SELECT id, paciente_id, prioridade, tipo_leito as "tipo_leito: TipoLeito", municipio_origem,
+               entrada_em, atendida_em, internacao_id,
+               (EXTRACT(EPOCH FROM COALESCE(atendida_em, now()) - entrada_em) / 60)::bigint as "espera_minutos!"
+        FROM fila_regulacao
+        WHERE paciente_id = $1
+        ORDER BY entrada_em
+        "#,
+        id
+    )
+    .fetch_all(&mut transaction)
+    .await?
+    .into_iter()
+    .map(|row| EntradaFila {
+        id: row.id,
+        paciente_id: row.paciente_id,
+        prioridade: row.prioridade,
+        tipo_leito: row.tipo_leito,
+        municipio_origem: row.municipio_origem,
+        entrada_em: row.entrada_em,
+        espera_minutos: row.espera_minutos,
+        atendida_em: row.atendida_em,
+        internacao_id: row.internacao_id
+    })
+    .collect();
+
+    let registros_covid = listar_registros_covid(&mut transaction, id).await?;
+    let condicoes = listar_condicoes_paciente(&mut transaction, id).await?;
+
+    let auditoria = sqlx::query!(
+        r#"
+        SELECT id, usuario_id, rota, entidade as "entidade: EntidadeAuditada", entidade_id,
+               operacao as "operacao: OperacaoAuditada", antes, depois, ocorrido_em
+        FROM auditoria
+        WHERE entidade = 'paciente' AND entidade_id = $1
+        ORDER BY id
+        "#,
+        id
+    )
+    .fetch_all(&mut transaction)
+    .await?
+    .into_iter()
+    .map(|row| RegistroAuditoria {
+        id: row.id,
+        usuario_id: row.usuario_id,
+        rota: row.rota,
+        entidade: row.entidade,
+        entidade_id: row.entidade_id,
+        operacao: row.operacao,
+        antes: row.antes,
+        depois: row.depois,
+        ocorrido_em: row.ocorrido_em
+    })
+    .collect();
+
+    transaction.commit().await?;
+
+    let dados = DadosPessoais {
+        paciente,
+        anonimizado_em: row.anonimizado_em,
+        internacoes,
+        registros_covid,
+        condicoes,
+        transferencias,
+        fila_regulacao,
+        auditoria,
+        exportado_em: Utc::now()
+    };
+
+    Ok(HttpResponse::Ok().json(&dados))
+}
+
+/// Apaga de forma irreversível nome, email e telefone do paciente, inclusive dos registros
+/// de auditoria. Sexo, idade, covid e internações ficam, para não distorcer os relatórios.
+#[tracing::instrument(name = "Anonimizar paciente", skip(pool, auditoria))]
+pub async fn anonimizar_paciente(
+    id: web::Path<Uuid>,
+    pool: web::Data<PgPool>,
+    auditoria: Auditoria
+) -> Result<HttpResponse, ApiError> {
+
+    let id = id.into_inner();
+    let mut transaction = auditoria.begin(pool.get_ref()).await?;
+
+    let atual = sqlx::query!(
+        "SELECT anonimizado_em FROM paciente WHERE id = $1 FOR UPDATE",
+        id
+    )
+    .fetch_optional(&mut transaction)
+    .await?
+    .ok_or_else(|| ApiError::NotFound(format!("Paciente {} não encontrado", id)))?;
+
+    if let Some(anonimizado_em) = atual.anonimizado_em {
+        return Err(ApiError::Conflict {
+            message: format!("Paciente {} já foi anonimizado em {}", id, anonimizado_em),
+            fields: Vec::new(),
+        });
+    }
+
+    let row = sqlx::query!(
+        r#"
+        UPDATE paciente as P
+        SET nome = 'anonimizado', email = 'anonimizado', telefone = 'anonimizado', anonimizado_em = now()
+        WHERE P.id = $1
+        RETURNING P.id, P.nome, P.sexo, P.idade, P.email, P.telefone,
+                  (SELECT situacao FROM situacao_covid_atual WHERE paciente_id = P.id) as "situacao_covid: SituacaoCovid",
+                  (SELECT leito_id FROM internacao WHERE paciente_id = P.id AND alta_em IS NULL) as leito_id
+        "#,
+        id
+    )
+    .fetch_one(&mut transaction)
+    .await?;
+
+    // o trigger de imutabilidade só aceita esta alteração com a autorização da transação;
+    // o registro da própria anonimização, gravado acima, também é limpo aqui
+    sqlx::query!(
+        "SELECT set_config('auditoria.anonimizar_paciente', $1, true) as anonimizar_paciente",
+        id.to_string()
+    )
+    .fetch_one(&mut transaction)
+    .await?;
+
+    sqlx::query!(
+        r#"
+        UPDATE auditoria
+        SET antes = anonimizar_dados_pessoais(antes), depois = anonimizar_dados_pessoais(depois)
+        WHERE entidade = 'paciente' AND entidade_id = $1
+        "#,
+        id
+    )
+    .execute(&mut transaction)
+    .await?;
+
+    transaction.commit().await?;
+
+    let paciente = Paciente {
+        id: row.id,
+        nome: row.nome,
+        sexo: row.sexo,
+        idade: row.idade,
+        email: row.email,
+        telefone: row.telefone,
+        situacao_covid: row.situacao_covid,
+        leito_id: row.leito_id
+    };
+
+    Ok(HttpResponse::Ok().json(&paciente))
+}
@@ -0,0 +1,259 @@
+use serde::{Serialize, Deserialize};
+use actix_web::{web, HttpResponse};
+use chrono::{DateTime, Utc};
+use sqlx::{PgPool, Postgres, Transaction, types::Uuid};
+use super::serializers::my_uuid;
+use crate::audit::Auditoria;
+use super::internacoes::internar;
+use crate::error::{ApiError, FieldError};
+use crate::domain::TipoLeito;
+use crate::query::{Listagem, Ordenacao, Pagina, Paginacao};
+use crate::validation::{Validacao, Validar};
+
+#[derive(Serialize, Deserialize, sqlx::FromRow)]
+pub struct EntradaFila {
+    #[serde(with = "my_uuid")]
+    pub id: Uuid,
+    #[serde(with = "my_uuid")]
+    pub paciente_id: Uuid,
+    pub prioridade: i16,
+    pub tipo_leito: TipoLeito,
+    pub municipio_origem: String,
+    pub entrada_em: DateTime<Utc>,
+    /// Tempo de espera até o atendimento, ou até agora se ainda aguarda
+    pub espera_minutos: i64,
+    pub atendida_em: Option<DateTime<Utc>>,
+    pub internacao_id: Option<Uuid>
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct EntradaFilaData {
+    #[serde(with = "my_uuid")]
+    pub paciente_id: Uuid,
+    /// Escore de gravidade, de 1 (menos grave) a 5 (mais grave)
+    pub prioridade: i16,
+    pub tipo_leito: TipoLeito,
+    pub municipio_origem: String
+}
+
+impl Validar for EntradaFilaData {
+    fn regras(&self, validacao: &mut Validacao) {
+        validacao
+            .regra("prioridade", (1..=5).contains(&self.prioridade), "fora do intervalo 1-5")
+            .texto("municipio_origem", &self.municipio_origem);
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct PropostaLeito {
+    #[serde(with = "my_uuid")]
+    pub leito_id: Uuid,
+    pub tipo: TipoLeito,
+    #[serde(with = "my_uuid")]
+    pub unidade_id: Uuid,
+    pub unidade_nome: String,
+    pub municipio: String,
+    pub mesmo_municipio: bool,
+    pub leitos_livres_na_unidade: i64
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct AtendimentoData {
+    #[serde(with = "my_uuid")]
+    pub leito_id: Uuid
+}
+
+async fn buscar_entrada(
+    transaction: &mut Transaction<'_, Postgres>,
+    id: Uuid
+) -> Result<EntradaFila, ApiError> {
+
+    let row = sqlx::query!(
+        r#"
+        SELECT id, paciente_id, prioridade, tipo_leito as "tipo_leito: TipoLeito", municipio_origem,
+               entrada_em, atendida_em, internacao_id,
+               (EXTRACT(EPOCH FROM COALESCE(atendida_em, now()) - entrada_em) / 60)::bigint as "espera_minutos!"
+        FROM fila_regulacao
+        WHERE id = $1
+        FOR UPDATE
+        "#,
+        id
+    )
+    .fetch_optional(&mut *transaction)
+    .await?
+    .ok_or_else(|| ApiError::NotFound(format!("Entrada {} não encontrada na fila", id)))?;
+
+    Ok(EntradaFila {
+        id: row.id,
+        paciente_id: row.paciente_id,
+        prioridade: row.prioridade,
+        tipo_leito: row.tipo_leito,
+        municipio_origem: row.municipio_origem,
+        entrada_em: row.entrada_em,
+        espera_minutos: row.espera_minutos,
+        atendida_em: row.atendida_em,
+        internacao_id: row.internacao_id
+    })
+}
+
+#[tracing::instrument(name = "Incluir paciente na fila de regulação", skip(entrada, pool))]
+pub async fn create_entrada_fila(
+    entrada: web::Json<EntradaFilaData>,
+    pool: web::Data<PgPool>
+) -> Result<HttpResponse, ApiError> {
+
+    entrada.validar()?;
+
+    let mut transaction = pool.begin().await?;
+
+    let internado = sqlx::query!(
+        "SELECT id FROM internacao WHERE paciente_id = $1 AND alta_em IS NULL",
+        entrada.paciente_id
+    )
+    .fetch_optional(&mut transaction)
+    .await?;
+
+    if internado.is_some() {
+        return Err(ApiError::Conflict {
+            message: format!("Paciente {} já está internado", entrada.paciente_id),
+            fields: vec![FieldError::new("paciente_id", "internação em aberto")],
+        });
+    }
+
+    let row = sqlx::query!(
+        r#"
+        INSERT INTO fila_regulacao (id, paciente_id, prioridade, tipo_leito, municipio_origem)
+        VALUES ($1, $2, $3, $4, $5)
+        RETURNING id
+        "#,
+        Uuid::new_v4(),
+        entrada.paciente_id,
+        entrada.prioridade,
+        entrada.tipo_leito as TipoLeito,
+        entrada.municipio_origem.trim()
+    )
+    .fetch_one(&mut transaction)
+    .await?;
+
+    let entrada = buscar_entrada(&mut transaction, row.id).await?;
+    transaction.commit().await?;
+
+    Ok(HttpResponse::Ok().json(&entrada))
+}
+
+// A fila anda por prioridade decrescente e, na mesma prioridade, por ordem de chegada;
+// as duas entram numa chave só para que o cursor guarde a posição inteira
+const ORDENACOES_FILA: &[Ordenacao] = &[
+    Ordenacao {
+        campo: "prioridade",
+        expressao: "(5 - prioridade)::text || to_char(entrada_em AT TIME ZONE 'UTC', 'YYYYMMDDHH24MISSUS')",
+        tipo: "text",
+    },
+    Ordenacao { campo: "entrada_em", expressao: "entrada_em", tipo: "timestamptz" },
+];
+
+/// Pacientes aguardando leito, dos mais graves para os menos graves e,
+/// dentro da mesma prioridade, por ordem de chegada.
+#[tracing::instrument(name = "Listar fila de regulação", skip(pool))]
+pub async fn get_fila(
+    paginacao: web::Query<Paginacao>,
+    pool: web::Data<PgPool>
+) -> Result<HttpResponse, ApiError> {
+
+    let pagina: Pagina<EntradaFila> = Listagem::new(
+        "id, paciente_id, prioridade, tipo_leito, municipio_origem, entrada_em, atendida_em, internacao_id, \
+         (EXTRACT(EPOCH FROM now() - entrada_em) / 60)::bigint as espera_minutos",
+        "fila_regulacao",
+        "id"
+    )
+    .condicao("atendida_em IS NULL")
+    .paginar(pool.get_ref(), ORDENACOES_FILA, &paginacao)
+    .await?;
+
+    Ok(HttpResponse::Ok().json(pagina))
+}
+
+/// Propõe o leito livre mais adequado para a entrada da fila: mesmo tipo de leito, com
+/// isolamento quando alguma condição do paciente o exige, preferindo unidades do município
+/// de origem e, entre elas, as com mais leitos livres.
+#[tracing::instrument(name = "Propor leito para entrada da fila", skip(pool))]
+pub async fn get_proposta_leito(
+    id: web::Path<Uuid>,
+    pool: web::Data<PgPool>
+) -> Result<HttpResponse, ApiError> {
+
+    let id = id.into_inner();
+    let mut transaction = pool.begin().await?;
+    let entrada = buscar_entrada(&mut transaction, id).await?;
+    transaction.commit().await?;
+
+    if entrada.atendida_em.is_some() {
+        return Err(ApiError::Conflict {
+            message: format!("Entrada {} já foi atendida", id),
+            fields: Vec::new(),
+        });
+    }
+
+    let row = sqlx::query!(
+        r#"
+        SELECT L.id, L.tipo as "tipo: TipoLeito", U.id as unidade_id, U.nome, U.municipio,
+               lower(U.municipio) = lower($2) as "mesmo_municipio!",
+               COUNT(*) OVER (PARTITION BY U.id) as "leitos_livres!"
+        FROM leito as L JOIN unidadeSaude as U ON U.id = L.unidade_id
+        WHERE L.situacao = 'livre' AND L.tipo = $1 AND L.desativado_em IS NULL
+          AND (permite_isolamento(L.tipo, L.isolamento_pressao_negativa) OR NOT EXISTS (
+              SELECT 1
+              FROM paciente_condicao as PC JOIN condicao_notificavel as C ON C.id = PC.condicao_id
+              WHERE PC.paciente_id = $3 AND C.exige_isolamento
+          ))
+        ORDER BY 6 DESC, 7 DESC, L.id
+        LIMIT 1
+        "#,
+        entrada.tipo_leito as TipoLeito,
+        entrada.municipio_origem,
+        entrada.paciente_id
+    )
+    .fetch_optional(pool.get_ref())
+    .await?
+    .ok_or_else(|| ApiError::NotFound(format!("Nenhum leito livre do tipo {}", entrada.tipo_leito)))?;
+
+    let proposta = PropostaLeito {
+        leito_id: row.id,
+        tipo: row.tipo,
+        unidade_id: row.unidade_id,
+        unidade_nome: row.nome,
+        municipio: row.municipio,
+        mesmo_municipio: row.mesmo_municipio,
+        leitos_livres_na_unidade: row.leitos_livres
+    };
+
+    Ok(HttpResponse::Ok().json(&proposta))
+}
+
+/// Interna o paciente da entrada no leito escolhido, retirando-o da fila.
+#[tracing::instrument(name = "Atender entrada da fila", skip(atendimento, pool, auditoria))]
+pub async fn atender_entrada_fila(
+    id: web::Path<Uuid>,
+    atendimento: web::Json<AtendimentoData>,
+    pool: web::Data<PgPool>,
+    auditoria: Auditoria
+) -> Result<HttpResponse, ApiError> {
+
+    let id = id.into_inner();
+    let mut transaction = auditoria.begin(pool.get_ref()).await?;
+
+    let entrada = buscar_entrada(&mut transaction, id).await?;
+    if entrada.atendida_em.is_some() {
+        return Err(ApiError::Conflict {
+            message: format!("Entrada {} já foi atendida", id),
+            fields: Vec::new(),
+        });
+    }
+
+    internar(&mut transaction, entrada.paciente_id, atendimento.leito_id, None).await?;
+
+    let entrada = buscar_entrada(&mut transaction, id).await?;
+    transaction.commit().await?;
+
+    Ok(HttpResponse::Ok().json(&entrada))
+}
@@ -1,20 +1,49 @@
 use serde::{Serialize, Deserialize};
 use actix_web::{web, HttpResponse};
-use sqlx::{PgPool, types::Uuid};
+use chrono::{DateTime, Utc};
+use sqlx::{PgPool, Postgres, Transaction, types::Uuid};
 use super::serializers::my_uuid;
+use super::{Ocupacao, Paciente};
+use crate::audit::Auditoria;
+use crate::domain::SituacaoCovid;
+use crate::authentication::UsuarioAutenticado;
+use crate::concurrency::{com_etag, PreCondicao, Versionado};
+use crate::error::{ApiError, FieldError};
+use crate::patch::mesclar;
+use crate::query::{Linhas, Listagem, Ordenacao, Pagina, Paginacao};
+use crate::spreadsheet::{Celula, Exportavel, Formato};
+use crate::validation::{Validacao, Validar};
 
 //#[derive(serde::Deserialize)]
-#[derive(Serialize, Deserialize)]
+#[derive(Serialize, Deserialize, sqlx::FromRow)]
 pub struct UnidadeSaude {
     #[serde(with = "my_uuid")]
     pub id: Uuid,
     pub email: String,
     pub nome: String,
     pub tipo: String,
-    pub municipio: String
+    pub municipio: String,
+    /// Preenchido quando a unidade foi removida mas continua referenciada pelo histórico
+    #[serde(default)]
+    pub desativado_em: Option<DateTime<Utc>>
+}
+
+impl Exportavel for UnidadeSaude {
+    const COLUNAS: &'static [&'static str] = &["id", "nome", "email", "tipo", "municipio", "desativado_em"];
+
+    fn celulas(self) -> Vec<Celula> {
+        vec![
+            self.id.into(),
+            self.nome.into(),
+            self.email.into(),
+            self.tipo.into(),
+            self.municipio.into(),
+            self.desativado_em.into(),
+        ]
+    }
 }
 
-#[derive(Deserialize)]
+#[derive(Serialize, Deserialize)]
 pub struct UnidadeData {
     pub email: String,
     pub nome: String,
@@ -22,205 +51,470 @@ pub struct UnidadeData {
     pub municipio: String
 }
 
+impl Validar for UnidadeData {
+    fn regras(&self, validacao: &mut Validacao) {
+        validacao
+            .texto("nome", &self.nome)
+            .email("email", &self.email)
+            .texto("tipo", &self.tipo)
+            .texto("municipio", &self.municipio);
+    }
+}
+
+/// Filtros opcionais de `GET /unidades`, combinados com `AND`.
+#[derive(Debug, Default, Deserialize)]
+pub struct FiltroUnidades {
+    pub municipio: Option<String>,
+    pub tipo: Option<String>,
+    /// Inclui as unidades desativadas, que por padrão ficam de fora
+    #[serde(default)]
+    pub incluir_desativadas: bool,
+}
+
+/// Parâmetros de `DELETE /unidades/{id}`.
+#[derive(Debug, Default, Deserialize)]
+pub struct RemocaoUnidade {
+    /// Desativa junto os leitos ativos da unidade em vez de recusar a remoção
+    #[serde(default)]
+    pub cascata: bool,
+}
+
+/// Unidade com a contagem dos seus leitos e os pacientes internados nela.
+#[derive(Serialize, Deserialize)]
+pub struct ResumoUnidade {
+    pub unidade: UnidadeSaude,
+    pub ocupacao: Ocupacao,
+    pub pacientes: Vec<Paciente>
+}
+
 #[derive(Serialize, Deserialize)]
 pub struct UnidadeId {
     #[serde(with = "my_uuid")]
     pub id: Uuid,
 }
 
-#[tracing::instrument(name = "Criar unidade", skip(unidade_saude, pool))]
-pub async fn create_unidade(
-    unidade_saude: web::Json<UnidadeData>,
-    pool: web::Data<PgPool>, // Renamed!
-) -> Result<HttpResponse, HttpResponse> {
-    
+/// Grava uma nova unidade na transação auditada.
+pub(crate) async fn inserir_unidade(
+    transaction: &mut Transaction<'_, Postgres>,
+    unidade: &UnidadeData
+) -> Result<UnidadeSaude, ApiError> {
+
     let row = sqlx::query!(
         r#"
         INSERT INTO unidadeSaude (id, email, nome, tipo, municipio)
         VALUES ($1, $2, $3, $4, $5)
-        RETURNING id
+        RETURNING id, email, nome, tipo, municipio, desativado_em
         "#,
         Uuid::new_v4(),
-        unidade_saude.email,
-        unidade_saude.nome,
-        unidade_saude.tipo,
-        unidade_saude.municipio,
+        unidade.email,
+        unidade.nome,
+        unidade.tipo,
+        unidade.municipio,
     )
-    // We got rid of the double-wrapping using .app_data()
-    .fetch_one(pool.get_ref())
-    .await
-    .map_err(|e| {
-        eprintln!("Failed to execute query: {}", e);
-        HttpResponse::InternalServerError().finish()
-    })?;
-
-    // let unidade = UnidadeSaude {
-    //     id: row.id,
-    //     email: row.email,
-    //     nome: row.nome,
-    //     tipo: row.tipo,
-    //     municipio: row.municipio
-    // };
+    .fetch_one(&mut *transaction)
+    .await?;
+
+    Ok(UnidadeSaude {
+        id: row.id,
+        email: row.email,
+        nome: row.nome,
+        tipo: row.tipo,
+        municipio: row.municipio,
+        desativado_em: row.desativado_em
+    })
+}
+
+#[tracing::instrument(name = "Criar unidade", skip(unidade_saude, pool, auditoria))]
+pub async fn create_unidade(
+    unidade_saude: web::Json<UnidadeData>,
+    pool: web::Data<PgPool>, // Renamed!
+    auditoria: Auditoria,
+) -> Result<HttpResponse, ApiError> {
+
+    unidade_saude.validar()?;
+
+    let mut transaction = auditoria.begin(pool.get_ref()).await?;
+    let unidade = inserir_unidade(&mut transaction, &unidade_saude).await?;
+    transaction.commit().await?;
 
     let unidade = UnidadeId{
-        id: row.id
+        id: unidade.id
     };
 
     Ok(HttpResponse::Ok().json(&unidade))
-    //Ok(HttpResponse::Ok().finish())
 }
 
-#[tracing::instrument(name = "Listar unidades", skip(pool))]
+const ORDENACOES_UNIDADES: &[Ordenacao] = &[
+    Ordenacao { campo: "criado_em", expressao: "criado_em", tipo: "timestamptz" },
+    Ordenacao { campo: "nome", expressao: "nome", tipo: "text" },
+    Ordenacao { campo: "municipio", expressao: "municipio", tipo: "text" },
+];
+
+const COLUNAS_UNIDADE: &str = "id, nome, email, tipo, municipio, desativado_em";
+
+/// Lista as unidades em páginas de JSON ou, com `Accept` de planilha, exporta todas as
+/// que atendem aos filtros.
+#[tracing::instrument(name = "Listar unidades", skip(pool, usuario))]
 pub async fn get_all_unidades(
-    pool: web::Data<PgPool>
-) -> Result<HttpResponse, HttpResponse>  {
+    filtro: web::Query<FiltroUnidades>,
+    paginacao: web::Query<Paginacao>,
+    formato: Formato,
+    pool: web::Data<PgPool>,
+    usuario: web::ReqData<UsuarioAutenticado>
+) -> Result<HttpResponse, ApiError>  {
 
-    let rows = sqlx::query!(
-        r#"
-        SELECT id, nome, email, tipo, municipio
-        FROM unidadeSaude
-        ORDER BY id
-        "#
-    )
-    .fetch_all(pool.get_ref())
-    .await
-    .map_err(|e| {
-        eprintln!("Failed to execute query: {}", e);
-        HttpResponse::InternalServerError().finish()
-    })?;
+    let escopo = usuario.escopo();
+    let filtro = filtro.into_inner();
+    let listagem = Listagem::new(COLUNAS_UNIDADE, "unidadeSaude", "id")
+        .filtro("({} OR desativado_em IS NULL)", Some(filtro.incluir_desativadas))
+        .filtro("lower(municipio) = lower({})", filtro.municipio)
+        .filtro("tipo = {}", filtro.tipo)
+        .filtro("id = {}", escopo.unidade_id())
+        .filtro("lower(municipio) = lower({})", escopo.municipio());
 
-    let mut unidades: Vec<UnidadeSaude> = Vec::new();
-    for row in rows {
-        let user = UnidadeSaude {
-            id: row.id,
-            email: row.email,
-            nome: row.nome,
-            tipo: row.tipo,
-            municipio: row.municipio
-        };
-        unidades.push(user);
+    if formato.planilha() {
+        let linhas: Linhas<UnidadeSaude> = listagem.exportar(pool.get_ref(), ORDENACOES_UNIDADES, &paginacao)?;
+        return formato.responder("unidades", linhas).await;
     }
 
-    Ok(HttpResponse::Ok().json(unidades))
-}
+    let pagina: Pagina<UnidadeSaude> = listagem.paginar(pool.get_ref(), ORDENACOES_UNIDADES, &paginacao).await?;
 
-#[tracing::instrument(name = "Listar unidade por id", skip(req, pool))]
-pub async fn get_unidade_by_id(
-    req: web::HttpRequest,
-    pool: web::Data<PgPool>
-) -> Result<HttpResponse, HttpResponse>  {
+    Ok(HttpResponse::Ok().json(pagina))
+}
 
-    let id:Uuid = req.match_info().get("id").unwrap().parse().unwrap();
+/// Busca a unidade pelo id, respondendo 404 se ela não existir. Unidades desativadas
+/// continuam sendo encontradas.
+pub(crate) async fn buscar_unidade(pool: &PgPool, id: Uuid) -> Result<Versionado<UnidadeSaude>, ApiError> {
 
     let row = sqlx::query!(
         r#"
-        SELECT id, email, nome, tipo, municipio
+        SELECT id, email, nome, tipo, municipio, desativado_em, versao
         FROM unidadeSaude
         WHERE id = $1
         "#,
         id,
     )
-    .fetch_one(pool.get_ref())
-    .await
-    .map_err(|e| {
-        eprintln!("Failed to execute query: {}", e);
-        HttpResponse::InternalServerError().finish()
-    })?;
+    .fetch_optional(pool)
+    .await?
+    .ok_or_else(|| ApiError::NotFound(format!("Unidade {} não encontrada", id)))?;
 
     let unidade = UnidadeSaude{
         id: row.id,
         email: row.email,
         nome: row.nome,
         tipo: row.tipo,
-        municipio: row.municipio
+        municipio: row.municipio,
+        desativado_em: row.desativado_em
     };
 
-    Ok(HttpResponse::Ok().json(&unidade))
+    Ok(Versionado { recurso: unidade, versao: row.versao })
 }
 
-#[tracing::instrument(name = "Listar unidades com leitos vazios", skip(pool))]
-pub async fn get_unidades_com_leitos(
+#[tracing::instrument(name = "Listar unidade por id", skip(pool, precondicao))]
+pub async fn get_unidade_by_id(
+    id: web::Path<Uuid>,
+    pool: web::Data<PgPool>,
+    precondicao: PreCondicao
+) -> Result<HttpResponse, ApiError>  {
+
+    let unidade = buscar_unidade(pool.get_ref(), id.into_inner()).await?;
+
+    Ok(precondicao.responder(&unidade))
+}
+
+#[tracing::instrument(name = "Resumir unidade", skip(pool))]
+pub async fn get_resumo_unidade(
+    id: web::Path<Uuid>,
     pool: web::Data<PgPool>
-) -> Result<HttpResponse, HttpResponse>  {
+) -> Result<HttpResponse, ApiError>  {
+
+    let unidade = buscar_unidade(pool.get_ref(), id.into_inner()).await?.recurso;
+
+    let ocupacao = sqlx::query!(
+        r#"
+        SELECT COUNT(*) as "total!",
+               COUNT(*) FILTER (WHERE situacao = 'ocupado') as "ocupados!",
+               COUNT(*) FILTER (WHERE situacao = 'livre') as "livres!",
+               COUNT(*) FILTER (WHERE situacao IN ('bloqueado', 'em_manutencao')) as "bloqueados!",
+               COALESCE(
+                   COUNT(*) FILTER (WHERE situacao = 'ocupado')::float8
+                   / NULLIF(COUNT(*) FILTER (WHERE situacao NOT IN ('bloqueado', 'em_manutencao')), 0),
+                   0
+               ) as "taxa_ocupacao!"
+        FROM leito
+        WHERE unidade_id = $1 AND desativado_em IS NULL
+        "#,
+        unidade.id
+    )
+    .fetch_one(pool.get_ref())
+    .await?;
 
     let rows = sqlx::query!(
         r#"
-        SELECT DISTINCT U.id, U.nome, U.email, U.tipo, U.municipio
-        FROM unidadesaude as U JOIN leito as L ON U.id = L.unidade_id
-        WHERE situacao != 'Ocupado'
+        SELECT P.id, P.nome, P.sexo, P.idade, P.email, P.telefone, CV.situacao as "situacao_covid?: SituacaoCovid",
+               I.leito_id
+        FROM internacao as I
+        JOIN leito as L ON L.id = I.leito_id
+        JOIN paciente as P ON P.id = I.paciente_id
+        LEFT JOIN situacao_covid_atual as CV ON CV.paciente_id = P.id
+        WHERE L.unidade_id = $1 AND I.alta_em IS NULL
+        ORDER BY I.admitido_em, P.id
         "#,
+        unidade.id
     )
     .fetch_all(pool.get_ref())
-    .await
-    .map_err(|e| {
-        eprintln!("Failed to execute query: {}", e);
-        HttpResponse::InternalServerError().finish()
-    })?;
+    .await?;
 
-    let mut unidades: Vec<UnidadeSaude> = Vec::new();
+    let mut pacientes: Vec<Paciente> = Vec::new();
     for row in rows {
-        let unidade = UnidadeSaude {
+        let paciente = Paciente {
             id: row.id,
-            email: row.email,
             nome: row.nome,
-            tipo: row.tipo,
-            municipio: row.municipio
+            sexo: row.sexo,
+            idade: row.idade,
+            email: row.email,
+            telefone: row.telefone,
+            situacao_covid: row.situacao_covid,
+            leito_id: Some(row.leito_id)
         };
-        unidades.push(unidade);
+        pacientes.push(paciente);
     }
 
-    Ok(HttpResponse::Ok().json(unidades))
+    let resumo = ResumoUnidade {
+        unidade,
+        ocupacao: Ocupacao {
+            total: ocupacao.total,
+            ocupados: ocupacao.ocupados,
+            livres: ocupacao.livres,
+            bloqueados: ocupacao.bloqueados,
+            taxa_ocupacao: ocupacao.taxa_ocupacao
+        },
+        pacientes
+    };
+
+    Ok(HttpResponse::Ok().json(&resumo))
 }
 
-#[tracing::instrument(name = "Atualizar unidade", skip(unidade_saude, pool))]
-pub async fn update_unidade(
-    unidade_saude: web::Json<UnidadeSaude>,
-    pool: web::Data<PgPool>
-) -> Result<HttpResponse, HttpResponse>  {
+/// Unidades com algum leito livre, em JSON ou planilha conforme o `Accept`.
+#[tracing::instrument(name = "Listar unidades com leitos vazios", skip(pool, usuario))]
+pub async fn get_unidades_com_leitos(
+    formato: Formato,
+    pool: web::Data<PgPool>,
+    usuario: web::ReqData<UsuarioAutenticado>
+) -> Result<HttpResponse, ApiError>  {
 
-    sqlx::query!(
+    let escopo = usuario.escopo();
+    let linhas: Linhas<UnidadeSaude> = Listagem::new(COLUNAS_UNIDADE, "unidadeSaude", "id")
+        .condicao("id IN (SELECT unidade_id FROM leito WHERE situacao = 'livre' AND desativado_em IS NULL)")
+        .filtro("id = {}", escopo.unidade_id())
+        .filtro("lower(municipio) = lower({})", escopo.municipio())
+        .exportar(pool.get_ref(), ORDENACOES_UNIDADES, &Paginacao::default())?;
+
+    formato.responder("leitos_disponiveis", linhas).await
+}
+
+// Trava a unidade ativa e confere o If-Match com a versão atual
+async fn travar_unidade(
+    transaction: &mut Transaction<'_, Postgres>,
+    id: Uuid,
+    precondicao: &PreCondicao
+) -> Result<(), ApiError> {
+
+    let atual = sqlx::query!(
+        "SELECT versao FROM unidadeSaude WHERE id = $1 AND desativado_em IS NULL FOR UPDATE",
+        id
+    )
+    .fetch_optional(&mut *transaction)
+    .await?
+    .ok_or_else(|| ApiError::NotFound(format!("Unidade {} não encontrada", id)))?;
+
+    precondicao.exigir(atual.versao)
+}
+
+// Grava os dados cadastrais da unidade já travada
+async fn atualizar_unidade(
+    transaction: &mut Transaction<'_, Postgres>,
+    id: Uuid,
+    unidade: &UnidadeData
+) -> Result<Versionado<UnidadeSaude>, ApiError> {
+
+    let row = sqlx::query!(
         r#"
         UPDATE unidadeSaude
         SET nome = $1, email = $2, tipo = $3, municipio = $4
         WHERE id = $5
+        RETURNING id, email, nome, tipo, municipio, desativado_em, versao
         "#,
-        unidade_saude.nome,
-        unidade_saude.email,
-        unidade_saude.tipo,
-        unidade_saude.municipio,
-        unidade_saude.id
+        unidade.nome,
+        unidade.email,
+        unidade.tipo,
+        unidade.municipio,
+        id
     )
-    .execute(pool.get_ref())
-    .await
-    .map_err(|e| {
-        eprintln!("Failed to execute query: {}", e);
-        HttpResponse::InternalServerError().finish()
-    })?;
+    .fetch_one(&mut *transaction)
+    .await?;
 
-    Ok(HttpResponse::Ok().finish())
+    let unidade = UnidadeSaude {
+        id: row.id,
+        email: row.email,
+        nome: row.nome,
+        tipo: row.tipo,
+        municipio: row.municipio,
+        desativado_em: row.desativado_em
+    };
+
+    Ok(Versionado { recurso: unidade, versao: row.versao })
 }
 
-#[tracing::instrument(name = "Deletar unidade", skip(req, pool))]
-pub async fn delete_unidade(
-    req: web::HttpRequest,
-    pool: web::Data<PgPool>
-) -> Result<HttpResponse, HttpResponse>  {
+#[tracing::instrument(name = "Atualizar unidade", skip(unidade_saude, pool, auditoria, precondicao))]
+pub async fn update_unidade(
+    id: web::Path<Uuid>,
+    unidade_saude: web::Json<UnidadeData>,
+    pool: web::Data<PgPool>,
+    auditoria: Auditoria,
+    precondicao: PreCondicao
+) -> Result<HttpResponse, ApiError>  {
+
+    unidade_saude.validar()?;
+
+    let id = id.into_inner();
+    let mut transaction = auditoria.begin(pool.get_ref()).await?;
+    travar_unidade(&mut transaction, id, &precondicao).await?;
+    let unidade = atualizar_unidade(&mut transaction, id, &unidade_saude).await?;
+    transaction.commit().await?;
+
+    Ok(com_etag(&unidade))
+}
+
+/// Atualização parcial por JSON Merge Patch: só os campos enviados mudam.
+#[tracing::instrument(name = "Atualizar unidade parcialmente", skip(patch, pool, auditoria, precondicao))]
+pub async fn patch_unidade(
+    id: web::Path<Uuid>,
+    patch: web::Json<serde_json::Value>,
+    pool: web::Data<PgPool>,
+    auditoria: Auditoria,
+    precondicao: PreCondicao
+) -> Result<HttpResponse, ApiError>  {
+
+    let id = id.into_inner();
+    let mut transaction = auditoria.begin(pool.get_ref()).await?;
+    travar_unidade(&mut transaction, id, &precondicao).await?;
+
+    let atual = sqlx::query!("SELECT email, nome, tipo, municipio FROM unidadeSaude WHERE id = $1", id)
+        .fetch_one(&mut transaction)
+        .await?;
 
-    let id:Uuid = req.match_info().get("id").unwrap().parse().unwrap();
+    let atual = UnidadeData {
+        email: atual.email,
+        nome: atual.nome,
+        tipo: atual.tipo,
+        municipio: atual.municipio
+    };
+    let unidade_saude: UnidadeData = mesclar(&atual, &patch)?;
+    unidade_saude.validar()?;
+
+    let unidade = atualizar_unidade(&mut transaction, id, &unidade_saude).await?;
+    transaction.commit().await?;
+
+    Ok(com_etag(&unidade))
+}
+
+/// Recusa a remoção com 409 listando, com a contagem, cada dependente que ainda existe.
+pub(crate) fn exigir_sem_dependentes(message: String, dependentes: &[(&str, i64, &str)]) -> Result<(), ApiError> {
+    let fields: Vec<FieldError> = dependentes
+        .iter()
+        .filter(|(_, total, _)| *total > 0)
+        .map(|(campo, total, descricao)| FieldError::new(*campo, format!("{} {}", total, descricao)))
+        .collect();
+
+    if fields.is_empty() {
+        Ok(())
+    } else {
+        Err(ApiError::Conflict { message, fields })
+    }
+}
+
+// Desativa os leitos ativos da unidade; a desativação entra no histórico de ocupação
+async fn desativar_leitos(
+    transaction: &mut Transaction<'_, Postgres>,
+    unidade_id: Uuid
+) -> Result<(), ApiError> {
 
     sqlx::query!(
+        "UPDATE leito SET desativado_em = now() WHERE unidade_id = $1 AND desativado_em IS NULL",
+        unidade_id
+    )
+    .execute(&mut *transaction)
+    .await?;
+
+    Ok(())
+}
+
+/// Remove a unidade. Leitos ativos impedem a remoção, a menos que `cascata=true` peça para
+/// desativá-los junto; pacientes internados, leitos reservados e transferências em andamento
+/// sempre impedem.
+///
+/// Unidades sem nenhum registro associado são apagadas; as demais são apenas desativadas,
+/// para que internações, transferências e relatórios antigos continuem resolvendo a unidade.
+#[tracing::instrument(name = "Deletar unidade", skip(pool, auditoria, precondicao))]
+pub async fn delete_unidade(
+    id: web::Path<Uuid>,
+    remocao: web::Query<RemocaoUnidade>,
+    pool: web::Data<PgPool>,
+    auditoria: Auditoria,
+    precondicao: PreCondicao
+) -> Result<HttpResponse, ApiError>  {
+
+    let id = id.into_inner();
+
+    let mut transaction = auditoria.begin(pool.get_ref()).await?;
+    travar_unidade(&mut transaction, id, &precondicao).await?;
+
+    let dependentes = sqlx::query!(
         r#"
-        DELETE FROM unidadeSaude
-        WHERE id = $1
+        SELECT
+            (SELECT count(*) FROM leito
+             WHERE unidade_id = $1 AND desativado_em IS NULL) as "leitos_ativos!",
+            (SELECT count(*) FROM internacao as I JOIN leito as L ON L.id = I.leito_id
+             WHERE L.unidade_id = $1 AND I.alta_em IS NULL) as "internacoes!",
+            (SELECT count(*) FROM leito
+             WHERE unidade_id = $1 AND desativado_em IS NULL AND situacao = 'reservado') as "reservas!",
+            (SELECT count(*) FROM transferencia as T
+             WHERE T.situacao IN ('solicitada', 'aceita', 'em_transito')
+               AND (T.unidade_destino_id = $1
+                    OR T.leito_origem_id IN (SELECT id FROM leito WHERE unidade_id = $1))) as "transferencias!",
+            (SELECT count(*) FROM leito WHERE unidade_id = $1) as "leitos!",
+            (SELECT count(*) FROM transferencia WHERE unidade_destino_id = $1) as "historico_transferencias!",
+            (SELECT count(*) FROM usuario WHERE unidade_id = $1) as "usuarios!"
         "#,
         id
     )
-    .execute(pool.get_ref())
-    .await
-    .map_err(|e| {
-        eprintln!("Failed to execute query: {}", e);
-        HttpResponse::InternalServerError().finish()
-    })?;
+    .fetch_one(&mut transaction)
+    .await?;
+
+    let leitos_ativos = if remocao.cascata { 0 } else { dependentes.leitos_ativos };
+    exigir_sem_dependentes(
+        format!("Unidade {} possui dependentes que impedem a remoção", id),
+        &[
+            ("leitos", leitos_ativos, "leito(s) ativo(s); use cascata=true para desativá-los"),
+            ("internacoes", dependentes.internacoes, "paciente(s) internado(s)"),
+            ("reservas", dependentes.reservas, "leito(s) reservado(s)"),
+            ("transferencias", dependentes.transferencias, "transferência(s) em andamento"),
+        ],
+    )?;
+
+    if dependentes.leitos + dependentes.historico_transferencias + dependentes.usuarios > 0 {
+        desativar_leitos(&mut transaction, id).await?;
+        sqlx::query!("UPDATE unidadeSaude SET desativado_em = now() WHERE id = $1", id)
+            .execute(&mut transaction)
+            .await?;
+    } else {
+        sqlx::query!("DELETE FROM unidadeSaude WHERE id = $1", id)
+            .execute(&mut transaction)
+            .await?;
+    }
+
+    transaction.commit().await?;
 
     Ok(HttpResponse::Ok().finish())
-}
\ No newline at end of file
+}
@@ -0,0 +1,271 @@
+use std::collections::HashMap;
+use serde::{Serialize, Deserialize};
+use actix_web::{web, HttpResponse};
+use sqlx::{PgPool, Postgres, Transaction, types::Uuid};
+use super::hospitais::{inserir_unidade, UnidadeData, UnidadeSaude};
+use super::leitos::{inserir_leito, Leito, LeitoData};
+use crate::audit::Auditoria;
+use crate::authentication::UsuarioAutenticado;
+use crate::authorization::Escopo;
+use crate::domain::SituacaoLeito;
+use crate::error::{ApiError, FieldError};
+use crate::spreadsheet::{exigir_linhas_validas, Linha, Planilha};
+use crate::validation::Validar;
+
+/// Parâmetros de `POST /importacoes/*`.
+#[derive(Debug, Default, Deserialize)]
+pub struct OpcoesImportacao {
+    /// Valida e devolve o que seria criado, sem gravar nada
+    #[serde(default)]
+    pub simular: bool,
+}
+
+/// Registros criados pela importação, ou que seriam criados numa simulação.
+#[derive(Serialize, Deserialize)]
+pub struct ResultadoImportacao<T> {
+    pub simulacao: bool,
+    pub total: usize,
+    pub criados: Vec<T>
+}
+
+// Grava tudo ou nada; na simulação as inserções rodam do mesmo jeito, para passar pelas
+// restrições do banco, e são desfeitas no fim
+async fn concluir<T: Serialize>(
+    transaction: Transaction<'_, Postgres>,
+    simular: bool,
+    criados: Vec<T>
+) -> Result<HttpResponse, ApiError> {
+
+    if simular {
+        transaction.rollback().await?;
+    } else {
+        transaction.commit().await?;
+    }
+
+    let resultado = ResultadoImportacao { simulacao: simular, total: criados.len(), criados };
+
+    Ok(HttpResponse::Ok().json(&resultado))
+}
+
+/// Importa unidades de uma planilha com as colunas `nome;email;tipo;municipio`.
+#[tracing::instrument(name = "Importar unidades", skip(corpo, pool, auditoria))]
+pub async fn importar_unidades(
+    corpo: web::Bytes,
+    opcoes: web::Query<OpcoesImportacao>,
+    pool: web::Data<PgPool>,
+    auditoria: Auditoria
+) -> Result<HttpResponse, ApiError> {
+
+    let planilha = Planilha::ler(&corpo)?;
+    planilha.exigir_colunas(&["nome", "email", "tipo", "municipio"])?;
+
+    let mut unidades = Vec::new();
+    let mut erros = Vec::new();
+    for mut linha in planilha.linhas() {
+        let unidade = UnidadeData {
+            nome: linha.texto("nome").unwrap_or_default().to_string(),
+            email: linha.texto("email").unwrap_or_default().to_string(),
+            tipo: linha.texto("tipo").unwrap_or_default().to_string(),
+            municipio: linha.texto("municipio").unwrap_or_default().to_string()
+        };
+        if let Err(erro) = unidade.validar() {
+            linha.erros_de(erro);
+        }
+
+        if linha.valida() {
+            unidades.push(unidade);
+        }
+        erros.extend(linha.concluir());
+    }
+    exigir_linhas_validas(erros)?;
+
+    let mut transaction = auditoria.begin(pool.get_ref()).await?;
+    let mut criadas: Vec<UnidadeSaude> = Vec::new();
+    for unidade in &unidades {
+        criadas.push(inserir_unidade(&mut transaction, unidade).await?);
+    }
+
+    concluir(transaction, opcoes.simular, criadas).await
+}
+
+/// Unidades citadas na planilha de leitos, carregadas de uma vez só.
+#[derive(Default)]
+struct UnidadesReferenciadas {
+    /// Município e se está desativada, pelo id
+    por_id: HashMap<Uuid, (String, bool)>,
+    /// Unidades ativas com o e-mail, em minúsculas
+    por_email: HashMap<String, Vec<(Uuid, String)>>,
+}
+
+// Busca numa consulta só, dentro da transação da importação, todas as unidades citadas
+// por id ou por e-mail. As linhas ficam travadas até o fim da importação, para que
+// nenhuma unidade seja desativada ou removida entre a validação e as inserções.
+async fn carregar_unidades(
+    transaction: &mut Transaction<'_, Postgres>,
+    planilha: &Planilha
+) -> Result<UnidadesReferenciadas, ApiError> {
+
+    let mut ids: Vec<Uuid> = Vec::new();
+    let mut emails: Vec<String> = Vec::new();
+    for linha in planilha.linhas() {
+        if let Some(id) = linha.texto("unidade_id").and_then(|valor| Uuid::parse_str(valor).ok()) {
+            ids.push(id);
+        } else if let Some(email) = linha.texto("unidade_email") {
+            emails.push(email.to_lowercase());
+        }
+    }
+
+    let mut unidades = UnidadesReferenciadas::default();
+    if ids.is_empty() && emails.is_empty() {
+        return Ok(unidades);
+    }
+
+    let rows = sqlx::query!(
+        r#"
+        SELECT id, lower(email) as "email!", municipio, desativado_em
+        FROM unidadeSaude
+        WHERE id = ANY($1) OR (lower(email) = ANY($2) AND desativado_em IS NULL)
+        FOR SHARE
+        "#,
+        &ids[..],
+        &emails[..]
+    )
+    .fetch_all(&mut *transaction)
+    .await?;
+
+    for row in rows {
+        let ativa = row.desativado_em.is_none();
+        if ativa {
+            unidades.por_email.entry(row.email).or_default().push((row.id, row.municipio.clone()));
+        }
+        unidades.por_id.insert(row.id, (row.municipio, !ativa));
+    }
+
+    Ok(unidades)
+}
+
+// A unidade do leito vem pelo id ou pelo e-mail; o e-mail só serve se for de uma única
+// unidade ativa. Devolve a unidade encontrada e o município dela, para o escopo.
+fn resolver_unidade(
+    unidades: &UnidadesReferenciadas,
+    linha: &mut Linha<'_>
+) -> Option<(Uuid, String)> {
+
+    if let Some(valor) = linha.texto("unidade_id") {
+        let id = match Uuid::parse_str(valor) {
+            Ok(id) => id,
+            Err(_) => {
+                linha.erro("unidade_id", format!("identificador inválido: '{}'", valor));
+                return None;
+            }
+        };
+        return match unidades.por_id.get(&id) {
+            None => {
+                linha.erro("unidade_id", "unidade não encontrada");
+                None
+            }
+            Some((_, true)) => {
+                linha.erro("unidade_id", "unidade desativada");
+                None
+            }
+            Some((municipio, false)) => Some((id, municipio.clone())),
+        };
+    }
+
+    let email = match linha.texto("unidade_email") {
+        Some(email) => email,
+        None => {
+            linha.erro("unidade_id", "informe unidade_id ou unidade_email");
+            return None;
+        }
+    };
+
+    match unidades.por_email.get(&email.to_lowercase()).map(Vec::as_slice) {
+        None | Some([]) => {
+            linha.erro("unidade_email", "nenhuma unidade ativa com este e-mail");
+            None
+        }
+        Some([unidade]) => Some(unidade.clone()),
+        Some(encontradas) => {
+            linha.erro("unidade_email", format!("e-mail de {} unidades; use unidade_id", encontradas.len()));
+            None
+        }
+    }
+}
+
+/// Importa leitos de uma planilha com as colunas `tipo` e `unidade_id` ou `unidade_email`;
+/// `situacao` (padrão `Livre`), `ventilador_mecanico`, `isolamento_pressao_negativa` e
+/// `hemodialise` são opcionais. Cada unidade precisa estar no escopo do usuário.
+#[tracing::instrument(name = "Importar leitos", skip(corpo, pool, auditoria, usuario))]
+pub async fn importar_leitos(
+    corpo: web::Bytes,
+    opcoes: web::Query<OpcoesImportacao>,
+    pool: web::Data<PgPool>,
+    auditoria: Auditoria,
+    usuario: web::ReqData<UsuarioAutenticado>
+) -> Result<HttpResponse, ApiError> {
+
+    let planilha = Planilha::ler(&corpo)?;
+    planilha.exigir_colunas(&["tipo"])?;
+    if !planilha.tem_coluna("unidade_id") && !planilha.tem_coluna("unidade_email") {
+        return Err(ApiError::validation(
+            "A planilha não tem todas as colunas obrigatórias",
+            vec![FieldError::new("unidade_id", "informe a coluna unidade_id ou unidade_email")],
+        ));
+    }
+
+    let mut transaction = auditoria.begin(pool.get_ref()).await?;
+    let unidades = carregar_unidades(&mut transaction, &planilha).await?;
+
+    let escopo = usuario.escopo();
+    let mut leitos = Vec::new();
+    let mut erros = Vec::new();
+    for mut linha in planilha.linhas() {
+        if linha.texto("tipo").is_none() {
+            linha.erro("tipo", "campo obrigatório");
+        }
+        let tipo = linha.enumeracao("tipo");
+        let situacao = linha.enumeracao("situacao").unwrap_or(SituacaoLeito::Livre);
+        let ventilador_mecanico = linha.booleano("ventilador_mecanico");
+        let isolamento_pressao_negativa = linha.booleano("isolamento_pressao_negativa");
+        let hemodialise = linha.booleano("hemodialise");
+
+        let unidade = resolver_unidade(&unidades, &mut linha);
+        if let Some((unidade_id, municipio)) = &unidade {
+            let permitido = match &escopo {
+                Escopo::Todos => true,
+                Escopo::Unidade(id) => id == unidade_id,
+                Escopo::Municipio(proprio) => proprio.to_lowercase() == municipio.to_lowercase(),
+            };
+            if !permitido {
+                linha.erro("unidade_id", "unidade fora do escopo do usuário");
+            }
+        }
+
+        if let (Some(tipo), Some((unidade_id, _))) = (tipo, unidade) {
+            let leito = LeitoData {
+                tipo,
+                situacao,
+                ventilador_mecanico,
+                isolamento_pressao_negativa,
+                hemodialise,
+                unidade_id
+            };
+            if let Err(erro) = leito.validar() {
+                linha.erros_de(erro);
+            }
+            if linha.valida() {
+                leitos.push(leito);
+            }
+        }
+        erros.extend(linha.concluir());
+    }
+    exigir_linhas_validas(erros)?;
+
+    let mut criados: Vec<Leito> = Vec::new();
+    for leito in &leitos {
+        criados.push(inserir_leito(&mut transaction, leito).await?);
+    }
+
+    concluir(transaction, opcoes.simular, criados).await
+}
@@ -0,0 +1,374 @@
+use serde::{Serialize, Deserialize};
+use actix_web::{web, HttpResponse};
+use chrono::{DateTime, Utc};
+use sqlx::{PgPool, Postgres, Transaction, types::Uuid};
+use super::serializers::my_uuid;
+use crate::audit::Auditoria;
+use crate::authentication::UsuarioAutenticado;
+use crate::authorization::{exigir_escopo, Recurso};
+use crate::error::{ApiError, FieldError};
+use crate::domain::{MotivoAlta, SituacaoLeito};
+use super::condicoes::exigir_leito_de_isolamento;
+use super::leitos::exigir_leito_ativo;
+
+#[derive(Serialize, Deserialize)]
+pub struct Internacao {
+    #[serde(with = "my_uuid")]
+    pub id: Uuid,
+    #[serde(with = "my_uuid")]
+    pub paciente_id: Uuid,
+    #[serde(with = "my_uuid")]
+    pub leito_id: Uuid,
+    pub admitido_em: DateTime<Utc>,
+    pub alta_em: Option<DateTime<Utc>>,
+    pub motivo_alta: Option<MotivoAlta>
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct InternacaoData {
+    #[serde(with = "my_uuid")]
+    pub leito_id: Uuid
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct AltaData {
+    pub motivo: MotivoAlta
+}
+
+/// Abre uma internação para o paciente no leito informado, marcando o leito como ocupado.
+///
+/// Paciente e leito são travados com `FOR UPDATE` dentro da transação recebida, então
+/// quem chama só precisa fazer o commit. Um leito reservado só é ocupado pela
+/// transferência dona da reserva, informada em `reserva`.
+pub(crate) async fn internar(
+    transaction: &mut Transaction<'_, Postgres>,
+    paciente_id: Uuid,
+    leito_id: Uuid,
+    reserva: Option<Uuid>
+) -> Result<Internacao, ApiError> {
+
+    sqlx::query!(
+        r#"
+        SELECT id
+        FROM paciente
+        WHERE id = $1
+        FOR UPDATE
+        "#,
+        paciente_id
+    )
+    .fetch_optional(&mut *transaction)
+    .await?
+    .ok_or_else(|| ApiError::NotFound(format!("Paciente {} não encontrado", paciente_id)))?;
+
+    let aberta = sqlx::query!(
+        r#"
+        SELECT id
+        FROM internacao
+        WHERE paciente_id = $1 AND alta_em IS NULL
+        "#,
+        paciente_id
+    )
+    .fetch_optional(&mut *transaction)
+    .await?;
+
+    if let Some(aberta) = aberta {
+        return Err(ApiError::Conflict {
+            message: format!("Paciente já está internado (internação {})", aberta.id),
+            fields: vec![FieldError::new("paciente_id", "internação em aberto")],
+        });
+    }
+
+    let leito = sqlx::query!(
+        r#"
+        SELECT situacao as "situacao: SituacaoLeito", desativado_em
+        FROM leito
+        WHERE id = $1
+        FOR UPDATE
+        "#,
+        leito_id
+    )
+    .fetch_optional(&mut *transaction)
+    .await?
+    .ok_or_else(|| ApiError::UnprocessableEntity {
+        message: format!("Leito {} não existe", leito_id),
+        fields: vec![FieldError::new("leito_id", "referência inválida")],
+    })?;
+    exigir_leito_ativo(leito_id, leito.desativado_em, "leito_id")?;
+
+    let disponivel = match (leito.situacao, reserva) {
+        (SituacaoLeito::Livre, _) => true,
+        (SituacaoLeito::Reservado, Some(transferencia_id)) => sqlx::query!(
+            r#"
+            SELECT EXISTS (
+                SELECT 1 FROM transferencia
+                WHERE id = $1 AND leito_destino_id = $2 AND situacao IN ('aceita', 'em_transito')
+            ) as "reservado!"
+            "#,
+            transferencia_id,
+            leito_id
+        )
+        .fetch_one(&mut *transaction)
+        .await?
+        .reservado,
+        _ => false,
+    };
+    if !disponivel {
+        return Err(ApiError::Conflict {
+            message: format!("Leito {} não está disponível (situação {})", leito_id, leito.situacao),
+            fields: vec![FieldError::new("leito_id", "leito indisponível")],
+        });
+    }
+    exigir_leito_de_isolamento(&mut *transaction, paciente_id, leito_id, "leito_id").await?;
+    let ocupado = leito.situacao.transicionar_para(SituacaoLeito::Ocupado)?;
+
+    sqlx::query!(
+        r#"
+        UPDATE leito
+        SET situacao = $1
+        WHERE id = $2
+        "#,
+        ocupado as SituacaoLeito,
+        leito_id
+    )
+    .execute(&mut *transaction)
+    .await?;
+
+    let row = sqlx::query!(
+        r#"
+        INSERT INTO internacao (id, paciente_id, leito_id)
+        VALUES ($1, $2, $3)
+        RETURNING id, paciente_id, leito_id, admitido_em, alta_em, motivo_alta as "motivo_alta: MotivoAlta"
+        "#,
+        Uuid::new_v4(),
+        paciente_id,
+        leito_id
+    )
+    .fetch_one(&mut *transaction)
+    .await?;
+
+    // o paciente internado deixa a fila de regulação
+    sqlx::query!(
+        r#"
+        UPDATE fila_regulacao
+        SET atendida_em = now(), internacao_id = $1
+        WHERE paciente_id = $2 AND atendida_em IS NULL
+        "#,
+        row.id,
+        paciente_id
+    )
+    .execute(&mut *transaction)
+    .await?;
+
+    Ok(Internacao {
+        id: row.id,
+        paciente_id: row.paciente_id,
+        leito_id: row.leito_id,
+        admitido_em: row.admitido_em,
+        alta_em: row.alta_em,
+        motivo_alta: row.motivo_alta
+    })
+}
+
+/// Encerra a internação informada e libera o leito para higienização.
+pub(crate) async fn dar_alta(
+    transaction: &mut Transaction<'_, Postgres>,
+    internacao_id: Uuid,
+    motivo: MotivoAlta
+) -> Result<Internacao, ApiError> {
+
+    let internacao = sqlx::query!(
+        r#"
+        SELECT leito_id, alta_em
+        FROM internacao
+        WHERE id = $1
+        FOR UPDATE
+        "#,
+        internacao_id
+    )
+    .fetch_optional(&mut *transaction)
+    .await?
+    .ok_or_else(|| ApiError::NotFound(format!("Internação {} não encontrada", internacao_id)))?;
+
+    if let Some(alta_em) = internacao.alta_em {
+        return Err(ApiError::Conflict {
+            message: format!("Internação {} já foi encerrada em {}", internacao_id, alta_em),
+            fields: Vec::new(),
+        });
+    }
+
+    let leito = sqlx::query!(
+        r#"
+        SELECT situacao as "situacao: SituacaoLeito"
+        FROM leito
+        WHERE id = $1
+        FOR UPDATE
+        "#,
+        internacao.leito_id
+    )
+    .fetch_one(&mut *transaction)
+    .await?;
+
+    let liberado = leito.situacao.transicionar_para(SituacaoLeito::EmHigienizacao)?;
+
+    sqlx::query!(
+        r#"
+        UPDATE leito
+        SET situacao = $1
+        WHERE id = $2
+        "#,
+        liberado as SituacaoLeito,
+        internacao.leito_id
+    )
+    .execute(&mut *transaction)
+    .await?;
+
+    let row = sqlx::query!(
+        r#"
+        UPDATE internacao
+        SET alta_em = now(), motivo_alta = $1
+        WHERE id = $2
+        RETURNING id, paciente_id, leito_id, admitido_em, alta_em, motivo_alta as "motivo_alta: MotivoAlta"
+        "#,
+        motivo as MotivoAlta,
+        internacao_id
+    )
+    .fetch_one(&mut *transaction)
+    .await?;
+
+    Ok(Internacao {
+        id: row.id,
+        paciente_id: row.paciente_id,
+        leito_id: row.leito_id,
+        admitido_em: row.admitido_em,
+        alta_em: row.alta_em,
+        motivo_alta: row.motivo_alta
+    })
+}
+
+#[tracing::instrument(name = "Internar paciente", skip(internacao, pool, usuario, auditoria))]
+pub async fn create_internacao(
+    id: web::Path<Uuid>,
+    internacao: web::Json<InternacaoData>,
+    pool: web::Data<PgPool>,
+    auditoria: Auditoria,
+    usuario: web::ReqData<UsuarioAutenticado>
+) -> Result<HttpResponse, ApiError> {
+
+    exigir_escopo(&usuario, pool.get_ref(), Recurso::Leito, internacao.leito_id).await?;
+
+    let mut transaction = auditoria.begin(pool.get_ref()).await?;
+    let internacao = internar(&mut transaction, id.into_inner(), internacao.leito_id, None).await?;
+    transaction.commit().await?;
+
+    Ok(HttpResponse::Ok().json(&internacao))
+}
+
+/// Alta avulsa. Com uma transferência em andamento a internação só se encerra pela
+/// conclusão dela; do contrário o leito de destino ficaria reservado para sempre.
+#[tracing::instrument(name = "Registrar alta", skip(alta, pool, auditoria))]
+pub async fn create_alta(
+    id: web::Path<Uuid>,
+    alta: web::Json<AltaData>,
+    pool: web::Data<PgPool>,
+    auditoria: Auditoria
+) -> Result<HttpResponse, ApiError> {
+
+    let id = id.into_inner();
+    let mut transaction = auditoria.begin(pool.get_ref()).await?;
+
+    let transferencia = sqlx::query!(
+        r#"
+        SELECT id
+        FROM transferencia
+        WHERE internacao_origem_id = $1 AND situacao IN ('solicitada', 'aceita', 'em_transito')
+        FOR UPDATE
+        "#,
+        id
+    )
+    .fetch_optional(&mut transaction)
+    .await?;
+
+    if let Some(transferencia) = transferencia {
+        return Err(ApiError::Conflict {
+            message: format!("Internação {} tem a transferência {} em andamento", id, transferencia.id),
+            fields: Vec::new(),
+        });
+    }
+
+    let internacao = dar_alta(&mut transaction, id, alta.motivo).await?;
+    transaction.commit().await?;
+
+    Ok(HttpResponse::Ok().json(&internacao))
+}
+
+#[tracing::instrument(name = "Listar internação por id", skip(pool))]
+pub async fn get_internacao_by_id(
+    id: web::Path<Uuid>,
+    pool: web::Data<PgPool>
+) -> Result<HttpResponse, ApiError> {
+
+    let id = id.into_inner();
+
+    let row = sqlx::query!(
+        r#"
+        SELECT id, paciente_id, leito_id, admitido_em, alta_em, motivo_alta as "motivo_alta: MotivoAlta"
+        FROM internacao
+        WHERE id = $1
+        "#,
+        id
+    )
+    .fetch_one(pool.get_ref())
+    .await?;
+
+    let internacao = Internacao {
+        id: row.id,
+        paciente_id: row.paciente_id,
+        leito_id: row.leito_id,
+        admitido_em: row.admitido_em,
+        alta_em: row.alta_em,
+        motivo_alta: row.motivo_alta
+    };
+
+    Ok(HttpResponse::Ok().json(&internacao))
+}
+
+#[tracing::instrument(name = "Listar internações do paciente", skip(pool))]
+pub async fn get_internacoes_paciente(
+    id: web::Path<Uuid>,
+    pool: web::Data<PgPool>
+) -> Result<HttpResponse, ApiError> {
+
+    let id = id.into_inner();
+
+    sqlx::query!("SELECT id FROM paciente WHERE id = $1", id)
+        .fetch_optional(pool.get_ref())
+        .await?
+        .ok_or_else(|| ApiError::NotFound(format!("Paciente {} não encontrado", id)))?;
+
+    let rows = sqlx::query!(
+        r#"
+        SELECT id, paciente_id, leito_id, admitido_em, alta_em, motivo_alta as "motivo_alta: MotivoAlta"
+        FROM internacao
+        WHERE paciente_id = $1
+        ORDER BY admitido_em
+        "#,
+        id
+    )
+    .fetch_all(pool.get_ref())
+    .await?;
+
+    let mut internacoes: Vec<Internacao> = Vec::new();
+    for row in rows {
+        let internacao = Internacao {
+            id: row.id,
+            paciente_id: row.paciente_id,
+            leito_id: row.leito_id,
+            admitido_em: row.admitido_em,
+            alta_em: row.alta_em,
+            motivo_alta: row.motivo_alta
+        };
+        internacoes.push(internacao);
+    }
+
+    Ok(HttpResponse::Ok().json(internacoes))
+}
@@ -1,187 +1,572 @@
 use serde::{Serialize, Deserialize};
 use actix_web::{web, HttpResponse};
-use sqlx::{PgPool, types::Uuid};
+use chrono::{DateTime, Utc};
+use sqlx::{Executor, PgPool, Postgres, Transaction, types::Uuid};
 use super::serializers::my_uuid;
+use super::hospitais::{buscar_unidade, exigir_sem_dependentes};
+use crate::audit::Auditoria;
+use crate::authentication::UsuarioAutenticado;
+use crate::authorization::{exigir_escopo, Escopo, Recurso};
+use crate::concurrency::{com_etag, PreCondicao, Versionado};
+use crate::error::{ApiError, FieldError};
+use crate::patch::mesclar;
+use crate::query::{Linhas, Listagem, Ordenacao, Pagina, Paginacao};
+use crate::spreadsheet::{Celula, Exportavel, Formato};
+use crate::validation::{Validacao, Validar};
+use crate::domain::{SituacaoLeito, TipoLeito};
 
 //#[derive(serde::Deserialize)]
-#[derive(Serialize, Deserialize)]
+#[derive(Serialize, Deserialize, sqlx::FromRow)]
 pub struct Leito {
     #[serde(with = "my_uuid")]
     pub id: Uuid,
-    pub tipo: String,
-    pub situacao: String,
+    pub tipo: TipoLeito,
+    pub situacao: SituacaoLeito,
+    #[serde(default)]
+    pub ventilador_mecanico: bool,
+    #[serde(default)]
+    pub isolamento_pressao_negativa: bool,
+    #[serde(default)]
+    pub hemodialise: bool,
     #[serde(with = "my_uuid")]
-    pub unidade_id: Uuid
+    pub unidade_id: Uuid,
+    /// Preenchido quando o leito foi removido mas continua referenciado pelo histórico
+    #[serde(default)]
+    pub desativado_em: Option<DateTime<Utc>>
+}
+
+impl Exportavel for Leito {
+    const COLUNAS: &'static [&'static str] = &[
+        "id",
+        "tipo",
+        "situacao",
+        "unidade_id",
+        "ventilador_mecanico",
+        "isolamento_pressao_negativa",
+        "hemodialise",
+        "desativado_em",
+    ];
+
+    fn celulas(self) -> Vec<Celula> {
+        vec![
+            self.id.into(),
+            Celula::enumeracao(&self.tipo),
+            Celula::enumeracao(&self.situacao),
+            self.unidade_id.into(),
+            self.ventilador_mecanico.into(),
+            self.isolamento_pressao_negativa.into(),
+            self.hemodialise.into(),
+            self.desativado_em.into(),
+        ]
+    }
 }
 
 #[derive(Serialize, Deserialize)]
 pub struct LeitoData {
-    pub tipo: String,
-    pub situacao: String,
+    pub tipo: TipoLeito,
+    pub situacao: SituacaoLeito,
+    #[serde(default)]
+    pub ventilador_mecanico: bool,
+    #[serde(default)]
+    pub isolamento_pressao_negativa: bool,
+    #[serde(default)]
+    pub hemodialise: bool,
     #[serde(with = "my_uuid")]
     pub unidade_id: Uuid
 }
 
+impl Validar for LeitoData {
+    fn regras(&self, validacao: &mut Validacao) {
+        validacao.regra(
+            "situacao",
+            self.situacao != SituacaoLeito::Ocupado,
+            "a ocupação acontece pela internação de um paciente",
+        );
+    }
+}
+
+/// Filtros opcionais de `GET /leitos`, combinados com `AND`.
+#[derive(Debug, Default, Deserialize)]
+pub struct FiltroLeitos {
+    pub tipo: Option<TipoLeito>,
+    pub situacao: Option<SituacaoLeito>,
+    pub unidade_id: Option<Uuid>,
+    pub municipio: Option<String>,
+    pub ventilador_mecanico: Option<bool>,
+    pub isolamento_pressao_negativa: Option<bool>,
+    pub hemodialise: Option<bool>,
+    /// Inclui os leitos desativados, que por padrão ficam de fora
+    #[serde(default)]
+    pub incluir_desativados: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TransicaoLeitoData {
+    pub situacao: SituacaoLeito,
+}
+
 #[derive(Serialize, Deserialize)]
 pub struct LeitoId {
     #[serde(with = "my_uuid")]
     pub id: Uuid,
 }
 
-#[tracing::instrument(name = "Criar leito", skip(leito, pool))]
-pub async fn create_leito(
-    leito: web::Json<LeitoData>,
-    pool: web::Data<PgPool>, // Renamed!
-) -> Result<HttpResponse, HttpResponse> {
-    
+/// Recusa alterações em leito desativado; ele só continua existindo para o histórico.
+pub(crate) fn exigir_leito_ativo(
+    leito_id: Uuid,
+    desativado_em: Option<DateTime<Utc>>,
+    campo: &str
+) -> Result<(), ApiError> {
+    match desativado_em {
+        Some(desativado_em) => Err(ApiError::Conflict {
+            message: format!("Leito {} foi desativado em {}", leito_id, desativado_em),
+            fields: vec![FieldError::new(campo, "leito desativado")],
+        }),
+        None => Ok(()),
+    }
+}
+
+/// Valida uma mudança de situação feita diretamente no leito, fora do fluxo de internação.
+///
+/// A ocupação só começa com a internação de um paciente e só termina com a alta, então
+/// nenhuma mudança manual pode entrar em `Ocupado` nem sair dele enquanto houver uma
+/// internação em aberto no leito.
+async fn validar_mudanca_manual(
+    transaction: &mut Transaction<'_, Postgres>,
+    leito_id: Uuid,
+    atual: SituacaoLeito,
+    nova: SituacaoLeito
+) -> Result<(), ApiError> {
+
+    atual.transicionar_para(nova)?;
+
+    if nova == SituacaoLeito::Ocupado {
+        return Err(ApiError::Conflict {
+            message: "A ocupação de um leito só acontece pela internação de um paciente".into(),
+            fields: vec![FieldError::new("situacao", "use POST /pacientes/{id}/internar")],
+        });
+    }
+
+    if atual == SituacaoLeito::Ocupado {
+        let internacao = sqlx::query!(
+            r#"
+            SELECT id
+            FROM internacao
+            WHERE leito_id = $1 AND alta_em IS NULL
+            "#,
+            leito_id
+        )
+        .fetch_optional(&mut *transaction)
+        .await?;
+
+        if let Some(internacao) = internacao {
+            return Err(ApiError::Conflict {
+                message: format!("Leito possui a internação {} em aberto", internacao.id),
+                fields: vec![FieldError::new("situacao", "use POST /internacoes/{id}/alta")],
+            });
+        }
+    }
+
+    Ok(())
+}
+
+/// Grava um novo leito na transação auditada; unidades desativadas não recebem leitos.
+pub(crate) async fn inserir_leito(
+    transaction: &mut Transaction<'_, Postgres>,
+    leito: &LeitoData
+) -> Result<Leito, ApiError> {
+
+    let unidade = sqlx::query!(
+        "SELECT desativado_em FROM unidadeSaude WHERE id = $1 FOR SHARE",
+        leito.unidade_id
+    )
+    .fetch_optional(&mut *transaction)
+    .await?;
+    if let Some(unidade) = unidade {
+        if unidade.desativado_em.is_some() {
+            return Err(ApiError::Conflict {
+                message: format!("Unidade {} está desativada", leito.unidade_id),
+                fields: vec![FieldError::new("unidade_id", "unidade desativada")],
+            });
+        }
+    }
+
     let row = sqlx::query!(
         r#"
-        INSERT INTO leito (id, tipo, situacao, unidade_id)
-        VALUES ($1, $2, $3, $4)
-        RETURNING id
+        INSERT INTO leito (id, tipo, situacao, ventilador_mecanico, isolamento_pressao_negativa, hemodialise, unidade_id)
+        VALUES ($1, $2, $3, $4, $5, $6, $7)
+        RETURNING id, tipo as "tipo: TipoLeito", situacao as "situacao: SituacaoLeito",
+                  ventilador_mecanico, isolamento_pressao_negativa, hemodialise, unidade_id, desativado_em
         "#,
         Uuid::new_v4(),
-        leito.tipo,
-        leito.situacao,
+        leito.tipo as TipoLeito,
+        leito.situacao as SituacaoLeito,
+        leito.ventilador_mecanico,
+        leito.isolamento_pressao_negativa,
+        leito.hemodialise,
         leito.unidade_id,
     )
-    // We got rid of the double-wrapping using .app_data()
-    .fetch_one(pool.get_ref())
-    .await
-    .map_err(|e| {
-        eprintln!("Failed to execute query: {}", e);
-        HttpResponse::InternalServerError().finish()
-    })?;
-
-    // let unidade = UnidadeSaude {
-    //     id: row.id,
-    //     email: row.email,
-    //     nome: row.nome,
-    //     tipo: row.tipo,
-    //     municipio: row.municipio
-    // };
+    .fetch_one(&mut *transaction)
+    .await?;
+
+    Ok(Leito {
+        id: row.id,
+        tipo: row.tipo,
+        situacao: row.situacao,
+        ventilador_mecanico: row.ventilador_mecanico,
+        isolamento_pressao_negativa: row.isolamento_pressao_negativa,
+        hemodialise: row.hemodialise,
+        unidade_id: row.unidade_id,
+        desativado_em: row.desativado_em
+    })
+}
+
+#[tracing::instrument(name = "Criar leito", skip(leito, pool, usuario, auditoria))]
+pub async fn create_leito(
+    leito: web::Json<LeitoData>,
+    pool: web::Data<PgPool>, // Renamed!
+    auditoria: Auditoria,
+    usuario: web::ReqData<UsuarioAutenticado>
+) -> Result<HttpResponse, ApiError> {
+
+    leito.validar()?;
+
+    exigir_escopo(&usuario, pool.get_ref(), Recurso::Unidade, leito.unidade_id).await?;
+
+    let mut transaction = auditoria.begin(pool.get_ref()).await?;
+    let leito = inserir_leito(&mut transaction, &leito).await?;
+    transaction.commit().await?;
 
     let leito = LeitoId{
-        id: row.id
+        id: leito.id
     };
 
     Ok(HttpResponse::Ok().json(&leito))
-    //Ok(HttpResponse::Ok().finish())
 }
 
-#[tracing::instrument(name = "Listar leitos", skip(pool))]
-pub async fn get_all_leitos(
-    pool: web::Data<PgPool>
-) -> Result<HttpResponse, HttpResponse>  {
-    let rows = sqlx::query!(
-        r#"
-        SELECT id, tipo, situacao, unidade_id
-        FROM leito
-        ORDER BY id
-        "#
+const ORDENACOES_LEITOS: &[Ordenacao] = &[
+    Ordenacao { campo: "criado_em", expressao: "criado_em", tipo: "timestamptz" },
+    Ordenacao { campo: "tipo", expressao: "tipo", tipo: "tipo_leito" },
+    Ordenacao { campo: "situacao", expressao: "situacao", tipo: "situacao_leito" },
+];
+
+fn listagem_leitos(filtro: FiltroLeitos, escopo: Escopo) -> Listagem {
+
+    Listagem::new(
+        "id, tipo, situacao, ventilador_mecanico, isolamento_pressao_negativa, hemodialise, unidade_id, desativado_em",
+        "leito",
+        "id"
     )
-    .fetch_all(pool.get_ref())
-    .await
-    .map_err(|e| {
-        eprintln!("Failed to execute query: {}", e);
-        HttpResponse::InternalServerError().finish()
-    })?;
-
-    let mut leitos: Vec<Leito> = Vec::new();
-    for row in rows {
-        let leito = Leito {
-            id: row.id,
-            tipo: row.tipo,
-            situacao: row.situacao,
-            unidade_id: row.unidade_id
-        };
-        leitos.push(leito);
+    .filtro("({} OR desativado_em IS NULL)", Some(filtro.incluir_desativados))
+    .filtro("tipo = {}", filtro.tipo)
+    .filtro("situacao = {}", filtro.situacao)
+    .filtro("ventilador_mecanico = {}", filtro.ventilador_mecanico)
+    .filtro("isolamento_pressao_negativa = {}", filtro.isolamento_pressao_negativa)
+    .filtro("hemodialise = {}", filtro.hemodialise)
+    .filtro("unidade_id = {}", filtro.unidade_id)
+    .filtro("unidade_id IN (SELECT id FROM unidadeSaude WHERE lower(municipio) = lower({}))", filtro.municipio)
+    .filtro("unidade_id = {}", escopo.unidade_id())
+    .filtro("unidade_id IN (SELECT id FROM unidadeSaude WHERE lower(municipio) = lower({}))", escopo.municipio())
+}
+
+/// Lista os leitos em páginas de JSON ou, com `Accept` de planilha, exporta todos os
+/// que atendem aos filtros.
+#[tracing::instrument(name = "Listar leitos", skip(pool, usuario))]
+pub async fn get_all_leitos(
+    filtro: web::Query<FiltroLeitos>,
+    paginacao: web::Query<Paginacao>,
+    formato: Formato,
+    pool: web::Data<PgPool>,
+    usuario: web::ReqData<UsuarioAutenticado>
+) -> Result<HttpResponse, ApiError>  {
+
+    let listagem = listagem_leitos(filtro.into_inner(), usuario.escopo());
+
+    if formato.planilha() {
+        let linhas: Linhas<Leito> = listagem.exportar(pool.get_ref(), ORDENACOES_LEITOS, &paginacao)?;
+        return formato.responder("leitos", linhas).await;
     }
 
-    Ok(HttpResponse::Ok().json(leitos))
+    let pagina: Pagina<Leito> = listagem.paginar(pool.get_ref(), ORDENACOES_LEITOS, &paginacao).await?;
+
+    Ok(HttpResponse::Ok().json(pagina))
 }
 
-#[tracing::instrument(name = "Listar leito por id", skip(req, pool))]
-pub async fn get_leito_by_id(
-    req: web::HttpRequest,
-    pool: web::Data<PgPool>
-) -> Result<HttpResponse, HttpResponse>  {
+#[tracing::instrument(name = "Listar leitos da unidade", skip(pool, usuario))]
+pub async fn get_leitos_da_unidade(
+    id: web::Path<Uuid>,
+    filtro: web::Query<FiltroLeitos>,
+    paginacao: web::Query<Paginacao>,
+    pool: web::Data<PgPool>,
+    usuario: web::ReqData<UsuarioAutenticado>
+) -> Result<HttpResponse, ApiError>  {
+
+    let unidade = buscar_unidade(pool.get_ref(), id.into_inner()).await?.recurso;
 
-    let id:Uuid = req.match_info().get("id").unwrap().parse().unwrap();
+    let filtro = FiltroLeitos { unidade_id: Some(unidade.id), ..filtro.into_inner() };
+    let pagina: Pagina<Leito> = listagem_leitos(filtro, usuario.escopo())
+        .paginar(pool.get_ref(), ORDENACOES_LEITOS, &paginacao)
+        .await?;
+
+    Ok(HttpResponse::Ok().json(pagina))
+}
 
+/// Busca o leito pelo id, respondendo 404 se ele não existir. Leitos desativados
+/// continuam sendo encontrados.
+pub(crate) async fn buscar_leito<'c, E>(executor: E, id: Uuid) -> Result<Versionado<Leito>, ApiError>
+where
+    E: Executor<'c, Database = Postgres>,
+{
     let row = sqlx::query!(
         r#"
-        SELECT id, tipo, situacao, unidade_id
+        SELECT id, tipo as "tipo: TipoLeito", situacao as "situacao: SituacaoLeito",
+               ventilador_mecanico, isolamento_pressao_negativa, hemodialise, unidade_id, desativado_em, versao
         FROM leito
         WHERE id = $1
         "#,
         id,
     )
-    .fetch_one(pool.get_ref())
-    .await
-    .map_err(|e| {
-        eprintln!("Failed to execute query: {}", e);
-        HttpResponse::InternalServerError().finish()
-    })?;
+    .fetch_optional(executor)
+    .await?
+    .ok_or_else(|| ApiError::NotFound(format!("Leito {} não encontrado", id)))?;
 
     let leito = Leito {
         id: row.id,
         tipo: row.tipo,
         situacao: row.situacao,
-        unidade_id: row.unidade_id
+        ventilador_mecanico: row.ventilador_mecanico,
+        isolamento_pressao_negativa: row.isolamento_pressao_negativa,
+        hemodialise: row.hemodialise,
+        unidade_id: row.unidade_id,
+        desativado_em: row.desativado_em
     };
 
-    Ok(HttpResponse::Ok().json(&leito))
+    Ok(Versionado { recurso: leito, versao: row.versao })
 }
 
-#[tracing::instrument(name = "Atualizar leito", skip(leito, pool))]
-pub async fn update_leito(
-    leito: web::Json<Leito>,
-    pool: web::Data<PgPool>
-) -> Result<HttpResponse, HttpResponse>  {
+#[tracing::instrument(name = "Listar leito por id", skip(pool, precondicao))]
+pub async fn get_leito_by_id(
+    id: web::Path<Uuid>,
+    pool: web::Data<PgPool>,
+    precondicao: PreCondicao
+) -> Result<HttpResponse, ApiError>  {
+
+    let leito = buscar_leito(pool.get_ref(), id.into_inner()).await?;
+
+    Ok(precondicao.responder(&leito))
+}
+
+// Trava o leito para que a situação não mude entre a validação e a escrita e confere o
+// If-Match com a versão travada
+async fn travar_leito(
+    transaction: &mut Transaction<'_, Postgres>,
+    id: Uuid,
+    precondicao: &PreCondicao
+) -> Result<Leito, ApiError> {
+
+    sqlx::query!("SELECT id FROM leito WHERE id = $1 FOR UPDATE", id)
+        .fetch_optional(&mut *transaction)
+        .await?
+        .ok_or_else(|| ApiError::NotFound(format!("Leito {} não encontrado", id)))?;
+
+    let leito = buscar_leito(&mut *transaction, id).await?;
+    exigir_leito_ativo(id, leito.recurso.desativado_em, "id")?;
+    precondicao.exigir(leito.versao)?;
+
+    Ok(leito.recurso)
+}
+
+// Grava o leito já travado; mudanças de situação seguem as mesmas regras das transições
+async fn atualizar_leito(
+    transaction: &mut Transaction<'_, Postgres>,
+    atual: &Leito,
+    leito: &LeitoData
+) -> Result<Versionado<Leito>, ApiError> {
+
+    if atual.situacao != leito.situacao {
+        validar_mudanca_manual(transaction, atual.id, atual.situacao, leito.situacao).await?;
+    }
 
     sqlx::query!(
         r#"
         UPDATE leito
-        SET tipo = $1, situacao = $2, unidade_id = $3
-        WHERE id = $4
+        SET tipo = $1, situacao = $2, ventilador_mecanico = $3, isolamento_pressao_negativa = $4,
+            hemodialise = $5, unidade_id = $6
+        WHERE id = $7
         "#,
-        leito.tipo,
-        leito.situacao,
+        leito.tipo as TipoLeito,
+        leito.situacao as SituacaoLeito,
+        leito.ventilador_mecanico,
+        leito.isolamento_pressao_negativa,
+        leito.hemodialise,
         leito.unidade_id,
-        leito.id
+        atual.id
     )
-    .execute(pool.get_ref())
-    .await
-    .map_err(|e| {
-        eprintln!("Failed to execute query: {}", e);
-        HttpResponse::InternalServerError().finish()
-    })?;
+    .execute(&mut *transaction)
+    .await?;
 
-    Ok(HttpResponse::Ok().finish())
+    buscar_leito(&mut *transaction, atual.id).await
 }
 
-#[tracing::instrument(name = "Deletar leito", skip(req, pool))]
-pub async fn delete_leito(
-    req: web::HttpRequest,
-    pool: web::Data<PgPool>
-) -> Result<HttpResponse, HttpResponse>  {
+#[tracing::instrument(name = "Atualizar leito", skip(leito, pool, usuario, auditoria, precondicao))]
+pub async fn update_leito(
+    id: web::Path<Uuid>,
+    leito: web::Json<LeitoData>,
+    pool: web::Data<PgPool>,
+    auditoria: Auditoria,
+    usuario: web::ReqData<UsuarioAutenticado>,
+    precondicao: PreCondicao
+) -> Result<HttpResponse, ApiError>  {
 
-    let id:Uuid = req.match_info().get("id").unwrap().parse().unwrap();
+    // o escopo do leito atual é conferido na rota; o da unidade de destino, aqui
+    exigir_escopo(&usuario, pool.get_ref(), Recurso::Unidade, leito.unidade_id).await?;
 
-    sqlx::query!(
+    let mut transaction = auditoria.begin(pool.get_ref()).await?;
+    let atual = travar_leito(&mut transaction, id.into_inner(), &precondicao).await?;
+    let leito = atualizar_leito(&mut transaction, &atual, &leito).await?;
+    transaction.commit().await?;
+
+    Ok(com_etag(&leito))
+}
+
+/// Atualização parcial por JSON Merge Patch: só os campos enviados mudam.
+#[tracing::instrument(name = "Atualizar leito parcialmente", skip(patch, pool, usuario, auditoria, precondicao))]
+pub async fn patch_leito(
+    id: web::Path<Uuid>,
+    patch: web::Json<serde_json::Value>,
+    pool: web::Data<PgPool>,
+    auditoria: Auditoria,
+    usuario: web::ReqData<UsuarioAutenticado>,
+    precondicao: PreCondicao
+) -> Result<HttpResponse, ApiError>  {
+
+    let mut transaction = auditoria.begin(pool.get_ref()).await?;
+    let atual = travar_leito(&mut transaction, id.into_inner(), &precondicao).await?;
+
+    let leito: LeitoData = mesclar(&atual, &patch)?;
+    if leito.unidade_id != atual.unidade_id {
+        exigir_escopo(&usuario, pool.get_ref(), Recurso::Unidade, leito.unidade_id).await?;
+    }
+
+    let leito = atualizar_leito(&mut transaction, &atual, &leito).await?;
+    transaction.commit().await?;
+
+    Ok(com_etag(&leito))
+}
+
+#[tracing::instrument(name = "Transicionar situação do leito", skip(pool, auditoria))]
+pub async fn create_transicao_leito(
+    id: web::Path<Uuid>,
+    transicao: web::Json<TransicaoLeitoData>,
+    pool: web::Data<PgPool>,
+    auditoria: Auditoria
+) -> Result<HttpResponse, ApiError>  {
+
+    let id = id.into_inner();
+    let mut transaction = auditoria.begin(pool.get_ref()).await?;
+
+    let atual = sqlx::query!(
         r#"
-        DELETE FROM leito
+        SELECT situacao as "situacao: SituacaoLeito", desativado_em
+        FROM leito
         WHERE id = $1
+        FOR UPDATE
+        "#,
+        id
+    )
+    .fetch_one(&mut transaction)
+    .await?;
+    exigir_leito_ativo(id, atual.desativado_em, "id")?;
+
+    validar_mudanca_manual(&mut transaction, id, atual.situacao, transicao.situacao).await?;
+    let nova = transicao.situacao;
+
+    let row = sqlx::query!(
+        r#"
+        UPDATE leito
+        SET situacao = $1
+        WHERE id = $2
+        RETURNING id, tipo as "tipo: TipoLeito", situacao as "situacao: SituacaoLeito",
+                  ventilador_mecanico, isolamento_pressao_negativa, hemodialise, unidade_id, desativado_em
         "#,
+        nova as SituacaoLeito,
         id
     )
-    .execute(pool.get_ref())
-    .await
-    .map_err(|e| {
-        eprintln!("Failed to execute query: {}", e);
-        HttpResponse::InternalServerError().finish()
-    })?;
+    .fetch_one(&mut transaction)
+    .await?;
+
+    transaction.commit().await?;
+
+    let leito = Leito {
+        id: row.id,
+        tipo: row.tipo,
+        situacao: row.situacao,
+        ventilador_mecanico: row.ventilador_mecanico,
+        isolamento_pressao_negativa: row.isolamento_pressao_negativa,
+        hemodialise: row.hemodialise,
+        unidade_id: row.unidade_id,
+        desativado_em: row.desativado_em
+    };
+
+    Ok(HttpResponse::Ok().json(&leito))
+}
+
+/// Remove o leito. Paciente internado, reserva ou transferência em andamento impedem a
+/// remoção; leitos que já tiveram internações ou transferências são apenas desativados.
+#[tracing::instrument(name = "Deletar leito", skip(pool, auditoria, precondicao))]
+pub async fn delete_leito(
+    id: web::Path<Uuid>,
+    pool: web::Data<PgPool>,
+    auditoria: Auditoria,
+    precondicao: PreCondicao
+) -> Result<HttpResponse, ApiError>  {
+
+    let id = id.into_inner();
+
+    let mut transaction = auditoria.begin(pool.get_ref()).await?;
+
+    let atual = sqlx::query!("SELECT versao FROM leito WHERE id = $1 AND desativado_em IS NULL FOR UPDATE", id)
+        .fetch_optional(&mut transaction)
+        .await?
+        .ok_or_else(|| ApiError::NotFound(format!("Leito {} não encontrado", id)))?;
+    precondicao.exigir(atual.versao)?;
+
+    let dependentes = sqlx::query!(
+        r#"
+        SELECT
+            (SELECT count(*) FROM internacao WHERE leito_id = $1 AND alta_em IS NULL) as "internacoes!",
+            (SELECT count(*) FROM leito WHERE id = $1 AND situacao = 'reservado') as "reservas!",
+            (SELECT count(*) FROM transferencia
+             WHERE situacao IN ('solicitada', 'aceita', 'em_transito')
+               AND (leito_origem_id = $1 OR leito_destino_id = $1)) as "transferencias!",
+            (SELECT count(*) FROM internacao WHERE leito_id = $1)
+            + (SELECT count(*) FROM transferencia WHERE leito_origem_id = $1 OR leito_destino_id = $1) as "historico!"
+        "#,
+        id
+    )
+    .fetch_one(&mut transaction)
+    .await?;
+
+    exigir_sem_dependentes(
+        format!("Leito {} possui dependentes que impedem a remoção", id),
+        &[
+            ("internacoes", dependentes.internacoes, "paciente(s) internado(s)"),
+            ("reserva", dependentes.reservas, "reserva ativa"),
+            ("transferencias", dependentes.transferencias, "transferência(s) em andamento"),
+        ],
+    )?;
+
+    if dependentes.historico > 0 {
+        sqlx::query!("UPDATE leito SET desativado_em = now() WHERE id = $1", id)
+            .execute(&mut transaction)
+            .await?;
+    } else {
+        sqlx::query!("DELETE FROM leito WHERE id = $1", id)
+            .execute(&mut transaction)
+            .await?;
+    }
+
+    transaction.commit().await?;
 
     Ok(HttpResponse::Ok().finish())
-}
\ No newline at end of file
+}
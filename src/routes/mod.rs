@@ -4,9 +4,29 @@ mod hospitais;
 mod serializers;
 mod leitos;
 mod pacientes;
+mod internacoes;
+mod transferencias;
+mod fila;
+mod relatorios;
+mod usuarios;
+mod auditoria;
+mod dados_pessoais;
+mod covid;
+mod condicoes;
+mod importacoes;
 
 pub use ping::*;
 pub use hospitais::*;
 pub use serializers::*;
 pub use leitos::*;
-pub use pacientes::*;
\ No newline at end of file
+pub use pacientes::*;
+pub use internacoes::*;
+pub use transferencias::*;
+pub use fila::*;
+pub use relatorios::*;
+pub use usuarios::*;
+pub use auditoria::*;
+pub use dados_pessoais::*;
+pub use covid::*;
+pub use condicoes::*;
+pub use importacoes::*;
\ No newline at end of file
@@ -1,33 +1,93 @@
 use serde::{Serialize, Deserialize};
 use actix_web::{web, HttpResponse};
-use sqlx::{PgPool, types::Uuid};
+use sqlx::{Executor, PgPool, Postgres, Transaction, types::Uuid};
 use super::serializers::my_uuid;
+use crate::audit::Auditoria;
+use crate::authentication::UsuarioAutenticado;
+use crate::authorization::{exigir_escopo, Escopo, Recurso};
+use crate::concurrency::{com_etag, PreCondicao, Versionado};
+use crate::error::{ApiError, FieldError};
+use crate::patch::mesclar;
+use crate::query::{Linhas, Listagem, Ordenacao, Pagina, Paginacao, LIMITE_MAXIMO};
+use crate::spreadsheet::{Celula, Exportavel, Formato};
+use crate::domain::SituacaoCovid;
+use crate::validation::{Validacao, Validar, SEXOS};
+use super::covid::{registrar_covid, RegistroCovidData};
+use super::internacoes::internar;
+use super::hospitais::{buscar_unidade, exigir_sem_dependentes};
 
 //#[derive(serde::Deserialize)]
-#[derive(Serialize, Deserialize)]
+#[derive(Serialize, Deserialize, sqlx::FromRow)]
 pub struct Paciente {
     #[serde(with = "my_uuid")]
     pub id: Uuid,
     pub nome: String,
     pub sexo: String,
-    pub idade: String,
+    /// Ausente apenas em cadastros antigos cuja idade não era um número
+    pub idade: Option<i32>,
     pub email: String,
     pub telefone: String,
-    pub covid_19: String,
-    #[serde(with = "my_uuid")]
-    pub leito_id: Uuid
+    /// Situação do registro de COVID-19 mais recente; alterada só por `POST /pacientes/{id}/covid`
+    #[serde(default)]
+    pub situacao_covid: Option<SituacaoCovid>,
+    /// Leito da internação em aberto, se houver
+    #[serde(default)]
+    pub leito_id: Option<Uuid>
+}
+
+impl Exportavel for Paciente {
+    const COLUNAS: &'static [&'static str] =
+        &["id", "nome", "sexo", "idade", "email", "telefone", "situacao_covid", "leito_id"];
+
+    fn celulas(self) -> Vec<Celula> {
+        vec![
+            self.id.into(),
+            self.nome.into(),
+            self.sexo.into(),
+            self.idade.into(),
+            self.email.into(),
+            self.telefone.into(),
+            self.situacao_covid.as_ref().map_or(Celula::Vazia, Celula::enumeracao),
+            self.leito_id.into(),
+        ]
+    }
 }
 
 #[derive(Serialize, Deserialize)]
 pub struct PacienteData {
     pub nome: String,
     pub sexo: String,
-    pub idade: String,
+    pub idade: i32,
     pub email: String,
     pub telefone: String,
-    pub covid_19: String,
-    #[serde(with = "my_uuid")]
-    pub leito_id: Uuid
+    /// Situação inicial de COVID-19, registrada sem dados de teste
+    #[serde(default)]
+    pub situacao_covid: Option<SituacaoCovid>,
+    /// Leito da internação em aberto, se houver
+    #[serde(default)]
+    pub leito_id: Option<Uuid>
+}
+
+impl Validar for PacienteData {
+    fn regras(&self, validacao: &mut Validacao) {
+        validacao
+            .texto("nome", &self.nome)
+            .um_de("sexo", &self.sexo, SEXOS)
+            .idade("idade", Some(self.idade))
+            .email("email", &self.email)
+            .telefone("telefone", &self.telefone);
+    }
+}
+
+/// Filtros opcionais de `GET /pacientes`, combinados com `AND`. Unidade e município
+/// são os da internação em aberto.
+#[derive(Debug, Default, Deserialize)]
+pub struct FiltroPacientes {
+    pub situacao_covid: Option<SituacaoCovid>,
+    /// Pacientes com a condição notificável registrada
+    pub condicao_id: Option<Uuid>,
+    pub unidade_id: Option<Uuid>,
+    pub municipio: Option<String>,
 }
 
 #[derive(Serialize, Deserialize)]
@@ -36,9 +96,17 @@ pub struct PacienteId {
     pub id: Uuid,
 }
 
-#[derive(Deserialize)]
-pub struct PacienteNome {
-    pub nome: String,
+/// Parâmetros de `GET /pacientes/covid/{id}`.
+#[derive(Debug, Deserialize)]
+pub struct FiltroCovid {
+    pub situacao: Option<SituacaoCovid>,
+}
+
+/// Parâmetros de `GET /pacientes/busca`.
+#[derive(Debug, Deserialize)]
+pub struct BuscaPacientes {
+    pub q: String,
+    pub limit: Option<i64>,
 }
 
 #[derive(Deserialize)]
@@ -46,16 +114,26 @@ pub struct Quantidade {
     pub quantidade: i32,
 }
 
-#[tracing::instrument(name = "Criar paciente", skip(paciente, pool))]
+#[tracing::instrument(name = "Criar paciente", skip(paciente, pool, usuario, auditoria))]
 pub async fn create_paciente(
     paciente: web::Json<PacienteData>,
     pool: web::Data<PgPool>, // Renamed!
-) -> Result<HttpResponse, HttpResponse> {
-    
+    auditoria: Auditoria,
+    usuario: web::ReqData<UsuarioAutenticado>
+) -> Result<HttpResponse, ApiError> {
+
+    paciente.validar()?;
+
+    if let Some(leito_id) = paciente.leito_id {
+        exigir_escopo(&usuario, pool.get_ref(), Recurso::Leito, leito_id).await?;
+    }
+
+    let mut transaction = auditoria.begin(pool.get_ref()).await?;
+
     let row = sqlx::query!(
         r#"
-        INSERT INTO paciente (id, nome, sexo, idade, email, telefone, covid_19, leito_id)
-        VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+        INSERT INTO paciente (id, nome, sexo, idade, email, telefone, unidade_cadastro_id)
+        VALUES ($1, $2, $3, $4, $5, $6, COALESCE((SELECT unidade_id FROM leito WHERE id = $7), $8))
         RETURNING id
         "#,
         Uuid::new_v4(),
@@ -64,16 +142,29 @@ pub async fn create_paciente(
         paciente.idade,
         paciente.email,
         paciente.telefone,
-        paciente.covid_19,
-        paciente.leito_id
+        paciente.leito_id,
+        usuario.escopo().unidade_id()
     )
-    // We got rid of the double-wrapping using .app_data()
-    .fetch_one(pool.get_ref())
-    .await
-    .map_err(|e| {
-        eprintln!("Failed to execute query: {}", e);
-        HttpResponse::InternalServerError().finish()
-    })?;
+    .fetch_one(&mut transaction)
+    .await?;
+
+    if let Some(situacao) = paciente.situacao_covid {
+        let registro = RegistroCovidData {
+            situacao,
+            tipo_teste: None,
+            coletado_em: None,
+            resultado_em: None,
+            inicio_sintomas_em: None
+        };
+        registrar_covid(&mut transaction, row.id, &registro).await?;
+    }
+
+    // informar o leito no cadastro equivale a internar o paciente
+    if let Some(leito_id) = paciente.leito_id {
+        internar(&mut transaction, row.id, leito_id, None).await?;
+    }
+
+    transaction.commit().await?;
 
     let paciente = PacienteId{
         id: row.id
@@ -83,64 +174,98 @@ pub async fn create_paciente(
     //Ok(HttpResponse::Ok().finish())
 }
 
-#[tracing::instrument(name = "Listar pacientes", skip(pool))]
-pub async fn get_all_pacientes(
-    pool: web::Data<PgPool>
-) -> Result<HttpResponse, HttpResponse>  {
-    let rows = sqlx::query!(
-        r#"
-        SELECT id, nome, sexo, idade, email, telefone, covid_19, leito_id
-        FROM paciente
-        ORDER BY id
-        "#
+const ORDENACOES_PACIENTES: &[Ordenacao] = &[
+    Ordenacao { campo: "criado_em", expressao: "P.criado_em", tipo: "timestamptz" },
+    Ordenacao { campo: "nome", expressao: "P.nome", tipo: "text" },
+];
+
+async fn listar_pacientes(
+    pool: &PgPool,
+    filtro: FiltroPacientes,
+    paginacao: &Paginacao,
+    escopo: Escopo
+) -> Result<Pagina<Paciente>, ApiError> {
+
+    Listagem::new(
+        "P.id, P.nome, P.sexo, P.idade, P.email, P.telefone, CV.situacao as situacao_covid, I.leito_id",
+        r#"paciente as P
+        LEFT JOIN situacao_covid_atual as CV ON CV.paciente_id = P.id
+        LEFT JOIN internacao as I ON I.paciente_id = P.id AND I.alta_em IS NULL
+        LEFT JOIN leito as L ON L.id = I.leito_id
+        LEFT JOIN unidadeSaude as U ON U.id = L.unidade_id
+        JOIN unidade_do_paciente as PU ON PU.paciente_id = P.id
+        LEFT JOIN unidadeSaude as UP ON UP.id = PU.unidade_id"#,
+        "P.id"
     )
-    .fetch_all(pool.get_ref())
+    .filtro("CV.situacao = {}", filtro.situacao_covid)
+    .filtro("EXISTS (SELECT 1 FROM paciente_condicao as PC WHERE PC.paciente_id = P.id AND PC.condicao_id = {})", filtro.condicao_id)
+    .filtro("U.id = {}", filtro.unidade_id)
+    .filtro("lower(U.municipio) = lower({})", filtro.municipio)
+    .filtro("UP.id = {}", escopo.unidade_id())
+    .filtro("lower(UP.municipio) = lower({})", escopo.municipio())
+    .paginar(pool, ORDENACOES_PACIENTES, paginacao)
     .await
-    .map_err(|e| {
-        eprintln!("Failed to execute query: {}", e);
-        HttpResponse::InternalServerError().finish()
-    })?;
+}
 
-    let mut pacientes: Vec<Paciente> = Vec::new();
-    for row in rows {
-        let paciente = Paciente {
-            id: row.id,
-            nome: row.nome,
-            sexo: row.sexo,
-            idade: row.idade,
-            email: row.email,
-            telefone: row.telefone,
-            covid_19: row.covid_19,
-            leito_id: row.leito_id
-        };
-        pacientes.push(paciente);
-    }
+/// O escopo do usuário vale pela unidade do paciente: a da internação mais recente ou, se
+/// ele nunca foi internado, a do cadastro.
+#[tracing::instrument(name = "Listar pacientes", skip(pool, usuario))]
+pub async fn get_all_pacientes(
+    filtro: web::Query<FiltroPacientes>,
+    paginacao: web::Query<Paginacao>,
+    pool: web::Data<PgPool>,
+    usuario: web::ReqData<UsuarioAutenticado>
+) -> Result<HttpResponse, ApiError>  {
 
-    Ok(HttpResponse::Ok().json(pacientes))
+    let pagina = listar_pacientes(pool.get_ref(), filtro.into_inner(), &paginacao, usuario.escopo()).await?;
+
+    Ok(HttpResponse::Ok().json(pagina))
 }
 
-#[tracing::instrument(name = "Listar paciente por id", skip(req, pool))]
-pub async fn get_paciente_by_id(
-    req: web::HttpRequest,
+/// Pacientes com internação em aberto num leito da unidade.
+#[tracing::instrument(name = "Listar pacientes da unidade", skip(pool, usuario))]
+pub async fn get_pacientes_da_unidade(
+    id: web::Path<Uuid>,
+    filtro: web::Query<FiltroPacientes>,
+    paginacao: web::Query<Paginacao>,
+    pool: web::Data<PgPool>,
+    usuario: web::ReqData<UsuarioAutenticado>
+) -> Result<HttpResponse, ApiError>  {
+
+    let unidade = buscar_unidade(pool.get_ref(), id.into_inner()).await?.recurso;
+
+    let filtro = FiltroPacientes { unidade_id: Some(unidade.id), ..filtro.into_inner() };
+    let pagina = listar_pacientes(pool.get_ref(), filtro, &paginacao, usuario.escopo()).await?;
+
+    Ok(HttpResponse::Ok().json(pagina))
+}
+
+/// Paciente internado no leito; 404 se o leito não existir ou estiver vago.
+#[tracing::instrument(name = "Buscar paciente do leito", skip(pool))]
+pub async fn get_paciente_do_leito(
+    id: web::Path<Uuid>,
     pool: web::Data<PgPool>
-) -> Result<HttpResponse, HttpResponse>  {
+) -> Result<HttpResponse, ApiError>  {
 
-    let id:Uuid = req.match_info().get("id").unwrap().parse().unwrap();
+    let id = id.into_inner();
+
+    sqlx::query!("SELECT id FROM leito WHERE id = $1", id)
+        .fetch_optional(pool.get_ref())
+        .await?
+        .ok_or_else(|| ApiError::NotFound(format!("Leito {} não encontrado", id)))?;
 
     let row = sqlx::query!(
         r#"
-        SELECT id, nome, sexo, idade, email, telefone, covid_19, leito_id
-        FROM paciente
-        WHERE id = $1
+        SELECT P.id, P.nome, P.sexo, P.idade, P.email, P.telefone, CV.situacao as "situacao_covid?: SituacaoCovid", I.leito_id
+        FROM internacao as I JOIN paciente as P ON P.id = I.paciente_id
+        LEFT JOIN situacao_covid_atual as CV ON CV.paciente_id = P.id
+        WHERE I.leito_id = $1 AND I.alta_em IS NULL
         "#,
-        id,
+        id
     )
-    .fetch_one(pool.get_ref())
-    .await
-    .map_err(|e| {
-        eprintln!("Failed to execute query: {}", e);
-        HttpResponse::InternalServerError().finish()
-    })?;
+    .fetch_optional(pool.get_ref())
+    .await?
+    .ok_or_else(|| ApiError::NotFound(format!("Leito {} não tem paciente internado", id)))?;
 
     let paciente = Paciente {
         id: row.id,
@@ -149,35 +274,65 @@ pub async fn get_paciente_by_id(
         idade: row.idade,
         email: row.email,
         telefone: row.telefone,
-        covid_19: row.covid_19,
-        leito_id: row.leito_id
+        situacao_covid: row.situacao_covid,
+        leito_id: Some(row.leito_id)
     };
 
     Ok(HttpResponse::Ok().json(&paciente))
 }
 
-#[tracing::instrument(name = "Listar pacientes com covid", skip(req, pool))]
-pub async fn get_pacientes_covid(
-    req: web::HttpRequest,
-    pool: web::Data<PgPool>
-) -> Result<HttpResponse, HttpResponse>  {
+const LIMITE_BUSCA_PADRAO: i64 = 20;
 
-    let id:Uuid = req.match_info().get("id").unwrap().parse().unwrap();
+/// Busca pacientes pelo nome, sem diferenciar acentos e tolerando erros de digitação.
+/// Combina a busca textual com a similaridade de trigramas e ordena pela relevância.
+#[tracing::instrument(name = "Buscar pacientes por nome", skip(pool, usuario))]
+pub async fn buscar_pacientes(
+    busca: web::Query<BuscaPacientes>,
+    pool: web::Data<PgPool>,
+    usuario: web::ReqData<UsuarioAutenticado>
+) -> Result<HttpResponse, ApiError>  {
 
+    let termo = busca.q.trim();
+    if termo.is_empty() {
+        return Err(ApiError::validation(
+            "Informe o nome a buscar",
+            vec![FieldError::new("q", "vazio")],
+        ));
+    }
+    let limite = busca.limit.unwrap_or(LIMITE_BUSCA_PADRAO);
+    if !(1..=LIMITE_MAXIMO).contains(&limite) {
+        return Err(ApiError::validation(
+            format!("O limite deve estar entre 1 e {}", LIMITE_MAXIMO),
+            vec![FieldError::new("limit", "fora do intervalo")],
+        ));
+    }
+
+    let escopo = usuario.escopo();
     let rows = sqlx::query!(
         r#"
-        SELECT P.id, P.nome, P.sexo, P.idade, P.email, P.telefone, P.covid_19, P.leito_id
-        FROM (unidadesaude as U JOIN leito as L ON U.id = L.unidade_id) JOIN paciente as P ON L.id = P.leito_id
-        WHERE covid_19 = 'Sim' AND U.id = $1
+        SELECT P.id, P.nome, P.sexo, P.idade, P.email, P.telefone, CV.situacao as "situacao_covid?: SituacaoCovid", I.leito_id as "leito_id?"
+        FROM paciente as P
+        LEFT JOIN situacao_covid_atual as CV ON CV.paciente_id = P.id
+        LEFT JOIN internacao as I ON I.paciente_id = P.id AND I.alta_em IS NULL
+        JOIN unidade_do_paciente as PU ON PU.paciente_id = P.id
+        LEFT JOIN unidadeSaude as U ON U.id = PU.unidade_id,
+        LATERAL (SELECT plainto_tsquery('simple', sem_acento($1)) as consulta) as C
+        WHERE (to_tsvector('simple', sem_acento(P.nome)) @@ C.consulta
+               OR sem_acento($1) <% sem_acento(P.nome))
+          AND ($2::uuid IS NULL OR U.id = $2)
+          AND ($3::text IS NULL OR lower(U.municipio) = lower($3))
+        ORDER BY ts_rank(to_tsvector('simple', sem_acento(P.nome)), C.consulta)
+                 + word_similarity(sem_acento($1), sem_acento(P.nome)) DESC,
+                 P.id
+        LIMIT $4
         "#,
-        id,
+        termo,
+        escopo.unidade_id(),
+        escopo.municipio(),
+        limite
     )
     .fetch_all(pool.get_ref())
-    .await
-    .map_err(|e| {
-        eprintln!("Failed to execute query: {}", e);
-        HttpResponse::InternalServerError().finish()
-    })?;
+    .await?;
 
     let mut pacientes: Vec<Paciente> = Vec::new();
     for row in rows {
@@ -188,7 +343,7 @@ pub async fn get_pacientes_covid(
             idade: row.idade,
             email: row.email,
             telefone: row.telefone,
-            covid_19: row.covid_19,
+            situacao_covid: row.situacao_covid,
             leito_id: row.leito_id
         };
         pacientes.push(paciente);
@@ -197,45 +352,236 @@ pub async fn get_pacientes_covid(
     Ok(HttpResponse::Ok().json(pacientes))
 }
 
-#[tracing::instrument(name = "Atualizar paciente", skip(paciente, pool))]
-pub async fn update_paciente(
-    paciente: web::Json<Paciente>,
+/// Busca o paciente pelo id, com a situação de COVID-19 e o leito atuais, respondendo
+/// 404 se ele não existir.
+pub(crate) async fn buscar_paciente<'c, E>(executor: E, id: Uuid) -> Result<Versionado<Paciente>, ApiError>
+where
+    E: Executor<'c, Database = Postgres>,
+{
+    let row = sqlx::query!(
+        r#"
+        SELECT P.id, P.nome, P.sexo, P.idade, P.email, P.telefone, CV.situacao as "situacao_covid?: SituacaoCovid", I.leito_id as "leito_id?",
+               P.versao
+        FROM paciente as P LEFT JOIN internacao as I ON I.paciente_id = P.id AND I.alta_em IS NULL
+        LEFT JOIN situacao_covid_atual as CV ON CV.paciente_id = P.id
+        WHERE P.id = $1
+        "#,
+        id,
+    )
+    .fetch_optional(executor)
+    .await?
+    .ok_or_else(|| ApiError::NotFound(format!("Paciente {} não encontrado", id)))?;
+
+    let paciente = Paciente {
+        id: row.id,
+        nome: row.nome,
+        sexo: row.sexo,
+        idade: row.idade,
+        email: row.email,
+        telefone: row.telefone,
+        situacao_covid: row.situacao_covid,
+        leito_id: row.leito_id
+    };
+
+    Ok(Versionado { recurso: paciente, versao: row.versao })
+}
+
+#[tracing::instrument(name = "Listar paciente por id", skip(pool, precondicao))]
+pub async fn get_paciente_by_id(
+    id: web::Path<Uuid>,
+    pool: web::Data<PgPool>,
+    precondicao: PreCondicao
+) -> Result<HttpResponse, ApiError>  {
+
+    let paciente = buscar_paciente(pool.get_ref(), id.into_inner()).await?;
+
+    Ok(precondicao.responder(&paciente))
+}
+
+const ORDENACOES_COVID: &[Ordenacao] = &[
+    Ordenacao { campo: "admitido_em", expressao: "I.admitido_em", tipo: "timestamptz" },
+];
+
+/// Pacientes internados na unidade com a situação de COVID-19 informada; por padrão,
+/// os confirmados. Em JSON ou planilha conforme o `Accept`.
+#[tracing::instrument(name = "Listar pacientes com covid", skip(pool))]
+pub async fn get_pacientes_covid(
+    id: web::Path<Uuid>,
+    filtro: web::Query<FiltroCovid>,
+    formato: Formato,
     pool: web::Data<PgPool>
-) -> Result<HttpResponse, HttpResponse>  {
+) -> Result<HttpResponse, ApiError>  {
+
+    let situacao = filtro.situacao.unwrap_or(SituacaoCovid::Confirmado);
+
+    let linhas: Linhas<Paciente> = Listagem::new(
+        "P.id, P.nome, P.sexo, P.idade, P.email, P.telefone, CV.situacao as situacao_covid, I.leito_id",
+        "leito as L JOIN internacao as I ON L.id = I.leito_id AND I.alta_em IS NULL \
+         JOIN paciente as P ON P.id = I.paciente_id \
+         JOIN situacao_covid_atual as CV ON CV.paciente_id = P.id",
+        "P.id"
+    )
+    .filtro("L.unidade_id = {}", Some(id.into_inner()))
+    .filtro("CV.situacao = {}", Some(situacao))
+    .exportar(pool.get_ref(), ORDENACOES_COVID, &Paginacao::default())?;
+
+    formato.responder("pacientes_covid", linhas).await
+}
+
+// Trava o paciente, recusa a alteração de quem já foi anonimizado e confere o If-Match
+// com a versão travada
+async fn travar_paciente(
+    transaction: &mut Transaction<'_, Postgres>,
+    id: Uuid,
+    precondicao: &PreCondicao
+) -> Result<Paciente, ApiError> {
+
+    let atual = sqlx::query!(
+        "SELECT anonimizado_em, versao FROM paciente WHERE id = $1 FOR UPDATE",
+        id
+    )
+    .fetch_optional(&mut *transaction)
+    .await?
+    .ok_or_else(|| ApiError::NotFound(format!("Paciente {} não encontrado", id)))?;
+
+    // a anonimização é irreversível: os dados pessoais não podem ser preenchidos de novo
+    if atual.anonimizado_em.is_some() {
+        return Err(ApiError::Conflict {
+            message: format!("Paciente {} foi anonimizado e não pode ser alterado", id),
+            fields: Vec::new(),
+        });
+    }
+    precondicao.exigir(atual.versao)?;
+
+    Ok(buscar_paciente(&mut *transaction, id).await?.recurso)
+}
+
+// Grava os dados cadastrais do paciente já travado
+async fn atualizar_paciente(
+    transaction: &mut Transaction<'_, Postgres>,
+    atual: &Paciente,
+    paciente: &PacienteData
+) -> Result<Versionado<Paciente>, ApiError> {
+
+    // o leito só muda por internação, alta ou transferência
+    if paciente.leito_id.is_some() && paciente.leito_id != atual.leito_id {
+        return Err(ApiError::Conflict {
+            message: "O leito do paciente não pode ser alterado por atualização cadastral".into(),
+            fields: vec![FieldError::new("leito_id", "use a internação ou a alta do paciente")],
+        });
+    }
+
+    // a situação de covid só muda com um novo registro, que guarda o histórico
+    if paciente.situacao_covid.is_some() && paciente.situacao_covid != atual.situacao_covid {
+        return Err(ApiError::Conflict {
+            message: "A situação de covid do paciente não pode ser alterada por atualização cadastral".into(),
+            fields: vec![FieldError::new("situacao_covid", "use POST /pacientes/{id}/covid")],
+        });
+    }
 
-    //id, nome, sexo, idade, email, telefone, covid_19, leito_id
     sqlx::query!(
         r#"
         UPDATE paciente
-        SET nome = $1, sexo = $2, idade = $3, email = $4, telefone = $5, covid_19 = $6, leito_id = $7
-        WHERE id = $8
+        SET nome = $1, sexo = $2, idade = $3, email = $4, telefone = $5
+        WHERE id = $6
         "#,
         paciente.nome,
         paciente.sexo,
         paciente.idade,
         paciente.email,
         paciente.telefone,
-        paciente.covid_19,
-        paciente.leito_id,
-        paciente.id
+        atual.id
     )
-    .execute(pool.get_ref())
-    .await
-    .map_err(|e| {
-        eprintln!("Failed to execute query: {}", e);
-        HttpResponse::InternalServerError().finish()
-    })?;
+    .execute(&mut *transaction)
+    .await?;
 
-    Ok(HttpResponse::Ok().finish())
+    buscar_paciente(&mut *transaction, atual.id).await
 }
 
-#[tracing::instrument(name = "Deletar paciente", skip(req, pool))]
+#[tracing::instrument(name = "Atualizar paciente", skip(paciente, pool, auditoria, precondicao))]
+pub async fn update_paciente(
+    id: web::Path<Uuid>,
+    paciente: web::Json<PacienteData>,
+    pool: web::Data<PgPool>,
+    auditoria: Auditoria,
+    precondicao: PreCondicao
+) -> Result<HttpResponse, ApiError>  {
+
+    paciente.validar()?;
+
+    let mut transaction = auditoria.begin(pool.get_ref()).await?;
+    let atual = travar_paciente(&mut transaction, id.into_inner(), &precondicao).await?;
+    let paciente = atualizar_paciente(&mut transaction, &atual, &paciente).await?;
+    transaction.commit().await?;
+
+    Ok(com_etag(&paciente))
+}
+
+/// Atualização parcial por JSON Merge Patch: só os campos enviados mudam.
+#[tracing::instrument(name = "Atualizar paciente parcialmente", skip(patch, pool, auditoria, precondicao))]
+pub async fn patch_paciente(
+    id: web::Path<Uuid>,
+    patch: web::Json<serde_json::Value>,
+    pool: web::Data<PgPool>,
+    auditoria: Auditoria,
+    precondicao: PreCondicao
+) -> Result<HttpResponse, ApiError>  {
+
+    let mut transaction = auditoria.begin(pool.get_ref()).await?;
+    let atual = travar_paciente(&mut transaction, id.into_inner(), &precondicao).await?;
+
+    let paciente: PacienteData = mesclar(&atual, &patch)?;
+    paciente.validar()?;
+
+    let paciente = atualizar_paciente(&mut transaction, &atual, &paciente).await?;
+    transaction.commit().await?;
+
+    Ok(com_etag(&paciente))
+}
+
+/// Remove o paciente. Internações, transferências, passagens pela fila, registros de COVID-19
+/// e condições são prontuário e impedem a remoção; para esses pacientes, use a anonimização.
+#[tracing::instrument(name = "Deletar paciente", skip(pool, auditoria, precondicao))]
 pub async fn delete_paciente(
-    req: web::HttpRequest,
-    pool: web::Data<PgPool>
-) -> Result<HttpResponse, HttpResponse>  {
+    id: web::Path<Uuid>,
+    pool: web::Data<PgPool>,
+    auditoria: Auditoria,
+    precondicao: PreCondicao
+) -> Result<HttpResponse, ApiError>  {
+
+    let id = id.into_inner();
+    let mut transaction = auditoria.begin(pool.get_ref()).await?;
 
-    let id:Uuid = req.match_info().get("id").unwrap().parse().unwrap();
+    let atual = sqlx::query!("SELECT versao FROM paciente WHERE id = $1 FOR UPDATE", id)
+        .fetch_optional(&mut transaction)
+        .await?
+        .ok_or_else(|| ApiError::NotFound(format!("Paciente {} não encontrado", id)))?;
+    precondicao.exigir(atual.versao)?;
+
+    let historico = sqlx::query!(
+        r#"
+        SELECT
+            (SELECT count(*) FROM internacao WHERE paciente_id = $1) as "internacoes!",
+            (SELECT count(*) FROM transferencia WHERE paciente_id = $1) as "transferencias!",
+            (SELECT count(*) FROM fila_regulacao WHERE paciente_id = $1) as "fila_regulacao!",
+            (SELECT count(*) FROM registro_covid WHERE paciente_id = $1) as "registros_covid!",
+            (SELECT count(*) FROM paciente_condicao WHERE paciente_id = $1) as "condicoes!"
+        "#,
+        id
+    )
+    .fetch_one(&mut transaction)
+    .await?;
+
+    exigir_sem_dependentes(
+        format!("Paciente {} possui histórico clínico; use POST /pacientes/{}/anonimizar", id, id),
+        &[
+            ("internacoes", historico.internacoes, "internação(ões)"),
+            ("transferencias", historico.transferencias, "transferência(s)"),
+            ("fila_regulacao", historico.fila_regulacao, "entrada(s) na fila de regulação"),
+            ("registros_covid", historico.registros_covid, "registro(s) de COVID-19"),
+            ("condicoes", historico.condicoes, "condição(ões) registrada(s)"),
+        ],
+    )?;
 
     sqlx::query!(
         r#"
@@ -244,12 +590,10 @@ pub async fn delete_paciente(
         "#,
         id
     )
-    .execute(pool.get_ref())
-    .await
-    .map_err(|e| {
-        eprintln!("Failed to execute query: {}", e);
-        HttpResponse::InternalServerError().finish()
-    })?;
+    .execute(&mut transaction)
+    .await?;
+
+    transaction.commit().await?;
 
     Ok(HttpResponse::Ok().finish())
 }
\ No newline at end of file
@@ -0,0 +1,407 @@
+use serde::{Serialize, Deserialize};
+use actix_web::{web, HttpResponse};
+use chrono::{DateTime, Utc};
+use sqlx::{PgPool, types::Uuid};
+use super::serializers::my_uuid;
+use crate::authentication::UsuarioAutenticado;
+use crate::error::{ApiError, FieldError};
+use crate::domain::TipoLeito;
+
+/// Contagem de leitos de um agrupamento. Leitos bloqueados ou em manutenção
+/// contam como bloqueados e ficam fora do cálculo da taxa de ocupação.
+#[derive(Serialize, Deserialize)]
+pub struct Ocupacao {
+    pub total: i64,
+    pub ocupados: i64,
+    pub livres: i64,
+    pub bloqueados: i64,
+    /// Ocupados sobre leitos operacionais (total - bloqueados), entre 0 e 1
+    pub taxa_ocupacao: f64
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct OcupacaoUnidade {
+    #[serde(with = "my_uuid")]
+    pub unidade_id: Uuid,
+    pub nome: String,
+    pub municipio: String,
+    pub ocupacao: Ocupacao
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct OcupacaoMunicipio {
+    pub municipio: String,
+    pub ocupacao: Ocupacao
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct OcupacaoTipo {
+    pub tipo: TipoLeito,
+    pub ocupacao: Ocupacao
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct RelatorioOcupacao {
+    pub unidades: Vec<OcupacaoUnidade>,
+    pub municipios: Vec<OcupacaoMunicipio>,
+    pub tipos: Vec<OcupacaoTipo>
+}
+
+/// Pacientes internados na unidade, contados pela situação atual de COVID-19.
+#[derive(Serialize, Deserialize)]
+pub struct CovidUnidade {
+    #[serde(with = "my_uuid")]
+    pub unidade_id: Uuid,
+    pub nome: String,
+    pub municipio: String,
+    pub suspeitos: i64,
+    pub confirmados: i64,
+    pub descartados: i64,
+    pub recuperados: i64,
+    /// Internados sem nenhum registro de COVID-19
+    pub sem_registro: i64
+}
+
+#[derive(Debug, Deserialize)]
+pub struct FiltroOcupacao {
+    pub municipio: Option<String>,
+    pub tipo: Option<TipoLeito>,
+    pub unidade_id: Option<Uuid>
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Granularidade {
+    Dia,
+    Hora
+}
+
+impl Granularidade {
+    /// Unidade aceita por `date_trunc` e por `interval` no Postgres
+    fn unidade_sql(self) -> &'static str {
+        match self {
+            Granularidade::Dia => "day",
+            Granularidade::Hora => "hour",
+        }
+    }
+
+    fn duracao(self) -> chrono::Duration {
+        match self {
+            Granularidade::Dia => chrono::Duration::days(1),
+            Granularidade::Hora => chrono::Duration::hours(1),
+        }
+    }
+}
+
+/// Máximo de instantes por consulta ao histórico
+const MAX_PONTOS_HISTORICO: i64 = 2000;
+
+#[derive(Debug, Deserialize)]
+pub struct FiltroHistorico {
+    pub de: DateTime<Utc>,
+    pub ate: DateTime<Utc>,
+    pub granularidade: Option<Granularidade>,
+    pub unidade_id: Option<Uuid>
+}
+
+/// Ocupação de uma unidade ao fim do período que começa em `instante`.
+#[derive(Serialize, Deserialize)]
+pub struct PontoHistorico {
+    pub instante: DateTime<Utc>,
+    #[serde(with = "my_uuid")]
+    pub unidade_id: Uuid,
+    pub ocupacao: Ocupacao
+}
+
+#[tracing::instrument(name = "Relatório de ocupação", skip(pool, usuario))]
+pub async fn get_relatorio_ocupacao(
+    filtro: web::Query<FiltroOcupacao>,
+    pool: web::Data<PgPool>,
+    usuario: web::ReqData<UsuarioAutenticado>
+) -> Result<HttpResponse, ApiError> {
+
+    let escopo = usuario.escopo();
+
+    let unidades = sqlx::query!(
+        r#"
+        SELECT U.id, U.nome, U.municipio,
+               COUNT(L.id) as "total!",
+               COUNT(L.id) FILTER (WHERE L.situacao = 'ocupado') as "ocupados!",
+               COUNT(L.id) FILTER (WHERE L.situacao = 'livre') as "livres!",
+               COUNT(L.id) FILTER (WHERE L.situacao IN ('bloqueado', 'em_manutencao')) as "bloqueados!",
+               COALESCE(
+                   COUNT(L.id) FILTER (WHERE L.situacao = 'ocupado')::float8
+                   / NULLIF(COUNT(L.id) FILTER (WHERE L.situacao NOT IN ('bloqueado', 'em_manutencao')), 0),
+                   0
+               ) as "taxa_ocupacao!"
+        FROM unidadeSaude as U
+        LEFT JOIN leito as L ON L.unidade_id = U.id AND L.desativado_em IS NULL
+                             AND ($2::tipo_leito IS NULL OR L.tipo = $2)
+        WHERE U.desativado_em IS NULL
+          AND ($1::text IS NULL OR lower(U.municipio) = lower($1))
+          AND ($3::uuid IS NULL OR U.id = $3)
+          AND ($4::uuid IS NULL OR U.id = $4)
+          AND ($5::text IS NULL OR lower(U.municipio) = lower($5))
+        GROUP BY U.id, U.nome, U.municipio
+        ORDER BY U.municipio, U.nome
+        "#,
+        filtro.municipio,
+        filtro.tipo as Option<TipoLeito>,
+        filtro.unidade_id,
+        escopo.unidade_id(),
+        escopo.municipio()
+    )
+    .fetch_all(pool.get_ref())
+    .await?;
+
+    let municipios = sqlx::query!(
+        r#"
+        SELECT U.municipio,
+               COUNT(L.id) as "total!",
+               COUNT(L.id) FILTER (WHERE L.situacao = 'ocupado') as "ocupados!",
+               COUNT(L.id) FILTER (WHERE L.situacao = 'livre') as "livres!",
+               COUNT(L.id) FILTER (WHERE L.situacao IN ('bloqueado', 'em_manutencao')) as "bloqueados!",
+               COALESCE(
+                   COUNT(L.id) FILTER (WHERE L.situacao = 'ocupado')::float8
+                   / NULLIF(COUNT(L.id) FILTER (WHERE L.situacao NOT IN ('bloqueado', 'em_manutencao')), 0),
+                   0
+               ) as "taxa_ocupacao!"
+        FROM unidadeSaude as U
+        LEFT JOIN leito as L ON L.unidade_id = U.id AND L.desativado_em IS NULL
+                             AND ($2::tipo_leito IS NULL OR L.tipo = $2)
+        WHERE U.desativado_em IS NULL
+          AND ($1::text IS NULL OR lower(U.municipio) = lower($1))
+          AND ($3::uuid IS NULL OR U.id = $3)
+          AND ($4::uuid IS NULL OR U.id = $4)
+          AND ($5::text IS NULL OR lower(U.municipio) = lower($5))
+        GROUP BY U.municipio
+        ORDER BY U.municipio
+        "#,
+        filtro.municipio,
+        filtro.tipo as Option<TipoLeito>,
+        filtro.unidade_id,
+        escopo.unidade_id(),
+        escopo.municipio()
+    )
+    .fetch_all(pool.get_ref())
+    .await?;
+
+    let tipos = sqlx::query!(
+        r#"
+        SELECT L.tipo as "tipo: TipoLeito",
+               COUNT(*) as "total!",
+               COUNT(*) FILTER (WHERE L.situacao = 'ocupado') as "ocupados!",
+               COUNT(*) FILTER (WHERE L.situacao = 'livre') as "livres!",
+               COUNT(*) FILTER (WHERE L.situacao IN ('bloqueado', 'em_manutencao')) as "bloqueados!",
+               COALESCE(
+                   COUNT(*) FILTER (WHERE L.situacao = 'ocupado')::float8
+                   / NULLIF(COUNT(*) FILTER (WHERE L.situacao NOT IN ('bloqueado', 'em_manutencao')), 0),
+                   0
+               ) as "taxa_ocupacao!"
+        FROM leito as L JOIN unidadeSaude as U ON U.id = L.unidade_id
+        WHERE L.desativado_em IS NULL
+          AND ($1::text IS NULL OR lower(U.municipio) = lower($1))
+          AND ($2::tipo_leito IS NULL OR L.tipo = $2)
+          AND ($3::uuid IS NULL OR U.id = $3)
+          AND ($4::uuid IS NULL OR U.id = $4)
+          AND ($5::text IS NULL OR lower(U.municipio) = lower($5))
+        GROUP BY L.tipo
+        ORDER BY L.tipo
+        "#,
+        filtro.municipio,
+        filtro.tipo as Option<TipoLeito>,
+        filtro.unidade_id,
+        escopo.unidade_id(),
+        escopo.municipio()
+    )
+    .fetch_all(pool.get_ref())
+    .await?;
+
+    let relatorio = RelatorioOcupacao {
+        unidades: unidades
+            .into_iter()
+            .map(|row| OcupacaoUnidade {
+                unidade_id: row.id,
+                nome: row.nome,
+                municipio: row.municipio,
+                ocupacao: Ocupacao {
+                    total: row.total,
+                    ocupados: row.ocupados,
+                    livres: row.livres,
+                    bloqueados: row.bloqueados,
+                    taxa_ocupacao: row.taxa_ocupacao
+                }
+            })
+            .collect(),
+        municipios: municipios
+            .into_iter()
+            .map(|row| OcupacaoMunicipio {
+                municipio: row.municipio,
+                ocupacao: Ocupacao {
+                    total: row.total,
+                    ocupados: row.ocupados,
+                    livres: row.livres,
+                    bloqueados: row.bloqueados,
+                    taxa_ocupacao: row.taxa_ocupacao
+                }
+            })
+            .collect(),
+        tipos: tipos
+            .into_iter()
+            .map(|row| OcupacaoTipo {
+                tipo: row.tipo,
+                ocupacao: Ocupacao {
+                    total: row.total,
+                    ocupados: row.ocupados,
+                    livres: row.livres,
+                    bloqueados: row.bloqueados,
+                    taxa_ocupacao: row.taxa_ocupacao
+                }
+            })
+            .collect()
+    };
+
+    Ok(HttpResponse::Ok().json(&relatorio))
+}
+
+/// Internados por situação de COVID-19 em cada unidade, com os mesmos filtros do
+/// relatório de ocupação.
+#[tracing::instrument(name = "Relatório de covid", skip(pool, usuario))]
+pub async fn get_relatorio_covid(
+    filtro: web::Query<FiltroOcupacao>,
+    pool: web::Data<PgPool>,
+    usuario: web::ReqData<UsuarioAutenticado>
+) -> Result<HttpResponse, ApiError> {
+
+    let escopo = usuario.escopo();
+
+    let rows = sqlx::query!(
+        r#"
+        SELECT U.id, U.nome, U.municipio,
+               COUNT(I.id) FILTER (WHERE CV.situacao = 'suspeito') as "suspeitos!",
+               COUNT(I.id) FILTER (WHERE CV.situacao = 'confirmado') as "confirmados!",
+               COUNT(I.id) FILTER (WHERE CV.situacao = 'descartado') as "descartados!",
+               COUNT(I.id) FILTER (WHERE CV.situacao = 'recuperado') as "recuperados!",
+               COUNT(I.id) FILTER (WHERE CV.situacao IS NULL) as "sem_registro!"
+        FROM unidadeSaude as U
+        LEFT JOIN leito as L ON L.unidade_id = U.id AND L.desativado_em IS NULL
+                             AND ($2::tipo_leito IS NULL OR L.tipo = $2)
+        LEFT JOIN internacao as I ON I.leito_id = L.id AND I.alta_em IS NULL
+        LEFT JOIN situacao_covid_atual as CV ON CV.paciente_id = I.paciente_id
+        WHERE U.desativado_em IS NULL
+          AND ($1::text IS NULL OR lower(U.municipio) = lower($1))
+          AND ($3::uuid IS NULL OR U.id = $3)
+          AND ($4::uuid IS NULL OR U.id = $4)
+          AND ($5::text IS NULL OR lower(U.municipio) = lower($5))
+        GROUP BY U.id, U.nome, U.municipio
+        ORDER BY U.municipio, U.nome
+        "#,
+        filtro.municipio,
+        filtro.tipo as Option<TipoLeito>,
+        filtro.unidade_id,
+        escopo.unidade_id(),
+        escopo.municipio()
+    )
+    .fetch_all(pool.get_ref())
+    .await?;
+
+    let unidades: Vec<CovidUnidade> = rows
+        .into_iter()
+        .map(|row| CovidUnidade {
+            unidade_id: row.id,
+            nome: row.nome,
+            municipio: row.municipio,
+            suspeitos: row.suspeitos,
+            confirmados: row.confirmados,
+            descartados: row.descartados,
+            recuperados: row.recuperados,
+            sem_registro: row.sem_registro
+        })
+        .collect();
+
+    Ok(HttpResponse::Ok().json(unidades))
+}
+
+/// Reconstrói a ocupação de cada unidade ao longo do tempo a partir de `leito_evento`:
+/// em cada instante vale o último evento de cada leito até o fim do período.
+#[tracing::instrument(name = "Histórico de ocupação", skip(pool, usuario))]
+pub async fn get_historico_ocupacao(
+    filtro: web::Query<FiltroHistorico>,
+    pool: web::Data<PgPool>,
+    usuario: web::ReqData<UsuarioAutenticado>
+) -> Result<HttpResponse, ApiError> {
+
+    let escopo = usuario.escopo();
+
+    let granularidade = filtro.granularidade.unwrap_or(Granularidade::Dia);
+
+    if filtro.de > filtro.ate {
+        return Err(ApiError::validation(
+            "O início do período deve ser anterior ao fim",
+            vec![FieldError::new("de", "posterior a ate")],
+        ));
+    }
+    if (filtro.ate - filtro.de).num_seconds() / granularidade.duracao().num_seconds() >= MAX_PONTOS_HISTORICO {
+        return Err(ApiError::validation(
+            format!("O período pedido passa de {} pontos", MAX_PONTOS_HISTORICO),
+            vec![FieldError::new("granularidade", "período longo demais")],
+        ));
+    }
+
+    let rows = sqlx::query!(
+        r#"
+        WITH instantes AS (
+            SELECT generate_series(date_trunc($3::text, $1::timestamptz), date_trunc($3, $2::timestamptz), ('1 ' || $3)::interval) as instante
+        )
+        SELECT I.instante as "instante!", E.unidade_id,
+               COUNT(*) as "total!",
+               COUNT(*) FILTER (WHERE E.situacao = 'ocupado') as "ocupados!",
+               COUNT(*) FILTER (WHERE E.situacao = 'livre') as "livres!",
+               COUNT(*) FILTER (WHERE E.situacao IN ('bloqueado', 'em_manutencao')) as "bloqueados!",
+               COALESCE(
+                   COUNT(*) FILTER (WHERE E.situacao = 'ocupado')::float8
+                   / NULLIF(COUNT(*) FILTER (WHERE E.situacao NOT IN ('bloqueado', 'em_manutencao')), 0),
+                   0
+               ) as "taxa_ocupacao!"
+        FROM instantes as I
+        CROSS JOIN LATERAL (
+            SELECT DISTINCT ON (leito_id) unidade_id, situacao
+            FROM leito_evento
+            WHERE ocorrido_em < I.instante + ('1 ' || $3)::interval
+            ORDER BY leito_id, ocorrido_em DESC, id DESC
+        ) as E
+        WHERE E.situacao IS NOT NULL
+          AND ($4::uuid IS NULL OR E.unidade_id = $4)
+          AND ($5::uuid IS NULL OR E.unidade_id = $5)
+          AND ($6::text IS NULL OR E.unidade_id IN (SELECT id FROM unidadeSaude WHERE lower(municipio) = lower($6)))
+        GROUP BY I.instante, E.unidade_id
+        ORDER BY I.instante, E.unidade_id
+        "#,
+        filtro.de,
+        filtro.ate,
+        granularidade.unidade_sql(),
+        filtro.unidade_id,
+        escopo.unidade_id(),
+        escopo.municipio()
+    )
+    .fetch_all(pool.get_ref())
+    .await?;
+
+    let mut pontos: Vec<PontoHistorico> = Vec::new();
+    for row in rows {
+        let ponto = PontoHistorico {
+            instante: row.instante,
+            unidade_id: row.unidade_id,
+            ocupacao: Ocupacao {
+                total: row.total,
+                ocupados: row.ocupados,
+                livres: row.livres,
+                bloqueados: row.bloqueados,
+                taxa_ocupacao: row.taxa_ocupacao
+            }
+        };
+        pontos.push(ponto);
+    }
+
+    Ok(HttpResponse::Ok().json(pontos))
+}
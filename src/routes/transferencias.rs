@@ -0,0 +1,375 @@
+use serde::{Serialize, Deserialize};
+use actix_web::{web, HttpResponse};
+use chrono::{DateTime, Utc};
+use sqlx::{PgPool, Postgres, Transaction, types::Uuid};
+use super::serializers::my_uuid;
+use crate::audit::Auditoria;
+use crate::authentication::UsuarioAutenticado;
+use super::internacoes::{dar_alta, internar};
+use super::condicoes::exigir_leito_de_isolamento;
+use super::leitos::exigir_leito_ativo;
+use crate::error::{ApiError, FieldError};
+use crate::domain::{MotivoAlta, SituacaoLeito, SituacaoTransferencia};
+
+#[derive(Serialize, Deserialize)]
+pub struct Transferencia {
+    #[serde(with = "my_uuid")]
+    pub id: Uuid,
+    #[serde(with = "my_uuid")]
+    pub paciente_id: Uuid,
+    #[serde(with = "my_uuid")]
+    pub internacao_origem_id: Uuid,
+    #[serde(with = "my_uuid")]
+    pub leito_origem_id: Uuid,
+    #[serde(with = "my_uuid")]
+    pub unidade_destino_id: Uuid,
+    pub leito_destino_id: Option<Uuid>,
+    pub internacao_destino_id: Option<Uuid>,
+    pub situacao: SituacaoTransferencia,
+    /// Usuário que solicitou; ausente nas transferências anteriores ao login
+    pub solicitado_por: Option<Uuid>,
+    pub aceito_por: Option<Uuid>,
+    pub motivo_recusa: Option<String>,
+    pub solicitada_em: DateTime<Utc>,
+    pub aceita_em: Option<DateTime<Utc>>,
+    pub iniciada_em: Option<DateTime<Utc>>,
+    pub finalizada_em: Option<DateTime<Utc>>
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct TransferenciaData {
+    #[serde(with = "my_uuid")]
+    pub leito_origem_id: Uuid,
+    #[serde(with = "my_uuid")]
+    pub unidade_destino_id: Uuid,
+    #[serde(default)]
+    pub leito_destino_id: Option<Uuid>
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct AceiteData {
+    /// Obrigatório quando a solicitação não indicou o leito de destino
+    #[serde(default)]
+    pub leito_destino_id: Option<Uuid>
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct RecusaData {
+    pub motivo: String
+}
+
+async fn buscar_transferencia(
+    transaction: &mut Transaction<'_, Postgres>,
+    id: Uuid
+) -> Result<Transferencia, ApiError> {
+
+    let row = sqlx::query!(
+        r#"
+        SELECT id, paciente_id, internacao_origem_id, leito_origem_id, unidade_destino_id,
+               leito_destino_id, internacao_destino_id, situacao as "situacao: SituacaoTransferencia",
+               solicitado_por, aceito_por, motivo_recusa, solicitada_em, aceita_em, iniciada_em, finalizada_em
+        FROM transferencia
+        WHERE id = $1
+        FOR UPDATE
+        "#,
+        id
+    )
+    .fetch_optional(&mut *transaction)
+    .await?
+    .ok_or_else(|| ApiError::NotFound(format!("Transferência {} não encontrada", id)))?;
+
+    Ok(Transferencia {
+        id: row.id,
+        paciente_id: row.paciente_id,
+        internacao_origem_id: row.internacao_origem_id,
+        leito_origem_id: row.leito_origem_id,
+        unidade_destino_id: row.unidade_destino_id,
+        leito_destino_id: row.leito_destino_id,
+        internacao_destino_id: row.internacao_destino_id,
+        situacao: row.situacao,
+        solicitado_por: row.solicitado_por,
+        aceito_por: row.aceito_por,
+        motivo_recusa: row.motivo_recusa,
+        solicitada_em: row.solicitada_em,
+        aceita_em: row.aceita_em,
+        iniciada_em: row.iniciada_em,
+        finalizada_em: row.finalizada_em
+    })
+}
+
+// Confere se o leito de destino existe e pertence à unidade de destino
+async fn validar_leito_destino(
+    transaction: &mut Transaction<'_, Postgres>,
+    leito_id: Uuid,
+    unidade_id: Uuid
+) -> Result<SituacaoLeito, ApiError> {
+
+    let leito = sqlx::query!(
+        r#"
+        SELECT unidade_id, situacao as "situacao: SituacaoLeito", desativado_em
+        FROM leito
+        WHERE id = $1
+        FOR UPDATE
+        "#,
+        leito_id
+    )
+    .fetch_optional(&mut *transaction)
+    .await?
+    .ok_or_else(|| ApiError::UnprocessableEntity {
+        message: format!("Leito {} não existe", leito_id),
+        fields: vec![FieldError::new("leito_destino_id", "referência inválida")],
+    })?;
+
+    exigir_leito_ativo(leito_id, leito.desativado_em, "leito_destino_id")?;
+
+    if leito.unidade_id != unidade_id {
+        return Err(ApiError::validation(
+            "O leito de destino não pertence à unidade de destino",
+            vec![FieldError::new("leito_destino_id", "leito de outra unidade")],
+        ));
+    }
+
+    Ok(leito.situacao)
+}
+
+async fn atualizar_situacao_leito(
+    transaction: &mut Transaction<'_, Postgres>,
+    leito_id: Uuid,
+    situacao: SituacaoLeito
+) -> Result<(), ApiError> {
+
+    sqlx::query!(
+        "UPDATE leito SET situacao = $1 WHERE id = $2",
+        situacao as SituacaoLeito,
+        leito_id
+    )
+    .execute(&mut *transaction)
+    .await?;
+
+    Ok(())
+}
+
+#[tracing::instrument(name = "Solicitar transferência", skip(transferencia, pool, usuario))]
+pub async fn create_transferencia(
+    transferencia: web::Json<TransferenciaData>,
+    pool: web::Data<PgPool>,
+    usuario: web::ReqData<UsuarioAutenticado>
+) -> Result<HttpResponse, ApiError> {
+
+    let mut transaction = pool.begin().await?;
+
+    let internacao = sqlx::query!(
+        r#"
+        SELECT id, paciente_id
+        FROM internacao
+        WHERE leito_id = $1 AND alta_em IS NULL
+        "#,
+        transferencia.leito_origem_id
+    )
+    .fetch_optional(&mut transaction)
+    .await?
+    .ok_or_else(|| ApiError::Conflict {
+        message: format!("Leito {} não possui internação em aberto", transferencia.leito_origem_id),
+        fields: vec![FieldError::new("leito_origem_id", "leito sem paciente")],
+    })?;
+
+    if let Some(leito_destino_id) = transferencia.leito_destino_id {
+        validar_leito_destino(&mut transaction, leito_destino_id, transferencia.unidade_destino_id).await?;
+    }
+
+    let row = sqlx::query!(
+        r#"
+        INSERT INTO transferencia (id, paciente_id, internacao_origem_id, leito_origem_id,
+                                   unidade_destino_id, leito_destino_id, solicitado_por)
+        VALUES ($1, $2, $3, $4, $5, $6, $7)
+        RETURNING id
+        "#,
+        Uuid::new_v4(),
+        internacao.paciente_id,
+        internacao.id,
+        transferencia.leito_origem_id,
+        transferencia.unidade_destino_id,
+        transferencia.leito_destino_id,
+        usuario.id
+    )
+    .fetch_one(&mut transaction)
+    .await?;
+
+    let transferencia = buscar_transferencia(&mut transaction, row.id).await?;
+    transaction.commit().await?;
+
+    Ok(HttpResponse::Ok().json(&transferencia))
+}
+
+#[tracing::instrument(name = "Listar transferência por id", skip(pool))]
+pub async fn get_transferencia_by_id(
+    id: web::Path<Uuid>,
+    pool: web::Data<PgPool>
+) -> Result<HttpResponse, ApiError> {
+
+    let mut transaction = pool.begin().await?;
+    let transferencia = buscar_transferencia(&mut transaction, id.into_inner()).await?;
+    transaction.commit().await?;
+
+    Ok(HttpResponse::Ok().json(&transferencia))
+}
+
+#[tracing::instrument(name = "Aceitar transferência", skip(aceite, pool, auditoria, usuario))]
+pub async fn aceitar_transferencia(
+    id: web::Path<Uuid>,
+    aceite: web::Json<AceiteData>,
+    pool: web::Data<PgPool>,
+    auditoria: Auditoria,
+    usuario: web::ReqData<UsuarioAutenticado>
+) -> Result<HttpResponse, ApiError> {
+
+    let id = id.into_inner();
+    let mut transaction = auditoria.begin(pool.get_ref()).await?;
+
+    let transferencia = buscar_transferencia(&mut transaction, id).await?;
+    transferencia.situacao.transicionar_para(SituacaoTransferencia::Aceita)?;
+
+    let leito_destino_id = aceite.leito_destino_id
+        .or(transferencia.leito_destino_id)
+        .ok_or_else(|| ApiError::validation(
+            "O aceite precisa indicar o leito de destino",
+            vec![FieldError::new("leito_destino_id", "campo obrigatório")],
+        ))?;
+
+    // o leito de destino fica reservado para o paciente até a conclusão
+    let situacao = validar_leito_destino(&mut transaction, leito_destino_id, transferencia.unidade_destino_id).await?;
+    if situacao != SituacaoLeito::Livre {
+        return Err(ApiError::Conflict {
+            message: format!("Leito {} não está livre (situação {})", leito_destino_id, situacao),
+            fields: vec![FieldError::new("leito_destino_id", "leito indisponível")],
+        });
+    }
+    exigir_leito_de_isolamento(&mut transaction, transferencia.paciente_id, leito_destino_id, "leito_destino_id").await?;
+    let reservado = situacao.transicionar_para(SituacaoLeito::Reservado)?;
+    atualizar_situacao_leito(&mut transaction, leito_destino_id, reservado).await?;
+
+    sqlx::query!(
+        r#"
+        UPDATE transferencia
+        SET situacao = 'aceita', aceito_por = $1, leito_destino_id = $2, aceita_em = now()
+        WHERE id = $3
+        "#,
+        usuario.id,
+        leito_destino_id,
+        id
+    )
+    .execute(&mut transaction)
+    .await?;
+
+    let transferencia = buscar_transferencia(&mut transaction, id).await?;
+    transaction.commit().await?;
+
+    Ok(HttpResponse::Ok().json(&transferencia))
+}
+
+#[tracing::instrument(name = "Recusar transferência", skip(recusa, pool, auditoria))]
+pub async fn recusar_transferencia(
+    id: web::Path<Uuid>,
+    recusa: web::Json<RecusaData>,
+    pool: web::Data<PgPool>,
+    auditoria: Auditoria
+) -> Result<HttpResponse, ApiError> {
+
+    let id = id.into_inner();
+    let mut transaction = auditoria.begin(pool.get_ref()).await?;
+
+    let transferencia = buscar_transferencia(&mut transaction, id).await?;
+    transferencia.situacao.transicionar_para(SituacaoTransferencia::Recusada)?;
+
+    // desfaz a reserva feita no aceite
+    if transferencia.situacao == SituacaoTransferencia::Aceita {
+        if let Some(leito_destino_id) = transferencia.leito_destino_id {
+            let situacao = validar_leito_destino(&mut transaction, leito_destino_id, transferencia.unidade_destino_id).await?;
+            if situacao == SituacaoLeito::Reservado {
+                atualizar_situacao_leito(&mut transaction, leito_destino_id, SituacaoLeito::Livre).await?;
+            }
+        }
+    }
+
+    sqlx::query!(
+        r#"
+        UPDATE transferencia
+        SET situacao = 'recusada', motivo_recusa = $1, finalizada_em = now()
+        WHERE id = $2
+        "#,
+        recusa.motivo,
+        id
+    )
+    .execute(&mut transaction)
+    .await?;
+
+    let transferencia = buscar_transferencia(&mut transaction, id).await?;
+    transaction.commit().await?;
+
+    Ok(HttpResponse::Ok().json(&transferencia))
+}
+
+#[tracing::instrument(name = "Iniciar transporte da transferência", skip(pool, auditoria))]
+pub async fn iniciar_transferencia(
+    id: web::Path<Uuid>,
+    pool: web::Data<PgPool>,
+    auditoria: Auditoria
+) -> Result<HttpResponse, ApiError> {
+
+    let id = id.into_inner();
+    let mut transaction = auditoria.begin(pool.get_ref()).await?;
+
+    let transferencia = buscar_transferencia(&mut transaction, id).await?;
+    transferencia.situacao.transicionar_para(SituacaoTransferencia::EmTransito)?;
+
+    sqlx::query!(
+        "UPDATE transferencia SET situacao = 'em_transito', iniciada_em = now() WHERE id = $1",
+        id
+    )
+    .execute(&mut transaction)
+    .await?;
+
+    let transferencia = buscar_transferencia(&mut transaction, id).await?;
+    transaction.commit().await?;
+
+    Ok(HttpResponse::Ok().json(&transferencia))
+}
+
+/// Conclui a transferência: encerra a internação de origem e abre a de destino na mesma transação.
+#[tracing::instrument(name = "Concluir transferência", skip(pool, auditoria))]
+pub async fn concluir_transferencia(
+    id: web::Path<Uuid>,
+    pool: web::Data<PgPool>,
+    auditoria: Auditoria
+) -> Result<HttpResponse, ApiError> {
+
+    let id = id.into_inner();
+    let mut transaction = auditoria.begin(pool.get_ref()).await?;
+
+    let transferencia = buscar_transferencia(&mut transaction, id).await?;
+    transferencia.situacao.transicionar_para(SituacaoTransferencia::Concluida)?;
+
+    // o aceite garante que o leito de destino foi definido
+    let leito_destino_id = transferencia.leito_destino_id
+        .ok_or_else(|| ApiError::Internal(anyhow::anyhow!("Transferência {} em trânsito sem leito de destino", id)))?;
+
+    dar_alta(&mut transaction, transferencia.internacao_origem_id, MotivoAlta::Transferencia).await?;
+    let destino = internar(&mut transaction, transferencia.paciente_id, leito_destino_id, Some(id)).await?;
+
+    sqlx::query!(
+        r#"
+        UPDATE transferencia
+        SET situacao = 'concluida', internacao_destino_id = $1, finalizada_em = now()
+        WHERE id = $2
+        "#,
+        destino.id,
+        id
+    )
+    .execute(&mut transaction)
+    .await?;
+
+    let transferencia = buscar_transferencia(&mut transaction, id).await?;
+    transaction.commit().await?;
+
+    Ok(HttpResponse::Ok().json(&transferencia))
+}
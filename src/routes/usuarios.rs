@@ -0,0 +1,186 @@
+use serde::{Serialize, Deserialize};
+use actix_web::{web, HttpResponse};
+use chrono::{DateTime, Utc};
+use sqlx::{PgPool, types::Uuid};
+use super::serializers::my_uuid;
+use crate::authentication::{gerar_token, hash_senha, hash_token, verificar_senha, UsuarioAutenticado, ValidadeToken};
+use crate::domain::Papel;
+use crate::error::{ApiError, FieldError};
+
+const TAMANHO_MINIMO_SENHA: usize = 8;
+
+// Chave do advisory lock que serializa o cadastro do primeiro usuário
+const TRAVA_CADASTRO_INICIAL: i64 = 0x5349_474c_0001;
+
+#[derive(Deserialize)]
+pub struct UsuarioData {
+    pub nome: String,
+    pub email: String,
+    pub senha: String,
+    pub papel: Papel,
+    /// Obrigatório para funcionários de unidade
+    #[serde(default)]
+    pub unidade_id: Option<Uuid>,
+    /// Obrigatório para gestores municipais
+    #[serde(default)]
+    pub municipio: Option<String>
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct UsuarioId {
+    #[serde(with = "my_uuid")]
+    pub id: Uuid,
+}
+
+#[derive(Deserialize)]
+pub struct LoginData {
+    pub email: String,
+    pub senha: String
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct TokenAcesso {
+    pub token: String,
+    pub expira_em: DateTime<Utc>
+}
+
+// Confere se unidade e município foram informados exatamente para os papéis que os usam
+fn validar_vinculo(usuario: &UsuarioData) -> Result<(), ApiError> {
+    let precisa_unidade = usuario.papel == Papel::FuncionarioUnidade;
+    let precisa_municipio = usuario.papel == Papel::GestorMunicipal;
+
+    if precisa_unidade != usuario.unidade_id.is_some() {
+        return Err(ApiError::validation(
+            format!("O papel {} {} unidade", usuario.papel, if precisa_unidade { "exige" } else { "não aceita" }),
+            vec![FieldError::new("unidade_id", "incompatível com o papel")],
+        ));
+    }
+    if precisa_municipio != usuario.municipio.is_some() {
+        return Err(ApiError::validation(
+            format!("O papel {} {} município", usuario.papel, if precisa_municipio { "exige" } else { "não aceita" }),
+            vec![FieldError::new("municipio", "incompatível com o papel")],
+        ));
+    }
+
+    Ok(())
+}
+
+/// Cadastra um usuário. Só administradores cadastram, exceto o primeiro usuário do
+/// sistema, que é criado sem token e precisa ser administrador.
+#[tracing::instrument(name = "Cadastrar usuário", skip(usuario, pool, autenticado))]
+pub async fn create_usuario(
+    usuario: web::Json<UsuarioData>,
+    pool: web::Data<PgPool>,
+    autenticado: Option<web::ReqData<UsuarioAutenticado>>
+) -> Result<HttpResponse, ApiError> {
+
+    let usuario = usuario.into_inner();
+    if usuario.senha.chars().count() < TAMANHO_MINIMO_SENHA {
+        return Err(ApiError::validation(
+            format!("A senha deve ter pelo menos {} caracteres", TAMANHO_MINIMO_SENHA),
+            vec![FieldError::new("senha", "senha curta demais")],
+        ));
+    }
+    if autenticado.is_none() && usuario.papel != Papel::Admin {
+        return Err(ApiError::validation(
+            "O primeiro usuário precisa ser administrador",
+            vec![FieldError::new("papel", "use Admin")],
+        ));
+    }
+    validar_vinculo(&usuario)?;
+
+    let senha_hash = hash_senha(usuario.senha).await?;
+
+    let mut transaction = pool.begin().await?;
+    if autenticado.is_none() {
+        // o middleware só viu a tabela vazia; dois cadastros simultâneos sem token não
+        // podem criar dois administradores
+        sqlx::query!("SELECT pg_advisory_xact_lock($1)", TRAVA_CADASTRO_INICIAL)
+            .execute(&mut transaction)
+            .await?;
+        let existe = sqlx::query!(r#"SELECT EXISTS (SELECT 1 FROM usuario) as "existe!""#)
+            .fetch_one(&mut transaction)
+            .await?
+            .existe;
+        if existe {
+            return Err(ApiError::Unauthorized("Autenticação necessária".into()));
+        }
+    }
+
+    let row = sqlx::query!(
+        r#"
+        INSERT INTO usuario (id, nome, email, senha_hash, papel, unidade_id, municipio)
+        VALUES ($1, $2, $3, $4, $5, $6, $7)
+        RETURNING id
+        "#,
+        Uuid::new_v4(),
+        usuario.nome,
+        usuario.email.trim().to_lowercase(),
+        senha_hash,
+        usuario.papel as Papel,
+        usuario.unidade_id,
+        usuario.municipio
+    )
+    .fetch_one(&mut transaction)
+    .await?;
+    transaction.commit().await?;
+
+    Ok(HttpResponse::Ok().json(&UsuarioId { id: row.id }))
+}
+
+#[tracing::instrument(name = "Login", skip(login, pool, validade))]
+pub async fn login(
+    login: web::Json<LoginData>,
+    pool: web::Data<PgPool>,
+    validade: web::Data<ValidadeToken>
+) -> Result<HttpResponse, ApiError> {
+
+    let login = login.into_inner();
+    let credenciais_invalidas = || ApiError::Unauthorized("E-mail ou senha inválidos".into());
+
+    let usuario = sqlx::query!(
+        "SELECT id, senha_hash FROM usuario WHERE email = $1",
+        login.email.trim().to_lowercase()
+    )
+    .fetch_optional(pool.get_ref())
+    .await?
+    .ok_or_else(credenciais_invalidas)?;
+
+    if !verificar_senha(login.senha, usuario.senha_hash).await? {
+        return Err(credenciais_invalidas());
+    }
+
+    let token = gerar_token();
+    let expira_em = Utc::now() + validade.0;
+
+    sqlx::query!(
+        r#"
+        INSERT INTO token_acesso (id, usuario_id, token_hash, expira_em)
+        VALUES ($1, $2, $3, $4)
+        "#,
+        Uuid::new_v4(),
+        usuario.id,
+        hash_token(&token),
+        expira_em
+    )
+    .execute(pool.get_ref())
+    .await?;
+
+    Ok(HttpResponse::Ok().json(&TokenAcesso { token, expira_em }))
+}
+
+#[tracing::instrument(name = "Logout", skip(usuario, pool))]
+pub async fn logout(
+    usuario: web::ReqData<UsuarioAutenticado>,
+    pool: web::Data<PgPool>
+) -> Result<HttpResponse, ApiError> {
+
+    sqlx::query!(
+        "UPDATE token_acesso SET revogado_em = now() WHERE id = $1",
+        usuario.token_id
+    )
+    .execute(pool.get_ref())
+    .await?;
+
+    Ok(HttpResponse::Ok().finish())
+}
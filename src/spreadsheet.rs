@@ -0,0 +1,443 @@
+//! src/spreadsheet.rs
+use std::future::{ready, Ready};
+
+use actix_web::dev::Payload;
+use actix_web::http::header::{ACCEPT, CONTENT_DISPOSITION};
+use actix_web::web::Bytes;
+use actix_web::{FromRequest, HttpRequest, HttpResponse};
+use chrono::{DateTime, SecondsFormat, Utc};
+use csv::{ReaderBuilder, StringRecord, Trim, WriterBuilder};
+use encoding_rs::WINDOWS_1252;
+use futures::{stream, Stream, StreamExt, TryStreamExt};
+use rust_xlsxwriter::{Format, Workbook, XlsxError};
+use serde::de::{value, DeserializeOwned, IntoDeserializer};
+use serde::Serialize;
+use sqlx::types::Uuid;
+
+use crate::error::{ApiError, FieldError};
+use crate::query::Linhas;
+
+/// Tamanho máximo aceito para o corpo das importações.
+pub const TAMANHO_MAXIMO: usize = 5 * 1024 * 1024;
+
+const SEPARADOR: u8 = b';';
+const BOM_UTF8: &[u8] = b"\xEF\xBB\xBF";
+
+const TIPO_CSV: &str = "text/csv";
+const TIPO_XLSX: &str = "application/vnd.openxmlformats-officedocument.spreadsheetml.sheet";
+/// O XLSX é montado inteiro em memória antes do envio; acima disso, só em CSV.
+pub const LINHAS_MAXIMAS_XLSX: u32 = 100_000;
+
+/// Planilha CSV separada por ponto e vírgula, como as exportadas pelas planilhas estaduais.
+/// A primeira linha traz os nomes das colunas.
+pub struct Planilha {
+    colunas: Vec<String>,
+    registros: Vec<StringRecord>,
+}
+
+impl Planilha {
+    /// Lê a planilha em UTF-8 ou, se o conteúdo não for UTF-8 válido, em Latin-1
+    /// (Windows-1252, o padrão do Excel em português). Linhas em branco são ignoradas.
+    pub fn ler(conteudo: &[u8]) -> Result<Self, ApiError> {
+        let texto = decodificar(conteudo);
+        let mut leitor = ReaderBuilder::new()
+            .delimiter(SEPARADOR)
+            .flexible(true)
+            .trim(Trim::All)
+            .from_reader(texto.as_bytes());
+
+        let colunas: Vec<String> = leitor
+            .headers()
+            .map_err(erro_de_leitura)?
+            .iter()
+            .map(normalizar_coluna)
+            .collect();
+
+        let mut registros = Vec::new();
+        for registro in leitor.records() {
+            let registro = registro.map_err(erro_de_leitura)?;
+            if registro.iter().any(|valor| !valor.is_empty()) {
+                registros.push(registro);
+            }
+        }
+
+        if registros.is_empty() {
+            return Err(ApiError::validation("A planilha não tem linhas de dados", Vec::new()));
+        }
+
+        Ok(Planilha { colunas, registros })
+    }
+
+    /// Recusa a planilha quando falta alguma das colunas obrigatórias.
+    pub fn exigir_colunas(&self, obrigatorias: &[&str]) -> Result<(), ApiError> {
+        let faltando: Vec<FieldError> = obrigatorias
+            .iter()
+            .filter(|coluna| !self.tem_coluna(coluna))
+            .map(|coluna| FieldError::new(*coluna, "coluna obrigatória ausente"))
+            .collect();
+
+        if faltando.is_empty() {
+            Ok(())
+        } else {
+            Err(ApiError::validation("A planilha não tem todas as colunas obrigatórias", faltando))
+        }
+    }
+
+    pub fn tem_coluna(&self, coluna: &str) -> bool {
+        self.colunas.iter().any(|c| c == coluna)
+    }
+
+    pub fn linhas(&self) -> impl Iterator<Item = Linha<'_>> {
+        self.registros.iter().map(move |registro| Linha {
+            numero: registro.position().map_or(0, |posicao| posicao.line()),
+            colunas: &self.colunas,
+            registro,
+            erros: Vec::new(),
+        })
+    }
+}
+
+/// Linha de dados da planilha, que acumula os próprios erros.
+pub struct Linha<'a> {
+    /// Número da linha no arquivo, contando o cabeçalho como linha 1
+    pub numero: u64,
+    colunas: &'a [String],
+    registro: &'a StringRecord,
+    erros: Vec<FieldError>,
+}
+
+impl<'a> Linha<'a> {
+    /// Valor da coluna; células vazias e colunas ausentes contam como não informadas.
+    pub fn texto(&self, coluna: &str) -> Option<&'a str> {
+        let posicao = self.colunas.iter().position(|c| c == coluna)?;
+        self.registro.get(posicao).filter(|valor| !valor.is_empty())
+    }
+
+    /// Sim/não, aceitando também true/false e 1/0; vazio vale `false`.
+    pub fn booleano(&mut self, coluna: &str) -> bool {
+        match self.texto(coluna).map(str::to_lowercase).as_deref() {
+            None | Some("não") | Some("nao") | Some("n") | Some("false") | Some("0") => false,
+            Some("sim") | Some("s") | Some("true") | Some("1") => true,
+            Some(_) => {
+                self.erro(coluna, "use sim ou não");
+                false
+            }
+        }
+    }
+
+    /// Valor de uma enumeração da API, escrito como no JSON (ex.: `UtiAdulto`).
+    pub fn enumeracao<T: DeserializeOwned>(&mut self, coluna: &str) -> Option<T> {
+        let valor = self.texto(coluna)?;
+        let lido: Result<T, value::Error> = T::deserialize(valor.into_deserializer());
+        match lido {
+            Ok(valor) => Some(valor),
+            Err(_) => {
+                self.erro(coluna, format!("valor inválido: '{}'", valor));
+                None
+            }
+        }
+    }
+
+    pub fn erro(&mut self, campo: &str, mensagem: impl Into<String>) {
+        self.erros.push(FieldError::new(campo, mensagem));
+    }
+
+    /// Acrescenta os campos de um erro de validação do payload equivalente.
+    pub fn erros_de(&mut self, erro: ApiError) {
+        self.erros.extend(erro.fields().iter().cloned());
+    }
+
+    pub fn valida(&self) -> bool {
+        self.erros.is_empty()
+    }
+
+    /// Erros da linha, com o campo prefixado pelo número dela: `linhas[3].email`.
+    pub fn concluir(self) -> Vec<FieldError> {
+        let numero = self.numero;
+        self.erros
+            .into_iter()
+            .map(|erro| FieldError::new(format!("linhas[{}].{}", numero, erro.field), erro.message))
+            .collect()
+    }
+}
+
+/// Recusa a importação inteira quando alguma linha tem erro.
+pub fn exigir_linhas_validas(erros: Vec<FieldError>) -> Result<(), ApiError> {
+    if erros.is_empty() {
+        return Ok(());
+    }
+
+    let mut linhas: Vec<&str> = erros
+        .iter()
+        .filter_map(|erro| erro.field.split('.').next())
+        .collect();
+    linhas.dedup();
+    Err(ApiError::validation(
+        format!("Importação recusada: {} linha(s) com erro; nada foi gravado", linhas.len()),
+        erros,
+    ))
+}
+
+fn decodificar(conteudo: &[u8]) -> String {
+    let conteudo = conteudo.strip_prefix(BOM_UTF8).unwrap_or(conteudo);
+    match std::str::from_utf8(conteudo) {
+        Ok(texto) => texto.to_string(),
+        Err(_) => WINDOWS_1252.decode_without_bom_handling(conteudo).0.into_owned(),
+    }
+}
+
+// "Município " vira "municipio", "E-mail" vira "email" e "Ventilador mecânico" vira
+// "ventilador_mecanico"
+fn normalizar_coluna(coluna: &str) -> String {
+    coluna
+        .trim()
+        .to_lowercase()
+        .chars()
+        .filter(|c| *c != '-')
+        .map(|c| match c {
+            'á' | 'à' | 'â' | 'ã' => 'a',
+            'é' | 'ê' => 'e',
+            'í' => 'i',
+            'ó' | 'ô' | 'õ' => 'o',
+            'ú' | 'ü' => 'u',
+            'ç' => 'c',
+            ' ' => '_',
+            c => c,
+        })
+        .collect()
+}
+
+fn erro_de_leitura(erro: csv::Error) -> ApiError {
+    ApiError::validation(format!("Planilha inválida: {}", erro), Vec::new())
+}
+
+/// Formato da resposta das listagens, negociado pelo cabeçalho `Accept`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Formato {
+    Json,
+    /// `text/csv`, no mesmo formato aceito pelas importações
+    Csv,
+    Xlsx,
+}
+
+impl Formato {
+    /// Escolhe o tipo de maior peso (`q`) entre JSON, CSV e XLSX; no empate vale o que
+    /// aparece primeiro. Sem nenhum deles, inclusive com `*/*` ou sem o cabeçalho, fica o JSON.
+    fn negociar(accept: &str) -> Formato {
+        let mut escolhido = None;
+        let mut maior_peso = 0.0;
+        for tipo in accept.split(',') {
+            let mut partes = tipo.split(';').map(str::trim);
+            let formato = match partes.next().map(str::to_lowercase).as_deref() {
+                Some("application/json") => Formato::Json,
+                Some(TIPO_CSV) => Formato::Csv,
+                Some(TIPO_XLSX) => Formato::Xlsx,
+                _ => continue,
+            };
+            let peso = partes
+                .find_map(|parametro| parametro.strip_prefix("q="))
+                .and_then(|peso| peso.parse::<f32>().ok())
+                .unwrap_or(1.0);
+            if peso > maior_peso {
+                escolhido = Some(formato);
+                maior_peso = peso;
+            }
+        }
+        escolhido.unwrap_or(Formato::Json)
+    }
+
+    /// Se a resposta deve sair como planilha em vez do JSON de sempre.
+    pub fn planilha(self) -> bool {
+        self != Formato::Json
+    }
+
+    /// Responde com as linhas no formato negociado; `nome` vira o nome do arquivo
+    /// anexado. O JSON e o CSV saem em partes, à medida que as linhas chegam do banco.
+    /// O XLSX não: é um zip que só fica pronto no fim, montado em memória, e por isso
+    /// recusa mais de `LINHAS_MAXIMAS_XLSX` linhas.
+    pub async fn responder<T>(self, nome: &str, linhas: Linhas<T>) -> Result<HttpResponse, ApiError>
+    where
+        T: Serialize + Exportavel + 'static,
+    {
+        match self {
+            Formato::Json => Ok(HttpResponse::Ok()
+                .content_type("application/json")
+                .streaming(json(linhas))),
+            Formato::Csv => Ok(HttpResponse::Ok()
+                .content_type("text/csv; charset=utf-8")
+                .insert_header(anexo(nome, "csv"))
+                .streaming(csv(linhas))),
+            Formato::Xlsx => Ok(HttpResponse::Ok()
+                .content_type(TIPO_XLSX)
+                .insert_header(anexo(nome, "xlsx"))
+                .body(xlsx(linhas).await?)),
+        }
+    }
+}
+
+impl FromRequest for Formato {
+    type Config = ();
+    type Error = actix_web::Error;
+    type Future = Ready<Result<Self, Self::Error>>;
+
+    fn from_request(req: &HttpRequest, _: &mut Payload) -> Self::Future {
+        let accept = req.headers().get(ACCEPT).and_then(|valor| valor.to_str().ok());
+        ready(Ok(accept.map_or(Formato::Json, Formato::negociar)))
+    }
+}
+
+/// Valor de uma célula exportada.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Celula {
+    Texto(String),
+    Numero(f64),
+    Booleano(bool),
+    Vazia,
+}
+
+impl Celula {
+    /// Enumeração da API escrita como no JSON (ex.: `UtiAdulto`), como a importação lê.
+    pub fn enumeracao<T: Serialize>(valor: &T) -> Celula {
+        match serde_json::to_value(valor) {
+            Ok(serde_json::Value::String(texto)) => Celula::Texto(texto),
+            _ => Celula::Vazia,
+        }
+    }
+
+    // No CSV, sim/não, como a importação aceita. Texto que o Excel interpretaria como
+    // fórmula ganha um apóstrofo na frente, para ser exibido como texto.
+    fn texto(&self) -> String {
+        match self {
+            Celula::Texto(texto) if texto.starts_with(['=', '+', '-', '@', '\t', '\r']) => format!("'{}", texto),
+            Celula::Texto(texto) => texto.clone(),
+            Celula::Numero(numero) => numero.to_string(),
+            Celula::Booleano(true) => "sim".into(),
+            Celula::Booleano(false) => "não".into(),
+            Celula::Vazia => String::new(),
+        }
+    }
+}
+
+impl From<String> for Celula {
+    fn from(valor: String) -> Self {
+        Celula::Texto(valor)
+    }
+}
+
+impl From<i32> for Celula {
+    fn from(valor: i32) -> Self {
+        Celula::Numero(valor.into())
+    }
+}
+
+impl From<bool> for Celula {
+    fn from(valor: bool) -> Self {
+        Celula::Booleano(valor)
+    }
+}
+
+impl From<Uuid> for Celula {
+    fn from(valor: Uuid) -> Self {
+        Celula::Texto(valor.to_string())
+    }
+}
+
+impl From<DateTime<Utc>> for Celula {
+    fn from(valor: DateTime<Utc>) -> Self {
+        Celula::Texto(valor.to_rfc3339_opts(SecondsFormat::Secs, true))
+    }
+}
+
+impl<T: Into<Celula>> From<Option<T>> for Celula {
+    fn from(valor: Option<T>) -> Self {
+        valor.map_or(Celula::Vazia, Into::into)
+    }
+}
+
+/// Registro que pode ser exportado como uma linha de planilha.
+pub trait Exportavel {
+    /// Cabeçalho da planilha, na ordem das células
+    const COLUNAS: &'static [&'static str];
+
+    fn celulas(self) -> Vec<Celula>;
+}
+
+fn anexo(nome: &str, extensao: &str) -> (actix_web::http::header::HeaderName, String) {
+    (CONTENT_DISPOSITION, format!("attachment; filename=\"{}.{}\"", nome, extensao))
+}
+
+// Cada linha vira um pedaço do corpo. O BOM faz o Excel abrir o arquivo como UTF-8.
+fn csv<T: Exportavel>(linhas: Linhas<T>) -> impl Stream<Item = Result<Bytes, ApiError>> {
+    let mut cabecalho = BOM_UTF8.to_vec();
+    cabecalho.extend(registro_csv(T::COLUNAS.iter().map(|coluna| coluna.to_string())));
+
+    stream::once(ready(Ok(Bytes::from(cabecalho)))).chain(linhas.map_ok(|linha| {
+        Bytes::from(registro_csv(linha.celulas().iter().map(Celula::texto)))
+    }))
+}
+
+// Um array JSON escrito elemento a elemento
+fn json<T: Serialize>(linhas: Linhas<T>) -> impl Stream<Item = Result<Bytes, ApiError>> {
+    let mut primeira = true;
+    let elementos = linhas.map(move |linha| {
+        let mut corpo = if primeira { Vec::new() } else { b",".to_vec() };
+        primeira = false;
+        serde_json::to_writer(&mut corpo, &linha?).map_err(|erro| ApiError::Internal(erro.into()))?;
+        Ok(Bytes::from(corpo))
+    });
+
+    stream::once(ready(Ok(Bytes::from_static(b"["))))
+        .chain(elementos)
+        .chain(stream::once(ready(Ok(Bytes::from_static(b"]")))))
+}
+
+fn registro_csv(campos: impl Iterator<Item = String>) -> Vec<u8> {
+    let mut escritor = WriterBuilder::new().delimiter(SEPARADOR).from_writer(Vec::new());
+    escritor
+        .write_record(campos)
+        .expect("A escrita em memória não falha");
+    escritor.into_inner().expect("A escrita em memória não falha")
+}
+
+async fn xlsx<T: Exportavel>(mut linhas: Linhas<T>) -> Result<Vec<u8>, ApiError> {
+    let mut pasta = Workbook::new();
+    let planilha = pasta.add_worksheet();
+
+    let negrito = Format::new().set_bold();
+    for (coluna, nome) in T::COLUNAS.iter().enumerate() {
+        planilha
+            .write_string_with_format(0, coluna as u16, *nome, &negrito)
+            .map_err(erro_de_escrita)?;
+    }
+    planilha.set_freeze_panes(1, 0).map_err(erro_de_escrita)?;
+
+    let mut numero = 0;
+    while let Some(linha) = linhas.next().await {
+        numero += 1;
+        if numero > LINHAS_MAXIMAS_XLSX {
+            return Err(ApiError::UnprocessableEntity {
+                message: format!(
+                    "A exportação em XLSX é limitada a {} linhas; use filtros ou peça text/csv",
+                    LINHAS_MAXIMAS_XLSX
+                ),
+                fields: Vec::new(),
+            });
+        }
+        for (coluna, celula) in linha?.celulas().into_iter().enumerate() {
+            let coluna = coluna as u16;
+            match celula {
+                Celula::Texto(texto) => planilha.write_string(numero, coluna, texto),
+                Celula::Numero(valor) => planilha.write_number(numero, coluna, valor),
+                Celula::Booleano(valor) => planilha.write_boolean(numero, coluna, valor),
+                Celula::Vazia => continue,
+            }
+            .map_err(erro_de_escrita)?;
+        }
+    }
+
+    pasta.save_to_buffer().map_err(erro_de_escrita)
+}
+
+fn erro_de_escrita(erro: XlsxError) -> ApiError {
+    ApiError::Internal(erro.into())
+}
@@ -1,5 +1,10 @@
 //! src/startup.rs
+use crate::authentication::{Autenticacao, ValidadeToken};
+use crate::authorization::Autorizacao;
 use crate::configuration::{DatabaseSettings, Settings};
+use crate::error::{json_error_handler, path_error_handler, query_error_handler, route_not_found};
+use crate::patch::MERGE_PATCH;
+use crate::spreadsheet::TAMANHO_MAXIMO;
 use crate::routes::*;
 use actix_web::dev::Server;
 use actix_web::web::Data;
@@ -26,7 +31,10 @@ impl Application {
         );
         let listener = TcpListener::bind(&address)?;
         let port = listener.local_addr().unwrap().port();
-        let server = run(listener, connection_pool)?;
+        let validade_token = ValidadeToken(chrono::Duration::minutes(
+            configuration.application.token_validade_minutos,
+        ));
+        let server = run(listener, connection_pool, validade_token)?;
 
         Ok(Self { port, server })
     }
@@ -50,31 +58,90 @@ pub async fn get_connection_pool(configuration: &DatabaseSettings) -> Result<PgP
 fn run(
     listener: TcpListener,
     db_pool: PgPool,
+    validade_token: ValidadeToken,
 ) -> Result<Server, std::io::Error> {
     let db_pool = Data::new(db_pool);
+    let validade_token = Data::new(validade_token);
     let server = HttpServer::new(move || {
         App::new()
+            .wrap(Autorizacao)
+            .wrap(Autenticacao)
             .wrap(TracingLogger)
             .route("/ping", web::get().to(ping))
+            .route("/usuarios", web::post().to(create_usuario))
+            .route("/login", web::post().to(login))
+            .route("/logout", web::post().to(logout))
             .route("/unidades", web::post().to(create_unidade))
             .route("/unidades", web::get().to(get_all_unidades))
             .route("/unidades/{id}", web::get().to(get_unidade_by_id))
             .route("/leitos_disponiveis", web::get().to(get_unidades_com_leitos))
-            .route("/unidades", web::put().to(update_unidade))
+            .route("/unidades/{id}", web::put().to(update_unidade))
+            .route("/unidades/{id}", web::patch().to(patch_unidade))
             .route("/unidades/{id}", web::delete().to(delete_unidade))
+            .route("/unidades/{id}/leitos", web::get().to(get_leitos_da_unidade))
+            .route("/unidades/{id}/pacientes", web::get().to(get_pacientes_da_unidade))
+            .route("/unidades/{id}/resumo", web::get().to(get_resumo_unidade))
             .route("/leitos", web::post().to(create_leito))
             .route("/leitos", web::get().to(get_all_leitos))
             .route("/leitos/{id}", web::get().to(get_leito_by_id))
-            .route("/leitos", web::put().to(update_leito))
+            .route("/leitos/{id}", web::put().to(update_leito))
+            .route("/leitos/{id}", web::patch().to(patch_leito))
             .route("/leitos/{id}", web::delete().to(delete_leito))
+            .route("/leitos/{id}/transicoes", web::post().to(create_transicao_leito))
+            .route("/leitos/{id}/paciente", web::get().to(get_paciente_do_leito))
             .route("/pacientes", web::post().to(create_paciente))
             .route("/pacientes", web::get().to(get_all_pacientes))
+            // antes de /pacientes/{id}, que também casaria com "busca"
+            .route("/pacientes/busca", web::get().to(buscar_pacientes))
             .route("/pacientes/{id}", web::get().to(get_paciente_by_id))
-            .route("/pacientes", web::put().to(update_paciente))
+            .route("/pacientes/{id}", web::put().to(update_paciente))
+            .route("/pacientes/{id}", web::patch().to(patch_paciente))
             .route("/pacientes/{id}", web::delete().to(delete_paciente))
             .route("/pacientes/covid/{id}", web::get().to(get_pacientes_covid))
+            .route("/pacientes/{id}/internar", web::post().to(create_internacao))
+            .route("/pacientes/{id}/internacoes", web::get().to(get_internacoes_paciente))
+            .route("/pacientes/{id}/covid", web::post().to(create_registro_covid))
+            .route("/pacientes/{id}/covid", web::get().to(get_registros_covid))
+            .route("/pacientes/{id}/condicoes", web::post().to(create_condicao_paciente))
+            .route("/pacientes/{id}/condicoes", web::get().to(get_condicoes_paciente))
+            .route("/pacientes/{id}/condicoes/{condicao_id}", web::delete().to(delete_condicao_paciente))
+            .route("/pacientes/{id}/dados-pessoais", web::get().to(get_dados_pessoais))
+            .route("/pacientes/{id}/anonimizar", web::post().to(anonimizar_paciente))
+            .route("/condicoes", web::post().to(create_condicao))
+            .route("/condicoes", web::get().to(get_condicoes))
+            .route("/condicoes", web::put().to(update_condicao))
+            .route("/condicoes/{id}", web::delete().to(delete_condicao))
+            .route("/internacoes/{id}", web::get().to(get_internacao_by_id))
+            .route("/internacoes/{id}/alta", web::post().to(create_alta))
+            .route("/transferencias", web::post().to(create_transferencia))
+            .route("/transferencias/{id}", web::get().to(get_transferencia_by_id))
+            .route("/transferencias/{id}/aceitar", web::post().to(aceitar_transferencia))
+            .route("/transferencias/{id}/recusar", web::post().to(recusar_transferencia))
+            .route("/transferencias/{id}/iniciar", web::post().to(iniciar_transferencia))
+            .route("/transferencias/{id}/concluir", web::post().to(concluir_transferencia))
+            .route("/fila", web::post().to(create_entrada_fila))
+            .route("/fila", web::get().to(get_fila))
+            .route("/fila/{id}/proposta", web::get().to(get_proposta_leito))
+            .route("/fila/{id}/atender", web::post().to(atender_entrada_fila))
+            .route("/relatorios/ocupacao", web::get().to(get_relatorio_ocupacao))
+            .route("/relatorios/ocupacao/historico", web::get().to(get_historico_ocupacao))
+            .route("/relatorios/covid", web::get().to(get_relatorio_covid))
+            .route("/auditoria", web::get().to(get_auditoria))
+            .route("/importacoes/unidades", web::post().to(importar_unidades))
+            .route("/importacoes/leitos", web::post().to(importar_leitos))
             // app data
             .app_data(db_pool.clone())
+            .app_data(validade_token.clone())
+            .app_data(
+                web::JsonConfig::default()
+                    .content_type(|mime| mime.essence_str() == MERGE_PATCH)
+                    .error_handler(json_error_handler)
+            )
+            // corpo bruto só é lido pelas importações de planilhas
+            .app_data(web::PayloadConfig::new(TAMANHO_MAXIMO))
+            .app_data(web::PathConfig::default().error_handler(path_error_handler))
+            .app_data(web::QueryConfig::default().error_handler(query_error_handler))
+            .default_service(web::route().to(route_not_found))
     })
     .listen(listener)?
     .run();
@@ -0,0 +1,133 @@
+//! src/validation.rs
+use crate::error::{ApiError, FieldError};
+
+pub const SEXOS: &[&str] = &["Masculino", "Feminino", "Intersexo", "Não informado"];
+pub const IDADE_MAXIMA: i32 = 130;
+const TAMANHO_MAXIMO_TEXTO: usize = 200;
+// RFC 5321
+const TAMANHO_MAXIMO_EMAIL: usize = 254;
+
+/// Regras de validação de um payload de entrada.
+pub trait Validar {
+    fn regras(&self, validacao: &mut Validacao);
+
+    /// Aplica todas as regras e devolve um único 400 com todos os campos inválidos.
+    fn validar(&self) -> Result<(), ApiError> {
+        let mut validacao = Validacao::default();
+        self.regras(&mut validacao);
+        validacao.concluir()
+    }
+}
+
+/// Acumula os campos inválidos em vez de parar no primeiro.
+#[derive(Debug, Default)]
+pub struct Validacao {
+    campos: Vec<FieldError>,
+}
+
+impl Validacao {
+    /// Registra o campo como inválido quando a condição não for atendida.
+    pub fn regra(&mut self, campo: &str, valido: bool, mensagem: impl Into<String>) -> &mut Self {
+        if !valido {
+            self.campos.push(FieldError::new(campo, mensagem));
+        }
+        self
+    }
+
+    /// Texto não vazio e de tamanho razoável.
+    pub fn texto(&mut self, campo: &str, valor: &str) -> &mut Self {
+        if valor.trim().is_empty() {
+            return self.regra(campo, false, "campo obrigatório");
+        }
+        self.regra(
+            campo,
+            valor.chars().count() <= TAMANHO_MAXIMO_TEXTO,
+            format!("máximo de {} caracteres", TAMANHO_MAXIMO_TEXTO),
+        )
+    }
+
+    pub fn email(&mut self, campo: &str, valor: &str) -> &mut Self {
+        self.regra(campo, email_valido(valor), "email inválido")
+    }
+
+    /// Telefone brasileiro com DDD, fixo ou celular, opcionalmente com o código do país.
+    pub fn telefone(&mut self, campo: &str, valor: &str) -> &mut Self {
+        self.regra(
+            campo,
+            telefone_valido(valor),
+            "use DDD + número, ex.: (84) 99887-6543 ou (84) 3211-4455",
+        )
+    }
+
+    pub fn idade(&mut self, campo: &str, valor: Option<i32>) -> &mut Self {
+        match valor {
+            None => self.regra(campo, false, "campo obrigatório"),
+            Some(idade) => self.regra(
+                campo,
+                (0..=IDADE_MAXIMA).contains(&idade),
+                format!("deve estar entre 0 e {}", IDADE_MAXIMA),
+            ),
+        }
+    }
+
+    /// Valor restrito a uma lista fixa de opções.
+    pub fn um_de(&mut self, campo: &str, valor: &str, opcoes: &[&str]) -> &mut Self {
+        self.regra(campo, opcoes.contains(&valor), format!("use um de: {}", opcoes.join(", ")))
+    }
+
+    pub fn concluir(self) -> Result<(), ApiError> {
+        if self.campos.is_empty() {
+            Ok(())
+        } else {
+            Err(ApiError::validation("Dados inválidos", self.campos))
+        }
+    }
+}
+
+// Validação sintática simples: local@dominio.tld, sem espaços e com rótulos não vazios
+fn email_valido(email: &str) -> bool {
+    if email.len() > TAMANHO_MAXIMO_EMAIL || email.chars().any(char::is_whitespace) {
+        return false;
+    }
+    let (local, dominio) = match email.rsplit_once('@') {
+        Some(partes) => partes,
+        None => return false,
+    };
+    let rotulos: Vec<&str> = dominio.split('.').collect();
+    !local.is_empty()
+        && !local.contains('@')
+        && rotulos.len() >= 2
+        && rotulos.iter().all(|rotulo| {
+            !rotulo.is_empty()
+                && !rotulo.starts_with('-')
+                && !rotulo.ends_with('-')
+                && rotulo.chars().all(|c| c.is_ascii_alphanumeric() || c == '-')
+        })
+}
+
+// Aceita pontuação usual; depois dela restam DDD (sem zero) e número de 8 dígitos (fixo,
+// começando de 2 a 5) ou 9 dígitos (celular, começando com 9), com ou sem o prefixo 55
+fn telefone_valido(telefone: &str) -> bool {
+    let telefone = telefone.trim();
+    let telefone = telefone.strip_prefix('+').unwrap_or(telefone);
+    if !telefone.chars().all(|c| c.is_ascii_digit() || " ()-.".contains(c)) {
+        return false;
+    }
+    let digitos: String = telefone.chars().filter(char::is_ascii_digit).collect();
+    let nacional = match digitos.len() {
+        12 | 13 => match digitos.strip_prefix("55") {
+            Some(nacional) => nacional,
+            None => return false,
+        },
+        10 | 11 => digitos.as_str(),
+        _ => return false,
+    };
+    let (ddd, numero) = nacional.split_at(2);
+    let ddd_valido = !ddd.contains('0');
+    let numero_valido = match numero.len() {
+        8 => matches!(numero.as_bytes()[0], b'2'..=b'5'),
+        9 => numero.starts_with('9'),
+        _ => false,
+    };
+    ddd_valido && numero_valido
+}
@@ -0,0 +1,191 @@
+use crate::helpers::{create_app, TestApp};
+use reqwest::Response;
+use gerenciador_leitos::domain::{SituacaoCovid, SituacaoLeito, TipoLeito};
+use gerenciador_leitos::error::ErrorBody;
+use gerenciador_leitos::routes::{Leito, LeitoId, Paciente, PacienteId, UnidadeId, UnidadeSaude};
+use uuid::Uuid;
+
+async fn cria_unidade(app: &TestApp) -> Uuid {
+    let response: Response = app.post_unidade(
+        String::from("UPA Norte"),
+        String::from("upanorte@gmail.com"),
+        String::from("UPA"),
+        String::from("Natal")
+    ).await;
+    let unidade: UnidadeId = response.json().await.unwrap();
+    unidade.id
+}
+
+async fn cria_leito(app: &TestApp, unidade_id: Uuid) -> Uuid {
+    let response = app.post_leito(TipoLeito::UtiAdulto, SituacaoLeito::Livre, unidade_id).await;
+    let leito: LeitoId = response.json().await.unwrap();
+    leito.id
+}
+
+async fn cria_paciente(app: &TestApp, leito_id: Uuid) -> Uuid {
+    let response = app.post_paciente(
+        String::from("Fulano"),
+        String::from("Masculino"),
+        54,
+        String::from("fulano@gmail.com"),
+        String::from("84991223344"),
+        Some(SituacaoCovid::Suspeito),
+        leito_id
+    ).await;
+    assert_eq!(200, response.status().as_u16());
+    let paciente: PacienteId = response.json().await.unwrap();
+    paciente.id
+}
+
+// envia o ETag atual quando o recurso existe
+async fn patch(app: &TestApp, caminho: &str, body: serde_json::Value) -> Response {
+    let mut request = app.api_client
+        .patch(format!("{}{}", &app.address, caminho))
+        .header("Content-Type", "application/merge-patch+json")
+        .body(body.to_string());
+    if let Some(etag) = app.etag(caminho).await {
+        request = request.header("If-Match", etag);
+    }
+    request
+        .send()
+        .await
+        .expect("Failed to execute request.")
+}
+
+fn campos(body: &ErrorBody) -> Vec<&str> {
+    body.fields.iter().map(|f| f.field.as_str()).collect()
+}
+
+#[actix_rt::test]
+async fn patch_unidade_changes_only_sent_fields() {
+    let app = create_app().await;
+    let id = cria_unidade(&app).await;
+
+    let response = patch(&app, &format!("/unidades/{}", id), serde_json::json!({"nome": "UPA Norte II"})).await;
+    assert_eq!(200, response.status().as_u16());
+
+    let unidade: UnidadeSaude = response.json().await.unwrap();
+    assert_eq!(id, unidade.id);
+    assert_eq!("UPA Norte II", unidade.nome);
+    assert_eq!("upanorte@gmail.com", unidade.email);
+    assert_eq!("Natal", unidade.municipio);
+}
+
+#[actix_rt::test]
+async fn patch_unidade_validates_merged_document() {
+    let app = create_app().await;
+    let id = cria_unidade(&app).await;
+
+    // null remove o campo, que é obrigatório
+    let response = patch(&app, &format!("/unidades/{}", id), serde_json::json!({"nome": null})).await;
+    assert_eq!(400, response.status().as_u16());
+    let body: ErrorBody = response.json().await.unwrap();
+    assert_eq!(vec!["nome"], campos(&body));
+
+    let response = patch(&app, &format!("/unidades/{}", id), serde_json::json!({"email": "upa@"})).await;
+    assert_eq!(400, response.status().as_u16());
+    let body: ErrorBody = response.json().await.unwrap();
+    assert_eq!(vec!["email"], campos(&body));
+
+    let response = patch(&app, &format!("/unidades/{}", id), serde_json::json!(["nome"])).await;
+    assert_eq!(400, response.status().as_u16());
+}
+
+#[actix_rt::test]
+async fn patch_leito_goes_through_situacao_rules() {
+    let app = create_app().await;
+    let leito_id = cria_leito(&app, cria_unidade(&app).await).await;
+
+    let response = patch(&app, &format!("/leitos/{}", leito_id), serde_json::json!({
+        "situacao": "Bloqueado",
+        "ventilador_mecanico": true
+    })).await;
+    assert_eq!(200, response.status().as_u16());
+    let leito: Leito = response.json().await.unwrap();
+    assert_eq!(SituacaoLeito::Bloqueado, leito.situacao);
+    assert!(leito.ventilador_mecanico);
+    assert_eq!(TipoLeito::UtiAdulto, leito.tipo);
+
+    let response = patch(&app, &format!("/leitos/{}", leito_id), serde_json::json!({"situacao": "Ocupado"})).await;
+    assert_eq!(409, response.status().as_u16());
+}
+
+#[actix_rt::test]
+async fn patch_paciente_keeps_internacao_and_covid_rules() {
+    let app = create_app().await;
+    let leito_id = cria_leito(&app, cria_unidade(&app).await).await;
+    let paciente_id = cria_paciente(&app, leito_id).await;
+
+    let response = patch(&app, &format!("/pacientes/{}", paciente_id), serde_json::json!({"telefone": "84998887766"})).await;
+    assert_eq!(200, response.status().as_u16());
+    let paciente: Paciente = response.json().await.unwrap();
+    assert_eq!("84998887766", paciente.telefone);
+    assert_eq!(Some(54), paciente.idade);
+    assert_eq!(Some(SituacaoCovid::Suspeito), paciente.situacao_covid);
+    assert_eq!(Some(leito_id), paciente.leito_id);
+
+    let response = patch(&app, &format!("/pacientes/{}", paciente_id), serde_json::json!({"idade": 200})).await;
+    assert_eq!(400, response.status().as_u16());
+
+    let response = patch(&app, &format!("/pacientes/{}", paciente_id), serde_json::json!({"situacao_covid": "Confirmado"})).await;
+    assert_eq!(409, response.status().as_u16());
+}
+
+#[actix_rt::test]
+async fn put_returns_updated_resource() {
+    let app = create_app().await;
+    let unidade_id = cria_unidade(&app).await;
+
+    let response = app.api_client
+        .put(format!("{}/unidades/{}", &app.address, unidade_id))
+        .header("If-Match", app.etag(&format!("/unidades/{}", unidade_id)).await.unwrap())
+        .json(&serde_json::json!({
+            "nome": "UPA Norte II",
+            "email": "upanorte2@gmail.com",
+            "tipo": "UPA",
+            "municipio": "Parnamirim"
+        }))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    assert_eq!(200, response.status().as_u16());
+    let unidade: UnidadeSaude = response.json().await.unwrap();
+    assert_eq!("Parnamirim", unidade.municipio);
+}
+
+#[actix_rt::test]
+async fn updates_of_missing_resources_return_404() {
+    let app = create_app().await;
+    let unidade_id = cria_unidade(&app).await;
+    let id = Uuid::new_v4();
+
+    for recurso in ["unidades", "leitos", "pacientes"].iter() {
+        let response = patch(&app, &format!("/{}/{}", recurso, id), serde_json::json!({"nome": "X"})).await;
+        assert_eq!(404, response.status().as_u16(), "{}", recurso);
+    }
+
+    let response = app.api_client
+        .put(format!("{}/unidades/{}", &app.address, id))
+        .json(&serde_json::json!({
+            "nome": "UPA",
+            "email": "upa@gmail.com",
+            "tipo": "UPA",
+            "municipio": "Natal"
+        }))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    assert_eq!(404, response.status().as_u16());
+
+    let response = app.api_client
+        .put(format!("{}/leitos/{}", &app.address, id))
+        .json(&serde_json::json!({
+            "tipo": "UtiAdulto",
+            "situacao": "Livre",
+            "unidade_id": unidade_id.to_string()
+        }))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    assert_eq!(404, response.status().as_u16());
+}
@@ -0,0 +1,245 @@
+use crate::helpers::{create_app, TestApp, EMAIL_TESTE};
+use reqwest::Response;
+use gerenciador_leitos::domain::{EntidadeAuditada, OperacaoAuditada, Papel, SituacaoCovid, SituacaoLeito, TipoLeito};
+use gerenciador_leitos::routes::{UnidadeId, LeitoId, PacienteId, RegistroAuditoria, RegistroCovid};
+use uuid::Uuid;
+
+async fn cria_leito(app: &TestApp) -> Uuid {
+    let response: Response = app.post_unidade(
+        String::from("UBS Aux"),
+        String::from("ubsaux@gmail.com"),
+        String::from("UBS"),
+        String::from("Natal")
+    ).await;
+    assert_eq!(200, response.status().as_u16());
+    let unidade: UnidadeId = response.json().await.unwrap();
+
+    let response = app.post_leito(TipoLeito::UtiAdulto, SituacaoLeito::Livre, unidade.id).await;
+    assert_eq!(200, response.status().as_u16());
+    let leito: LeitoId = response.json().await.unwrap();
+    leito.id
+}
+
+async fn get_auditoria(app: &TestApp, entidade: &str, id: Uuid) -> Vec<RegistroAuditoria> {
+    let response = app.api_client
+        .get(format!("{}/auditoria?entidade={}&id={}", &app.address, entidade, id))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    assert_eq!(200, response.status().as_u16());
+    response.json().await.unwrap()
+}
+
+async fn id_usuario_teste(app: &TestApp) -> Uuid {
+    sqlx::query!("SELECT id FROM usuario WHERE email = $1", EMAIL_TESTE)
+        .fetch_one(&app.db_pool)
+        .await
+        .expect("Failed to fetch usuario.")
+        .id
+}
+
+#[actix_rt::test]
+async fn update_paciente_records_actor_route_and_diff() {
+    let app = create_app().await;
+    let leito_id = cria_leito(&app).await;
+
+    let response = app.post_paciente(
+        String::from("Fulano"),
+        String::from("Masculino"),
+        54,
+        String::from("fulano@gmail.com"),
+        String::from("84991223344"),
+        Some(SituacaoCovid::Confirmado),
+        leito_id
+    ).await;
+    assert_eq!(200, response.status().as_u16());
+    let paciente: PacienteId = response.json().await.unwrap();
+
+    let response = app.api_client
+        .put(format!("{}/pacientes/{}", &app.address, paciente.id))
+        .header("If-Match", app.etag(&format!("/pacientes/{}", paciente.id)).await.unwrap())
+        .json(&serde_json::json!({
+            "nome": "Fulano",
+            "sexo": "Masculino",
+            "idade": 55,
+            "email": "fulano@gmail.com",
+            "telefone": "84998887766",
+            "situacao_covid": "Confirmado",
+            "leito_id": leito_id.to_string()
+        }))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    assert_eq!(200, response.status().as_u16());
+
+    let registros = get_auditoria(&app, "Paciente", paciente.id).await;
+    assert_eq!(2, registros.len());
+    assert_eq!(OperacaoAuditada::Insercao, registros[0].operacao);
+    assert_eq!(Some("Fulano"), registros[0].depois.as_ref().and_then(|d| d["nome"].as_str()));
+
+    let atualizacao = &registros[1];
+    assert_eq!(EntidadeAuditada::Paciente, atualizacao.entidade);
+    assert_eq!(OperacaoAuditada::Atualizacao, atualizacao.operacao);
+    assert_eq!(Some(id_usuario_teste(&app).await), atualizacao.usuario_id);
+    assert_eq!(Some(format!("PUT /pacientes/{}", paciente.id)), atualizacao.rota);
+    // só as colunas alteradas entram no diff
+    assert_eq!(
+        serde_json::json!({"idade": 54, "telefone": "84991223344"}),
+        atualizacao.antes.clone().unwrap()
+    );
+    assert_eq!(
+        serde_json::json!({"idade": 55, "telefone": "84998887766"}),
+        atualizacao.depois.clone().unwrap()
+    );
+}
+
+#[actix_rt::test]
+async fn delete_leito_keeps_audit_trail() {
+    let app = create_app().await;
+    let leito_id = cria_leito(&app).await;
+
+    let response = app.api_client
+        .delete(format!("{}/leitos/{}", &app.address, leito_id))
+        .header("If-Match", app.etag(&format!("/leitos/{}", leito_id)).await.unwrap())
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    assert_eq!(200, response.status().as_u16());
+
+    let registros = get_auditoria(&app, "Leito", leito_id).await;
+    let operacoes: Vec<OperacaoAuditada> = registros.iter().map(|r| r.operacao).collect();
+    assert_eq!(vec![OperacaoAuditada::Insercao, OperacaoAuditada::Remocao], operacoes);
+
+    let remocao = &registros[1];
+    assert!(remocao.depois.is_none());
+    assert_eq!(Some("livre"), remocao.antes.as_ref().and_then(|a| a["situacao"].as_str()));
+    assert_eq!(Some(format!("DELETE /leitos/{}", leito_id)), remocao.rota);
+}
+
+#[actix_rt::test]
+async fn registro_covid_records_actor_and_route() {
+    let app = create_app().await;
+    let leito_id = cria_leito(&app).await;
+
+    let response = app.post_paciente(
+        String::from("Fulano"),
+        String::from("Masculino"),
+        54,
+        String::from("fulano@gmail.com"),
+        String::from("84991223344"),
+        Some(SituacaoCovid::Suspeito),
+        leito_id
+    ).await;
+    let paciente: PacienteId = response.json().await.unwrap();
+
+    let response = app.api_client
+        .post(format!("{}/pacientes/{}/covid", &app.address, paciente.id))
+        .json(&serde_json::json!({"situacao": "Confirmado"}))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    assert_eq!(200, response.status().as_u16());
+    let registro: RegistroCovid = response.json().await.unwrap();
+
+    let registros = get_auditoria(&app, "RegistroCovid", registro.id).await;
+    assert_eq!(1, registros.len());
+    assert_eq!(EntidadeAuditada::RegistroCovid, registros[0].entidade);
+    assert_eq!(OperacaoAuditada::Insercao, registros[0].operacao);
+    assert_eq!(Some("confirmado"), registros[0].depois.as_ref().and_then(|d| d["situacao"].as_str()));
+    assert_eq!(Some(id_usuario_teste(&app).await), registros[0].usuario_id);
+    assert_eq!(Some(format!("POST /pacientes/{}/covid", paciente.id)), registros[0].rota);
+}
+
+#[actix_rt::test]
+async fn condicoes_do_paciente_are_audited_under_the_paciente() {
+    let app = create_app().await;
+    let response = app.api_client
+        .post(format!("{}/pacientes", &app.address))
+        .json(&serde_json::json!({
+            "nome": "Fulano",
+            "sexo": "Masculino",
+            "idade": 54,
+            "email": "fulano@gmail.com",
+            "telefone": "84991223344"
+        }))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    let paciente: PacienteId = response.json().await.unwrap();
+    let condicao_id = sqlx::query!("SELECT id FROM condicao_notificavel WHERE nome = 'Dengue'")
+        .fetch_one(&app.db_pool)
+        .await
+        .expect("Failed to fetch condicao.")
+        .id;
+
+    let caminho = format!("/pacientes/{}/condicoes", paciente.id);
+    let response = app.api_client
+        .post(format!("{}{}", &app.address, caminho))
+        .json(&serde_json::json!({"condicao_id": condicao_id.to_string()}))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    assert_eq!(200, response.status().as_u16());
+    let response = app.api_client
+        .delete(format!("{}{}/{}", &app.address, caminho, condicao_id))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    assert_eq!(200, response.status().as_u16());
+
+    let registros = get_auditoria(&app, "PacienteCondicao", paciente.id).await;
+    let operacoes: Vec<OperacaoAuditada> = registros.iter().map(|r| r.operacao).collect();
+    assert_eq!(vec![OperacaoAuditada::Insercao, OperacaoAuditada::Remocao], operacoes);
+    let condicao = condicao_id.to_string();
+    assert_eq!(Some(condicao.as_str()), registros[0].depois.as_ref().and_then(|d| d["condicao_id"].as_str()));
+    assert_eq!(Some(condicao.as_str()), registros[1].antes.as_ref().and_then(|a| a["condicao_id"].as_str()));
+    assert_eq!(Some(id_usuario_teste(&app).await), registros[1].usuario_id);
+    assert_eq!(Some(format!("DELETE {}/{}", caminho, condicao_id)), registros[1].rota);
+}
+
+#[actix_rt::test]
+async fn rejected_write_leaves_no_audit_record() {
+    let app = create_app().await;
+    let leito_id = cria_leito(&app).await;
+
+    // Livre -> Ocupado só acontece pela internação; a transação é desfeita
+    let response = app.api_client
+        .post(format!("{}/leitos/{}/transicoes", &app.address, leito_id))
+        .json(&serde_json::json!({"situacao": "Ocupado"}))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    assert!(response.status().is_client_error());
+
+    let registros = get_auditoria(&app, "Leito", leito_id).await;
+    assert_eq!(1, registros.len());
+}
+
+#[actix_rt::test]
+async fn auditoria_is_append_only() {
+    let app = create_app().await;
+    cria_leito(&app).await;
+
+    let resultado = sqlx::query("UPDATE auditoria SET rota = 'adulterada'")
+        .execute(&app.db_pool)
+        .await;
+    assert!(resultado.is_err());
+
+    let resultado = sqlx::query("DELETE FROM auditoria")
+        .execute(&app.db_pool)
+        .await;
+    assert!(resultado.is_err());
+}
+
+#[actix_rt::test]
+async fn get_auditoria_requires_admin() {
+    let app = create_app().await;
+    let client = app.client_com_papel(Papel::ReguladorEstadual, None, None).await;
+
+    let response = client
+        .get(format!("{}/auditoria", &app.address))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    assert_eq!(403, response.status().as_u16());
+}
@@ -0,0 +1,311 @@
+use crate::helpers::{create_app, TestApp};
+use std::collections::HashMap;
+use reqwest::Response;
+use gerenciador_leitos::domain::{MotivoAlta, Papel, SituacaoLeito, TipoLeito};
+use gerenciador_leitos::routes::{Internacao, LeitoId, Paciente, PacienteId, UnidadeId};
+use uuid::Uuid;
+use gerenciador_leitos::query::Pagina;
+
+async fn cria_unidade(app: &TestApp, nome: &str, municipio: &str) -> Uuid {
+    let response: Response = app.post_unidade(
+        String::from(nome),
+        format!("{}@gmail.com", nome.to_lowercase().replace(' ', "")),
+        String::from("UPA"),
+        String::from(municipio)
+    ).await;
+    assert_eq!(200, response.status().as_u16());
+    let unidade: UnidadeId = response.json().await.unwrap();
+    unidade.id
+}
+
+async fn cria_leito(app: &TestApp, unidade_id: Uuid) -> Uuid {
+    let response = app.post_leito(TipoLeito::UtiAdulto, SituacaoLeito::Livre, unidade_id).await;
+    assert_eq!(200, response.status().as_u16());
+    let leito: LeitoId = response.json().await.unwrap();
+    leito.id
+}
+
+async fn post_leito_com(client: &reqwest::Client, app: &TestApp, unidade_id: Uuid) -> Response {
+    let id = unidade_id.to_string();
+    let mut map = HashMap::new();
+    map.insert("tipo", "EnfermariaClinica");
+    map.insert("situacao", "Livre");
+    map.insert("unidade_id", &id);
+
+    client
+        .post(format!("{}/leitos", &app.address))
+        .json(&map)
+        .send()
+        .await
+        .expect("Failed to execute request.")
+}
+
+#[actix_rt::test]
+async fn funcionario_unidade_creates_leito_only_in_own_unidade() {
+    let app = create_app().await;
+    let propria = cria_unidade(&app, "UPA Norte", "Natal").await;
+    let outra = cria_unidade(&app, "UPA Sul", "Natal").await;
+    let client = app.client_com_papel(Papel::FuncionarioUnidade, Some(propria), None).await;
+
+    let response = post_leito_com(&client, &app, propria).await;
+    assert_eq!(200, response.status().as_u16());
+
+    let response = post_leito_com(&client, &app, outra).await;
+    assert_eq!(403, response.status().as_u16());
+}
+
+#[actix_rt::test]
+async fn funcionario_unidade_only_sees_own_leitos() {
+    let app = create_app().await;
+    let propria = cria_unidade(&app, "UPA Norte", "Natal").await;
+    let outra = cria_unidade(&app, "UPA Sul", "Natal").await;
+    let leito_proprio = cria_leito(&app, propria).await;
+    let leito_alheio = cria_leito(&app, outra).await;
+    let client = app.client_com_papel(Papel::FuncionarioUnidade, Some(propria), None).await;
+
+    let response = client
+        .get(format!("{}/leitos", &app.address))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    assert_eq!(200, response.status().as_u16());
+    let leitos: Vec<serde_json::Value> = response.json::<Pagina<serde_json::Value>>().await.unwrap().itens;
+    assert_eq!(1, leitos.len());
+    assert_eq!(leito_proprio.to_string(), leitos[0]["id"]);
+
+    let response = client
+        .get(format!("{}/leitos/{}", &app.address, leito_alheio))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    assert_eq!(403, response.status().as_u16());
+}
+
+#[actix_rt::test]
+async fn funcionario_unidade_cannot_manage_unidades() {
+    let app = create_app().await;
+    let propria = cria_unidade(&app, "UPA Norte", "Natal").await;
+    let client = app.client_com_papel(Papel::FuncionarioUnidade, Some(propria), None).await;
+
+    let response = client
+        .delete(format!("{}/unidades/{}", &app.address, propria))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    assert_eq!(403, response.status().as_u16());
+}
+
+#[actix_rt::test]
+async fn gestor_municipal_is_limited_to_own_municipio() {
+    let app = create_app().await;
+    let natal = cria_unidade(&app, "UPA Natal", "Natal").await;
+    let mossoro = cria_unidade(&app, "UPA Mossoro", "Mossoró").await;
+    let client = app.client_com_papel(Papel::GestorMunicipal, None, Some("natal")).await;
+
+    let response = client
+        .get(format!("{}/unidades", &app.address))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    assert_eq!(200, response.status().as_u16());
+    let unidades: Vec<serde_json::Value> = response.json::<Pagina<serde_json::Value>>().await.unwrap().itens;
+    assert_eq!(1, unidades.len());
+    assert_eq!(natal.to_string(), unidades[0]["id"]);
+
+    let response = client
+        .get(format!("{}/unidades/{}", &app.address, mossoro))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    assert_eq!(403, response.status().as_u16());
+
+    // gestores só consultam
+    let response = post_leito_com(&client, &app, natal).await;
+    assert_eq!(403, response.status().as_u16());
+}
+
+#[actix_rt::test]
+async fn regulador_estadual_accesses_fila_but_not_unidades() {
+    let app = create_app().await;
+    let client = app.client_com_papel(Papel::ReguladorEstadual, None, None).await;
+
+    let response = client
+        .get(format!("{}/fila", &app.address))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    assert_eq!(200, response.status().as_u16());
+
+    let response = client
+        .post(format!("{}/unidades", &app.address))
+        .json(&serde_json::json!({
+            "nome": "UPA Leste",
+            "email": "upaleste@gmail.com",
+            "tipo": "UPA",
+            "municipio": "Natal"
+        }))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    assert_eq!(403, response.status().as_u16());
+}
+
+#[actix_rt::test]
+async fn funcionario_unidade_cannot_access_fila() {
+    let app = create_app().await;
+    let propria = cria_unidade(&app, "UPA Norte", "Natal").await;
+    let client = app.client_com_papel(Papel::FuncionarioUnidade, Some(propria), None).await;
+
+    let response = client
+        .get(format!("{}/fila", &app.address))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    assert_eq!(403, response.status().as_u16());
+}
+
+#[actix_rt::test]
+async fn only_admin_creates_usuarios() {
+    let app = create_app().await;
+    let client = app.client_com_papel(Papel::ReguladorEstadual, None, None).await;
+
+    let response = client
+        .post(format!("{}/usuarios", &app.address))
+        .json(&serde_json::json!({
+            "nome": "Outro",
+            "email": "outro@sigleitos.com",
+            "senha": "12345678",
+            "papel": "Admin"
+        }))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    assert_eq!(403, response.status().as_u16());
+}
+
+#[actix_rt::test]
+async fn create_usuario_with_papel_missing_vinculo_returns_400() {
+    let app = create_app().await;
+
+    let casos = vec![
+        (serde_json::json!({"papel": "FuncionarioUnidade"}), "unidade_id"),
+        (serde_json::json!({"papel": "GestorMunicipal"}), "municipio"),
+        (serde_json::json!({"papel": "ReguladorEstadual", "municipio": "Natal"}), "municipio"),
+    ];
+
+    for (mut body, campo) in casos {
+        body["nome"] = "Fulano".into();
+        body["email"] = "fulano@sigleitos.com".into();
+        body["senha"] = "12345678".into();
+
+        let response = app.api_client
+            .post(format!("{}/usuarios", &app.address))
+            .json(&body)
+            .send()
+            .await
+            .expect("Failed to execute request.");
+        assert_eq!(400, response.status().as_u16(), "campo {}", campo);
+        let erro: serde_json::Value = response.json().await.unwrap();
+        assert_eq!(campo, erro["fields"][0]["field"]);
+    }
+}
+
+async fn get_com(client: &reqwest::Client, app: &TestApp, caminho: &str) -> Response {
+    client
+        .get(format!("{}{}", &app.address, caminho))
+        .send()
+        .await
+        .expect("Failed to execute request.")
+}
+
+async fn cria_paciente_sem_leito(client: &reqwest::Client, app: &TestApp, nome: &str) -> Uuid {
+    let response = client
+        .post(format!("{}/pacientes", &app.address))
+        .json(&serde_json::json!({
+            "nome": nome,
+            "sexo": "Feminino",
+            "idade": 40,
+            "email": "paciente@gmail.com",
+            "telefone": "84991223344"
+        }))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    assert_eq!(200, response.status().as_u16());
+    let paciente: PacienteId = response.json().await.unwrap();
+    paciente.id
+}
+
+async fn ids_listados(client: &reqwest::Client, app: &TestApp) -> Vec<Uuid> {
+    let pagina: Pagina<Paciente> = get_com(client, app, "/pacientes").await.json().await.unwrap();
+    pagina.itens.iter().map(|paciente| paciente.id).collect()
+}
+
+#[actix_rt::test]
+async fn discharged_paciente_stays_in_unidade_of_last_internacao() {
+    let app = create_app().await;
+    let propria = cria_unidade(&app, "UPA Norte", "Natal").await;
+    let outra = cria_unidade(&app, "UPA Sul", "Natal").await;
+    let leito_id = cria_leito(&app, propria).await;
+    let response = app.post_paciente(
+        String::from("Fulana"),
+        String::from("Feminino"),
+        40,
+        String::from("fulana@gmail.com"),
+        String::from("84991223344"),
+        None,
+        leito_id
+    ).await;
+    let paciente: PacienteId = response.json().await.unwrap();
+
+    let internacoes: Vec<Internacao> = app.api_client
+        .get(format!("{}/pacientes/{}/internacoes", &app.address, paciente.id))
+        .send()
+        .await
+        .expect("Failed to execute request.")
+        .json()
+        .await
+        .unwrap();
+    let response = app.post_alta(internacoes[0].id, MotivoAlta::AltaMedica).await;
+    assert_eq!(200, response.status().as_u16());
+
+    let caminho = format!("/pacientes/{}", paciente.id);
+    let da_outra = app.client_com_papel(Papel::FuncionarioUnidade, Some(outra), None).await;
+    assert_eq!(403, get_com(&da_outra, &app, &caminho).await.status().as_u16());
+    let response = da_outra
+        .post(format!("{}/pacientes/{}/internar", &app.address, paciente.id))
+        .json(&serde_json::json!({"leito_id": cria_leito(&app, outra).await}))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    assert_eq!(403, response.status().as_u16());
+    assert!(ids_listados(&da_outra, &app).await.is_empty());
+
+    let da_propria = app.client_com_papel(Papel::FuncionarioUnidade, Some(propria), None).await;
+    assert_eq!(200, get_com(&da_propria, &app, &caminho).await.status().as_u16());
+    assert_eq!(vec![paciente.id], ids_listados(&da_propria, &app).await);
+
+    let gestor = app.client_com_papel(Papel::GestorMunicipal, None, Some("Mossoró")).await;
+    assert_eq!(403, get_com(&gestor, &app, &caminho).await.status().as_u16());
+}
+
+#[actix_rt::test]
+async fn paciente_never_admitted_belongs_to_unidade_that_registered_it() {
+    let app = create_app().await;
+    let propria = cria_unidade(&app, "UPA Norte", "Natal").await;
+    let outra = cria_unidade(&app, "UPA Sul", "Natal").await;
+    let da_propria = app.client_com_papel(Papel::FuncionarioUnidade, Some(propria), None).await;
+    let da_outra = app.client_com_papel(Papel::FuncionarioUnidade, Some(outra), None).await;
+
+    let paciente_id = cria_paciente_sem_leito(&da_propria, &app, "Fulana").await;
+    let caminho = format!("/pacientes/{}", paciente_id);
+    assert_eq!(200, get_com(&da_propria, &app, &caminho).await.status().as_u16());
+    assert_eq!(403, get_com(&da_outra, &app, &caminho).await.status().as_u16());
+
+    // cadastrado pelo admin, sem leito, não pertence a nenhuma unidade
+    let sem_unidade = cria_paciente_sem_leito(&app.api_client, &app, "Beltrana").await;
+    let caminho = format!("/pacientes/{}", sem_unidade);
+    assert_eq!(403, get_com(&da_propria, &app, &caminho).await.status().as_u16());
+    assert_eq!(vec![paciente_id], ids_listados(&da_propria, &app).await);
+    assert_eq!(200, get_com(&app.api_client, &app, &caminho).await.status().as_u16());
+}
@@ -0,0 +1,130 @@
+use crate::helpers::{create_app, TestApp};
+use reqwest::Response;
+use gerenciador_leitos::domain::{Papel, SituacaoLeito, TipoLeito};
+use gerenciador_leitos::error::ErrorBody;
+use gerenciador_leitos::routes::{LeitoId, Paciente, PacienteId, UnidadeId};
+use uuid::Uuid;
+
+async fn cria_unidade(app: &TestApp, nome: &str) -> Uuid {
+    let response: Response = app.post_unidade(
+        String::from(nome),
+        format!("{}@gmail.com", nome.to_lowercase().replace(' ', "")),
+        String::from("UPA"),
+        String::from("Natal")
+    ).await;
+    let unidade: UnidadeId = response.json().await.unwrap();
+    unidade.id
+}
+
+// cadastra o paciente internado num leito novo da unidade
+async fn cria_paciente(app: &TestApp, nome: &str, unidade_id: Uuid) -> Uuid {
+    let response = app.post_leito(TipoLeito::UtiAdulto, SituacaoLeito::Livre, unidade_id).await;
+    let leito: LeitoId = response.json().await.unwrap();
+
+    let response = app.post_paciente(
+        String::from(nome),
+        String::from("Feminino"),
+        40,
+        String::from("paciente@gmail.com"),
+        String::from("84991223344"),
+        None,
+        leito.id
+    ).await;
+    assert_eq!(200, response.status().as_u16());
+    let paciente: PacienteId = response.json().await.unwrap();
+    paciente.id
+}
+
+async fn buscar(client: &reqwest::Client, app: &TestApp, q: &str) -> Response {
+    client
+        .get(format!("{}/pacientes/busca", &app.address))
+        .query(&[("q", q)])
+        .send()
+        .await
+        .expect("Failed to execute request.")
+}
+
+async fn nomes_encontrados(client: &reqwest::Client, app: &TestApp, q: &str) -> Vec<String> {
+    let response = buscar(client, app, q).await;
+    assert_eq!(200, response.status().as_u16());
+    let pacientes: Vec<Paciente> = response.json().await.unwrap();
+    pacientes.into_iter().map(|p| p.nome).collect()
+}
+
+#[actix_rt::test]
+async fn busca_ignores_accents() {
+    let app = create_app().await;
+    let unidade_id = cria_unidade(&app, "UPA Norte").await;
+    cria_paciente(&app, "João da Silva", unidade_id).await;
+    cria_paciente(&app, "Maria Souza", unidade_id).await;
+
+    assert_eq!(vec!["João da Silva"], nomes_encontrados(&app.api_client, &app, "Joao").await);
+    assert_eq!(vec!["João da Silva"], nomes_encontrados(&app.api_client, &app, "JOÃO").await);
+}
+
+#[actix_rt::test]
+async fn busca_tolerates_typos() {
+    let app = create_app().await;
+    let unidade_id = cria_unidade(&app, "UPA Norte").await;
+    cria_paciente(&app, "Ana Fernandes", unidade_id).await;
+    cria_paciente(&app, "Maria Souza", unidade_id).await;
+
+    assert_eq!(vec!["Ana Fernandes"], nomes_encontrados(&app.api_client, &app, "Fernandez").await);
+}
+
+#[actix_rt::test]
+async fn busca_ranks_exact_words_first() {
+    let app = create_app().await;
+    let unidade_id = cria_unidade(&app, "UPA Norte").await;
+    cria_paciente(&app, "Silvana Costa", unidade_id).await;
+    cria_paciente(&app, "Pedro Silva", unidade_id).await;
+
+    assert_eq!(vec!["Pedro Silva", "Silvana Costa"], nomes_encontrados(&app.api_client, &app, "Silva").await);
+}
+
+#[actix_rt::test]
+async fn busca_is_scoped_to_callers_unidade() {
+    let app = create_app().await;
+    let norte = cria_unidade(&app, "UPA Norte").await;
+    let sul = cria_unidade(&app, "UPA Sul").await;
+    cria_paciente(&app, "João Norte", norte).await;
+    cria_paciente(&app, "João Sul", sul).await;
+    let client = app.client_com_papel(Papel::FuncionarioUnidade, Some(norte), None).await;
+
+    assert_eq!(vec!["João Norte"], nomes_encontrados(&client, &app, "joao").await);
+}
+
+#[actix_rt::test]
+async fn busca_hides_pacientes_without_internacao_of_other_unidades() {
+    let app = create_app().await;
+    let norte = cria_unidade(&app, "UPA Norte").await;
+    let client = app.client_com_papel(Papel::FuncionarioUnidade, Some(norte), None).await;
+
+    // cadastrado pelo admin e nunca internado: não pertence à unidade
+    let response = app.api_client
+        .post(format!("{}/pacientes", &app.address))
+        .json(&serde_json::json!({
+            "nome": "João Avulso",
+            "sexo": "Masculino",
+            "idade": 40,
+            "email": "avulso@gmail.com",
+            "telefone": "84991223344"
+        }))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    assert_eq!(200, response.status().as_u16());
+
+    assert!(nomes_encontrados(&client, &app, "joao").await.is_empty());
+    assert_eq!(vec!["João Avulso"], nomes_encontrados(&app.api_client, &app, "joao").await);
+}
+
+#[actix_rt::test]
+async fn busca_without_term_returns_400() {
+    let app = create_app().await;
+
+    let response = buscar(&app.api_client, &app, "   ").await;
+    assert_eq!(400, response.status().as_u16());
+    let body: ErrorBody = response.json().await.unwrap();
+    assert_eq!("q", body.fields[0].field);
+}
@@ -0,0 +1,197 @@
+use crate::helpers::{create_app, TestApp};
+use reqwest::Response;
+use gerenciador_leitos::domain::{SituacaoCovid, SituacaoLeito, TipoLeito};
+use gerenciador_leitos::error::ErrorBody;
+use gerenciador_leitos::routes::{Leito, LeitoId, PacienteId, UnidadeId};
+use uuid::Uuid;
+
+async fn cria_unidade(app: &TestApp) -> Uuid {
+    let response: Response = app.post_unidade(
+        String::from("UPA Norte"),
+        String::from("upanorte@gmail.com"),
+        String::from("UPA"),
+        String::from("Natal")
+    ).await;
+    let unidade: UnidadeId = response.json().await.unwrap();
+    unidade.id
+}
+
+async fn cria_leito(app: &TestApp) -> Uuid {
+    let unidade_id = cria_unidade(app).await;
+    let response = app.post_leito(TipoLeito::UtiAdulto, SituacaoLeito::Livre, unidade_id).await;
+    let leito: LeitoId = response.json().await.unwrap();
+    leito.id
+}
+
+async fn get(app: &TestApp, caminho: &str, if_none_match: Option<&str>) -> Response {
+    let mut request = app.api_client.get(format!("{}{}", &app.address, caminho));
+    if let Some(etag) = if_none_match {
+        request = request.header("If-None-Match", etag);
+    }
+    request
+        .send()
+        .await
+        .expect("Failed to execute request.")
+}
+
+async fn patch(app: &TestApp, caminho: &str, if_match: Option<&str>, body: serde_json::Value) -> Response {
+    let mut request = app.api_client
+        .patch(format!("{}{}", &app.address, caminho))
+        .header("Content-Type", "application/merge-patch+json")
+        .body(body.to_string());
+    if let Some(etag) = if_match {
+        request = request.header("If-Match", etag);
+    }
+    request
+        .send()
+        .await
+        .expect("Failed to execute request.")
+}
+
+fn etag(response: &Response) -> String {
+    response.headers()["etag"].to_str().unwrap().to_string()
+}
+
+#[actix_rt::test]
+async fn get_returns_etag_and_304_when_unchanged() {
+    let app = create_app().await;
+    let leito_id = cria_leito(&app).await;
+    let caminho = format!("/leitos/{}", leito_id);
+
+    let response = get(&app, &caminho, None).await;
+    assert_eq!(200, response.status().as_u16());
+    let versao = etag(&response);
+
+    let response = get(&app, &caminho, Some(&versao)).await;
+    assert_eq!(304, response.status().as_u16());
+    assert_eq!(versao, etag(&response));
+    assert!(response.text().await.unwrap().is_empty());
+
+    // a comparação do If-None-Match é fraca e aceita listas
+    let response = get(&app, &caminho, Some(&format!("\"0\", W/{}", versao))).await;
+    assert_eq!(304, response.status().as_u16());
+
+    let response = get(&app, &caminho, Some("\"0\"")).await;
+    assert_eq!(200, response.status().as_u16());
+}
+
+#[actix_rt::test]
+async fn concurrent_update_of_leito_returns_412() {
+    let app = create_app().await;
+    let leito_id = cria_leito(&app).await;
+    let caminho = format!("/leitos/{}", leito_id);
+
+    // dois operadores leem a mesma versão
+    let versao = app.etag(&caminho).await.unwrap();
+
+    let response = patch(&app, &caminho, Some(&versao), serde_json::json!({"situacao": "Bloqueado"})).await;
+    assert_eq!(200, response.status().as_u16());
+    let nova_versao = etag(&response);
+    assert_ne!(versao, nova_versao);
+
+    let response = patch(&app, &caminho, Some(&versao), serde_json::json!({"situacao": "EmManutencao"})).await;
+    assert_eq!(412, response.status().as_u16());
+    let body: ErrorBody = response.json().await.unwrap();
+    assert_eq!("precondition_failed", body.code);
+
+    let leito: Leito = get(&app, &caminho, None).await.json().await.unwrap();
+    assert_eq!(SituacaoLeito::Bloqueado, leito.situacao);
+
+    let response = patch(&app, &caminho, Some(&nova_versao), serde_json::json!({"situacao": "EmManutencao"})).await;
+    assert_eq!(200, response.status().as_u16());
+}
+
+#[actix_rt::test]
+async fn writes_without_if_match_return_428() {
+    let app = create_app().await;
+    let leito_id = cria_leito(&app).await;
+    let caminho = format!("/leitos/{}", leito_id);
+
+    let response = patch(&app, &caminho, None, serde_json::json!({"hemodialise": true})).await;
+    assert_eq!(428, response.status().as_u16());
+    let body: ErrorBody = response.json().await.unwrap();
+    assert_eq!("precondition_required", body.code);
+
+    let response = app.api_client
+        .delete(format!("{}{}", &app.address, caminho))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    assert_eq!(428, response.status().as_u16());
+
+    // `*` aceita qualquer versão do recurso existente
+    let response = app.api_client
+        .delete(format!("{}{}", &app.address, caminho))
+        .header("If-Match", "*")
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    assert_eq!(200, response.status().as_u16());
+}
+
+#[actix_rt::test]
+async fn stale_etag_is_refused_on_put_and_delete_of_unidade() {
+    let app = create_app().await;
+    let unidade_id = cria_unidade(&app).await;
+    let caminho = format!("/unidades/{}", unidade_id);
+    let versao = app.etag(&caminho).await.unwrap();
+
+    let response = patch(&app, &caminho, Some(&versao), serde_json::json!({"nome": "UPA Norte II"})).await;
+    assert_eq!(200, response.status().as_u16());
+
+    let response = app.api_client
+        .put(format!("{}{}", &app.address, caminho))
+        .header("If-Match", &versao)
+        .json(&serde_json::json!({
+            "nome": "UPA Norte III",
+            "email": "upanorte@gmail.com",
+            "tipo": "UPA",
+            "municipio": "Natal"
+        }))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    assert_eq!(412, response.status().as_u16());
+
+    let response = app.api_client
+        .delete(format!("{}{}", &app.address, caminho))
+        .header("If-Match", &versao)
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    assert_eq!(412, response.status().as_u16());
+}
+
+#[actix_rt::test]
+async fn paciente_etag_changes_with_covid_records() {
+    let app = create_app().await;
+    let leito_id = cria_leito(&app).await;
+    let response = app.post_paciente(
+        String::from("Fulano"),
+        String::from("Masculino"),
+        54,
+        String::from("fulano@gmail.com"),
+        String::from("84991223344"),
+        Some(SituacaoCovid::Suspeito),
+        leito_id
+    ).await;
+    let paciente: PacienteId = response.json().await.unwrap();
+    let caminho = format!("/pacientes/{}", paciente.id);
+    let versao = app.etag(&caminho).await.unwrap();
+
+    // a situação de covid faz parte da representação do paciente
+    let response = app.api_client
+        .post(format!("{}/pacientes/{}/covid", &app.address, paciente.id))
+        .json(&serde_json::json!({"situacao": "Confirmado"}))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    assert_eq!(200, response.status().as_u16());
+
+    let response = get(&app, &caminho, Some(&versao)).await;
+    assert_eq!(200, response.status().as_u16());
+    assert_ne!(versao, etag(&response));
+
+    let response = patch(&app, &caminho, Some(&versao), serde_json::json!({"telefone": "84998887766"})).await;
+    assert_eq!(412, response.status().as_u16());
+}
@@ -0,0 +1,239 @@
+use crate::helpers::{create_app, TestApp};
+use reqwest::Response;
+use gerenciador_leitos::domain::TipoLeito;
+use gerenciador_leitos::error::ErrorBody;
+use gerenciador_leitos::query::Pagina;
+use gerenciador_leitos::routes::{CondicaoNotificavel, CondicaoPaciente, EntradaFila, LeitoId, Paciente, PacienteId, PropostaLeito, UnidadeId};
+use uuid::Uuid;
+
+async fn cria_unidade(app: &TestApp) -> Uuid {
+    let response: Response = app.post_unidade(
+        String::from("UPA Norte"),
+        String::from("upanorte@gmail.com"),
+        String::from("UPA"),
+        String::from("Natal")
+    ).await;
+    let unidade: UnidadeId = response.json().await.unwrap();
+    unidade.id
+}
+
+async fn cria_leito(app: &TestApp, unidade_id: Uuid, tipo: &str, pressao_negativa: bool) -> Uuid {
+    let response = post_json(app, "/leitos", &serde_json::json!({
+        "tipo": tipo,
+        "situacao": "Livre",
+        "isolamento_pressao_negativa": pressao_negativa,
+        "unidade_id": unidade_id.to_string()
+    })).await;
+    assert_eq!(200, response.status().as_u16());
+    let leito: LeitoId = response.json().await.unwrap();
+    leito.id
+}
+
+// paciente cadastrado sem internação
+async fn cria_paciente(app: &TestApp) -> Uuid {
+    let response = post_json(app, "/pacientes", &serde_json::json!({
+        "nome": "Fulano",
+        "sexo": "Masculino",
+        "idade": 54,
+        "email": "fulano@gmail.com",
+        "telefone": "84991223344"
+    })).await;
+    assert_eq!(200, response.status().as_u16());
+    let paciente: PacienteId = response.json().await.unwrap();
+    paciente.id
+}
+
+async fn condicao(app: &TestApp, nome: &str) -> CondicaoNotificavel {
+    let condicoes: Vec<CondicaoNotificavel> = get(app, "/condicoes").await.json().await.unwrap();
+    condicoes.into_iter().find(|c| c.nome == nome).unwrap()
+}
+
+async fn vincula(app: &TestApp, paciente_id: Uuid, condicao_id: Uuid) -> Response {
+    post_json(app, &format!("/pacientes/{}/condicoes", paciente_id), &serde_json::json!({
+        "condicao_id": condicao_id.to_string()
+    })).await
+}
+
+async fn interna(app: &TestApp, paciente_id: Uuid, leito_id: Uuid) -> Response {
+    post_json(app, &format!("/pacientes/{}/internar", paciente_id), &serde_json::json!({
+        "leito_id": leito_id.to_string()
+    })).await
+}
+
+async fn post_json(app: &TestApp, caminho: &str, body: &serde_json::Value) -> Response {
+    app.api_client
+        .post(format!("{}{}", &app.address, caminho))
+        .json(body)
+        .send()
+        .await
+        .expect("Failed to execute request.")
+}
+
+async fn get(app: &TestApp, caminho: &str) -> Response {
+    app.api_client
+        .get(format!("{}{}", &app.address, caminho))
+        .send()
+        .await
+        .expect("Failed to execute request.")
+}
+
+async fn delete(app: &TestApp, caminho: &str) -> Response {
+    app.api_client
+        .delete(format!("{}{}", &app.address, caminho))
+        .send()
+        .await
+        .expect("Failed to execute request.")
+}
+
+#[actix_rt::test]
+async fn catalogo_comes_seeded_and_accepts_new_condicoes() {
+    let app = create_app().await;
+
+    assert!(condicao(&app, "Tuberculose").await.exige_isolamento);
+    assert!(!condicao(&app, "Dengue").await.exige_isolamento);
+
+    let response = post_json(&app, "/condicoes", &serde_json::json!({
+        "nome": "Coqueluche",
+        "exige_isolamento": true
+    })).await;
+    assert_eq!(200, response.status().as_u16());
+    let criada: CondicaoNotificavel = response.json().await.unwrap();
+    assert!(criada.exige_isolamento);
+
+    let response = post_json(&app, "/condicoes", &serde_json::json!({"nome": "Coqueluche"})).await;
+    assert_eq!(409, response.status().as_u16());
+    let body: ErrorBody = response.json().await.unwrap();
+    assert_eq!("nome", body.fields[0].field);
+
+    let response = post_json(&app, "/condicoes", &serde_json::json!({"nome": " "})).await;
+    assert_eq!(400, response.status().as_u16());
+}
+
+#[actix_rt::test]
+async fn condicoes_do_paciente_can_be_linked_listed_and_removed() {
+    let app = create_app().await;
+    let paciente_id = cria_paciente(&app).await;
+    let dengue = condicao(&app, "Dengue").await;
+
+    let response = vincula(&app, paciente_id, dengue.id).await;
+    assert_eq!(200, response.status().as_u16());
+    let condicoes: Vec<CondicaoPaciente> = response.json().await.unwrap();
+    assert_eq!(vec![dengue.id], condicoes.iter().map(|c| c.condicao_id).collect::<Vec<Uuid>>());
+
+    let response = vincula(&app, paciente_id, dengue.id).await;
+    assert_eq!(409, response.status().as_u16());
+    let body: ErrorBody = response.json().await.unwrap();
+    assert_eq!("condicao_id", body.fields[0].field);
+
+    let response = vincula(&app, paciente_id, Uuid::new_v4()).await;
+    assert_eq!(422, response.status().as_u16());
+
+    let response = vincula(&app, Uuid::new_v4(), dengue.id).await;
+    assert_eq!(404, response.status().as_u16());
+
+    let pagina: Pagina<Paciente> = get(&app, &format!("/pacientes?condicao_id={}", dengue.id)).await.json().await.unwrap();
+    assert_eq!(vec![paciente_id], pagina.itens.iter().map(|p| p.id).collect::<Vec<Uuid>>());
+
+    let response = delete(&app, &format!("/pacientes/{}/condicoes/{}", paciente_id, dengue.id)).await;
+    assert_eq!(200, response.status().as_u16());
+    let condicoes: Vec<CondicaoPaciente> = get(&app, &format!("/pacientes/{}/condicoes", paciente_id)).await.json().await.unwrap();
+    assert!(condicoes.is_empty());
+
+    let response = delete(&app, &format!("/pacientes/{}/condicoes/{}", paciente_id, dengue.id)).await;
+    assert_eq!(404, response.status().as_u16());
+}
+
+#[actix_rt::test]
+async fn delete_condicao_in_use_returns_409() {
+    let app = create_app().await;
+    let paciente_id = cria_paciente(&app).await;
+    let influenza = condicao(&app, "Influenza").await;
+    vincula(&app, paciente_id, influenza.id).await;
+
+    let response = delete(&app, &format!("/condicoes/{}", influenza.id)).await;
+    assert_eq!(409, response.status().as_u16());
+
+    delete(&app, &format!("/pacientes/{}/condicoes/{}", paciente_id, influenza.id)).await;
+    let response = delete(&app, &format!("/condicoes/{}", influenza.id)).await;
+    assert_eq!(200, response.status().as_u16());
+}
+
+#[actix_rt::test]
+async fn paciente_requiring_isolamento_is_only_admitted_to_isolation_leitos() {
+    let app = create_app().await;
+    let unidade_id = cria_unidade(&app).await;
+    let comum = cria_leito(&app, unidade_id, "UtiAdulto", false).await;
+    let pressao_negativa = cria_leito(&app, unidade_id, "UtiAdulto", true).await;
+    let respiratorio = cria_leito(&app, unidade_id, "IsolamentoRespiratorio", false).await;
+
+    let paciente_id = cria_paciente(&app).await;
+    vincula(&app, paciente_id, condicao(&app, "Tuberculose").await.id).await;
+
+    let response = interna(&app, paciente_id, comum).await;
+    assert_eq!(409, response.status().as_u16());
+    let body: ErrorBody = response.json().await.unwrap();
+    assert_eq!("leito_id", body.fields[0].field);
+    assert!(body.message.contains("Tuberculose"));
+
+    let response = interna(&app, paciente_id, pressao_negativa).await;
+    assert_eq!(200, response.status().as_u16());
+
+    let outro = cria_paciente(&app).await;
+    vincula(&app, outro, condicao(&app, "Sarampo").await.id).await;
+    let response = interna(&app, outro, respiratorio).await;
+    assert_eq!(200, response.status().as_u16());
+
+    // condições sem exigência de isolamento não restringem o leito
+    let com_dengue = cria_paciente(&app).await;
+    vincula(&app, com_dengue, condicao(&app, "Dengue").await.id).await;
+    let response = interna(&app, com_dengue, comum).await;
+    assert_eq!(200, response.status().as_u16());
+}
+
+#[actix_rt::test]
+async fn condicao_requiring_isolamento_is_rejected_for_paciente_in_common_leito() {
+    let app = create_app().await;
+    let unidade_id = cria_unidade(&app).await;
+    let comum = cria_leito(&app, unidade_id, "UtiAdulto", false).await;
+    let pressao_negativa = cria_leito(&app, unidade_id, "UtiAdulto", true).await;
+    let tuberculose = condicao(&app, "Tuberculose").await;
+
+    let paciente_id = cria_paciente(&app).await;
+    interna(&app, paciente_id, comum).await;
+
+    let response = vincula(&app, paciente_id, tuberculose.id).await;
+    assert_eq!(422, response.status().as_u16());
+    let body: ErrorBody = response.json().await.unwrap();
+    assert_eq!("condicao_id", body.fields[0].field);
+    let condicoes: Vec<CondicaoPaciente> = get(&app, &format!("/pacientes/{}/condicoes", paciente_id)).await.json().await.unwrap();
+    assert!(condicoes.is_empty());
+
+    let isolado = cria_paciente(&app).await;
+    interna(&app, isolado, pressao_negativa).await;
+    let response = vincula(&app, isolado, tuberculose.id).await;
+    assert_eq!(200, response.status().as_u16());
+}
+
+#[actix_rt::test]
+async fn proposta_da_fila_skips_leitos_without_isolamento() {
+    let app = create_app().await;
+    let unidade_id = cria_unidade(&app).await;
+    cria_leito(&app, unidade_id, "UtiAdulto", false).await;
+    let isolado = cria_leito(&app, unidade_id, "UtiAdulto", true).await;
+
+    let paciente_id = cria_paciente(&app).await;
+    vincula(&app, paciente_id, condicao(&app, "COVID-19").await.id).await;
+
+    let response = post_json(&app, "/fila", &serde_json::json!({
+        "paciente_id": paciente_id.to_string(),
+        "prioridade": 3,
+        "tipo_leito": TipoLeito::UtiAdulto,
+        "municipio_origem": "Natal"
+    })).await;
+    let entrada: EntradaFila = response.json().await.unwrap();
+
+    let response = get(&app, &format!("/fila/{}/proposta", entrada.id)).await;
+    assert_eq!(200, response.status().as_u16());
+    let proposta: PropostaLeito = response.json().await.unwrap();
+    assert_eq!(isolado, proposta.leito_id);
+}
@@ -0,0 +1,187 @@
+use crate::helpers::{create_app, TestApp};
+use reqwest::Response;
+use gerenciador_leitos::domain::{SituacaoCovid, SituacaoLeito, TipoLeito, TipoTesteCovid};
+use gerenciador_leitos::error::ErrorBody;
+use gerenciador_leitos::routes::{CovidUnidade, LeitoId, Paciente, PacienteId, RegistroCovid, UnidadeId};
+use uuid::Uuid;
+
+async fn cria_unidade(app: &TestApp, nome: &str) -> Uuid {
+    let response: Response = app.post_unidade(
+        String::from(nome),
+        format!("{}@gmail.com", nome.to_lowercase().replace(' ', "")),
+        String::from("UPA"),
+        String::from("Natal")
+    ).await;
+    let unidade: UnidadeId = response.json().await.unwrap();
+    unidade.id
+}
+
+// interna um paciente num leito novo da unidade
+async fn interna_paciente(app: &TestApp, unidade_id: Uuid, situacao_covid: Option<SituacaoCovid>) -> Uuid {
+    let response = app.post_leito(TipoLeito::UtiAdulto, SituacaoLeito::Livre, unidade_id).await;
+    let leito: LeitoId = response.json().await.unwrap();
+
+    let response = app.post_paciente(
+        String::from("Fulano"),
+        String::from("Masculino"),
+        54,
+        String::from("fulano@gmail.com"),
+        String::from("84991223344"),
+        situacao_covid,
+        leito.id
+    ).await;
+    assert_eq!(200, response.status().as_u16());
+    let paciente: PacienteId = response.json().await.unwrap();
+    paciente.id
+}
+
+async fn registra(app: &TestApp, paciente_id: Uuid, body: serde_json::Value) -> Response {
+    app.api_client
+        .post(format!("{}/pacientes/{}/covid", &app.address, paciente_id))
+        .json(&body)
+        .send()
+        .await
+        .expect("Failed to execute request.")
+}
+
+async fn get(app: &TestApp, caminho: &str) -> Response {
+    app.api_client
+        .get(format!("{}{}", &app.address, caminho))
+        .send()
+        .await
+        .expect("Failed to execute request.")
+}
+
+#[actix_rt::test]
+async fn registro_covid_keeps_history_and_updates_current_situacao() {
+    let app = create_app().await;
+    let unidade_id = cria_unidade(&app, "UPA Norte").await;
+    let paciente_id = interna_paciente(&app, unidade_id, Some(SituacaoCovid::Suspeito)).await;
+
+    let response = registra(&app, paciente_id, serde_json::json!({
+        "situacao": "Confirmado",
+        "tipo_teste": "RtPcr",
+        "coletado_em": "2021-06-01",
+        "resultado_em": "2021-06-03",
+        "inicio_sintomas_em": "2021-05-29"
+    })).await;
+    assert_eq!(200, response.status().as_u16());
+    let registro: RegistroCovid = response.json().await.unwrap();
+    assert_eq!(SituacaoCovid::Confirmado, registro.situacao);
+    assert_eq!(Some(TipoTesteCovid::RtPcr), registro.tipo_teste);
+
+    let paciente: Paciente = get(&app, &format!("/pacientes/{}", paciente_id)).await.json().await.unwrap();
+    assert_eq!(Some(SituacaoCovid::Confirmado), paciente.situacao_covid);
+
+    let response = get(&app, &format!("/pacientes/{}/covid", paciente_id)).await;
+    assert_eq!(200, response.status().as_u16());
+    let historico: Vec<RegistroCovid> = response.json().await.unwrap();
+    let situacoes: Vec<SituacaoCovid> = historico.iter().map(|r| r.situacao).collect();
+    assert_eq!(vec![SituacaoCovid::Suspeito, SituacaoCovid::Confirmado], situacoes);
+    assert_eq!(None, historico[0].tipo_teste);
+}
+
+#[actix_rt::test]
+async fn registro_covid_validates_test_data() {
+    let app = create_app().await;
+    let unidade_id = cria_unidade(&app, "UPA Norte").await;
+    let paciente_id = interna_paciente(&app, unidade_id, None).await;
+
+    let response = registra(&app, paciente_id, serde_json::json!({
+        "situacao": "Descartado",
+        "coletado_em": "2021-06-03",
+        "resultado_em": "2021-06-01",
+        "inicio_sintomas_em": "2999-01-01"
+    })).await;
+
+    assert_eq!(400, response.status().as_u16());
+    let body: ErrorBody = response.json().await.unwrap();
+    let campos: Vec<&str> = body.fields.iter().map(|f| f.field.as_str()).collect();
+    assert_eq!(vec!["tipo_teste", "inicio_sintomas_em", "resultado_em"], campos);
+}
+
+#[actix_rt::test]
+async fn registro_covid_for_missing_paciente_returns_404() {
+    let app = create_app().await;
+    let id = Uuid::new_v4();
+
+    let response = registra(&app, id, serde_json::json!({"situacao": "Suspeito"})).await;
+    assert_eq!(404, response.status().as_u16());
+
+    let response = get(&app, &format!("/pacientes/{}/covid", id)).await;
+    assert_eq!(404, response.status().as_u16());
+}
+
+#[actix_rt::test]
+async fn get_pacientes_covid_filters_by_current_situacao() {
+    let app = create_app().await;
+    let unidade_id = cria_unidade(&app, "UPA Norte").await;
+    let confirmado = interna_paciente(&app, unidade_id, Some(SituacaoCovid::Confirmado)).await;
+    interna_paciente(&app, unidade_id, Some(SituacaoCovid::Suspeito)).await;
+    let recuperado = interna_paciente(&app, unidade_id, Some(SituacaoCovid::Confirmado)).await;
+    let response = registra(&app, recuperado, serde_json::json!({"situacao": "Recuperado"})).await;
+    assert_eq!(200, response.status().as_u16());
+
+    let pacientes: Vec<Paciente> = get(&app, &format!("/pacientes/covid/{}", unidade_id)).await.json().await.unwrap();
+    let ids: Vec<Uuid> = pacientes.iter().map(|p| p.id).collect();
+    assert_eq!(vec![confirmado], ids);
+
+    let pacientes: Vec<Paciente> = get(&app, &format!("/pacientes/covid/{}?situacao=Recuperado", unidade_id))
+        .await
+        .json()
+        .await
+        .unwrap();
+    let ids: Vec<Uuid> = pacientes.iter().map(|p| p.id).collect();
+    assert_eq!(vec![recuperado], ids);
+}
+
+#[actix_rt::test]
+async fn relatorio_covid_counts_internados_per_unidade() {
+    let app = create_app().await;
+    let norte = cria_unidade(&app, "UPA Norte").await;
+    let sul = cria_unidade(&app, "UPA Sul").await;
+    interna_paciente(&app, norte, Some(SituacaoCovid::Confirmado)).await;
+    interna_paciente(&app, norte, Some(SituacaoCovid::Confirmado)).await;
+    interna_paciente(&app, norte, Some(SituacaoCovid::Suspeito)).await;
+    interna_paciente(&app, sul, None).await;
+
+    let response = get(&app, "/relatorios/covid").await;
+    assert_eq!(200, response.status().as_u16());
+    let unidades: Vec<CovidUnidade> = response.json().await.unwrap();
+    assert_eq!(2, unidades.len());
+
+    let unidade_norte = unidades.iter().find(|u| u.unidade_id == norte).unwrap();
+    assert_eq!(2, unidade_norte.confirmados);
+    assert_eq!(1, unidade_norte.suspeitos);
+    assert_eq!(0, unidade_norte.sem_registro);
+
+    let unidade_sul = unidades.iter().find(|u| u.unidade_id == sul).unwrap();
+    assert_eq!(0, unidade_sul.confirmados);
+    assert_eq!(1, unidade_sul.sem_registro);
+}
+
+#[actix_rt::test]
+async fn update_paciente_cannot_change_situacao_covid() {
+    let app = create_app().await;
+    let unidade_id = cria_unidade(&app, "UPA Norte").await;
+    let paciente_id = interna_paciente(&app, unidade_id, Some(SituacaoCovid::Suspeito)).await;
+
+    let response = app.api_client
+        .put(format!("{}/pacientes/{}", &app.address, paciente_id))
+        .header("If-Match", app.etag(&format!("/pacientes/{}", paciente_id)).await.unwrap())
+        .json(&serde_json::json!({
+            "nome": "Fulano",
+            "sexo": "Masculino",
+            "idade": 54,
+            "email": "fulano@gmail.com",
+            "telefone": "84991223344",
+            "situacao_covid": "Descartado"
+        }))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+
+    assert_eq!(409, response.status().as_u16());
+    let body: ErrorBody = response.json().await.unwrap();
+    assert_eq!("situacao_covid", body.fields[0].field);
+}
@@ -0,0 +1,182 @@
+use crate::helpers::{create_app, TestApp};
+use reqwest::Response;
+use gerenciador_leitos::domain::{Papel, SituacaoCovid, SituacaoLeito, TipoLeito};
+use gerenciador_leitos::routes::{DadosPessoais, LeitoId, Paciente, PacienteId, UnidadeId};
+use uuid::Uuid;
+
+// cadastra um paciente internado e devolve os ids do paciente e do leito
+async fn cria_paciente_internado(app: &TestApp) -> (Uuid, Uuid) {
+    let response: Response = app.post_unidade(
+        String::from("UBS Aux"),
+        String::from("ubsaux@gmail.com"),
+        String::from("UBS"),
+        String::from("Natal")
+    ).await;
+    let unidade: UnidadeId = response.json().await.unwrap();
+
+    let response = app.post_leito(TipoLeito::UtiAdulto, SituacaoLeito::Livre, unidade.id).await;
+    let leito: LeitoId = response.json().await.unwrap();
+
+    let response = app.post_paciente(
+        String::from("Fulano de Tal"),
+        String::from("Masculino"),
+        54,
+        String::from("fulano@gmail.com"),
+        String::from("84991223344"),
+        Some(SituacaoCovid::Confirmado),
+        leito.id
+    ).await;
+    assert_eq!(200, response.status().as_u16());
+    let paciente: PacienteId = response.json().await.unwrap();
+
+    (paciente.id, leito.id)
+}
+
+async fn anonimizar(app: &TestApp, id: Uuid) -> Response {
+    app.api_client
+        .post(format!("{}/pacientes/{}/anonimizar", &app.address, id))
+        .send()
+        .await
+        .expect("Failed to execute request.")
+}
+
+async fn exportar(app: &TestApp, id: Uuid) -> Response {
+    app.api_client
+        .get(format!("{}/pacientes/{}/dados-pessoais", &app.address, id))
+        .send()
+        .await
+        .expect("Failed to execute request.")
+}
+
+#[actix_rt::test]
+async fn get_dados_pessoais_exports_record_internacoes_and_auditoria() {
+    let app = create_app().await;
+    let (paciente_id, leito_id) = cria_paciente_internado(&app).await;
+
+    let response = exportar(&app, paciente_id).await;
+    assert_eq!(200, response.status().as_u16());
+    let dados: DadosPessoais = response.json().await.unwrap();
+
+    assert_eq!("Fulano de Tal", dados.paciente.nome);
+    assert_eq!(Some(leito_id), dados.paciente.leito_id);
+    assert!(dados.anonimizado_em.is_none());
+    assert_eq!(1, dados.internacoes.len());
+    assert_eq!(leito_id, dados.internacoes[0].leito_id);
+    assert!(dados.transferencias.is_empty());
+    assert_eq!(1, dados.auditoria.len());
+}
+
+#[actix_rt::test]
+async fn get_dados_pessoais_for_missing_paciente_returns_404() {
+    let app = create_app().await;
+
+    let response = exportar(&app, Uuid::new_v4()).await;
+    assert_eq!(404, response.status().as_u16());
+}
+
+#[actix_rt::test]
+async fn anonimizar_scrubs_personal_data_and_keeps_clinical_fields() {
+    let app = create_app().await;
+    let (paciente_id, leito_id) = cria_paciente_internado(&app).await;
+
+    let response = anonimizar(&app, paciente_id).await;
+    assert_eq!(200, response.status().as_u16());
+    let paciente: Paciente = response.json().await.unwrap();
+    assert_eq!("anonimizado", paciente.nome);
+    assert_eq!("anonimizado", paciente.email);
+    assert_eq!("anonimizado", paciente.telefone);
+    assert_eq!("Masculino", paciente.sexo);
+    assert_eq!(Some(54), paciente.idade);
+    assert_eq!(Some(SituacaoCovid::Confirmado), paciente.situacao_covid);
+    assert_eq!(Some(leito_id), paciente.leito_id);
+
+    // nenhum vestígio dos dados pessoais, nem na auditoria
+    let response = exportar(&app, paciente_id).await;
+    let texto = response.text().await.unwrap();
+    assert!(!texto.contains("Fulano"));
+    assert!(!texto.contains("fulano@gmail.com"));
+    assert!(!texto.contains("84991223344"));
+
+    let dados: DadosPessoais = serde_json::from_str(&texto).unwrap();
+    assert!(dados.anonimizado_em.is_some());
+    assert_eq!(2, dados.auditoria.len());
+
+    // o leito continua ocupado, então os relatórios não mudam
+    let situacao = sqlx::query!("SELECT situacao as \"situacao: SituacaoLeito\" FROM leito WHERE id = $1", leito_id)
+        .fetch_one(&app.db_pool)
+        .await
+        .unwrap()
+        .situacao;
+    assert_eq!(SituacaoLeito::Ocupado, situacao);
+}
+
+#[actix_rt::test]
+async fn anonimizar_is_irreversible() {
+    let app = create_app().await;
+    let (paciente_id, leito_id) = cria_paciente_internado(&app).await;
+
+    let response = anonimizar(&app, paciente_id).await;
+    assert_eq!(200, response.status().as_u16());
+
+    let response = anonimizar(&app, paciente_id).await;
+    assert_eq!(409, response.status().as_u16());
+
+    let response = app.api_client
+        .put(format!("{}/pacientes/{}", &app.address, paciente_id))
+        .header("If-Match", app.etag(&format!("/pacientes/{}", paciente_id)).await.unwrap())
+        .json(&serde_json::json!({
+            "nome": "Fulano de Tal",
+            "sexo": "Masculino",
+            "idade": 54,
+            "email": "fulano@gmail.com",
+            "telefone": "84991223344",
+            "situacao_covid": "Confirmado",
+            "leito_id": leito_id.to_string()
+        }))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    assert_eq!(409, response.status().as_u16());
+}
+
+#[actix_rt::test]
+async fn auditoria_of_other_pacientes_stays_immutable() {
+    let app = create_app().await;
+    let (paciente_id, _) = cria_paciente_internado(&app).await;
+
+    // a autorização da transação vale só para o paciente informado nela
+    let mut transaction = app.db_pool.begin().await.unwrap();
+    sqlx::query("SELECT set_config('auditoria.anonimizar_paciente', $1, true)")
+        .bind(Uuid::new_v4().to_string())
+        .execute(&mut transaction)
+        .await
+        .unwrap();
+    let resultado = sqlx::query(
+        "UPDATE auditoria SET antes = anonimizar_dados_pessoais(antes), depois = anonimizar_dados_pessoais(depois) WHERE entidade_id = $1"
+    )
+    .bind(paciente_id)
+    .execute(&mut transaction)
+    .await;
+    assert!(resultado.is_err());
+}
+
+#[actix_rt::test]
+async fn dados_pessoais_require_admin() {
+    let app = create_app().await;
+    let (paciente_id, _) = cria_paciente_internado(&app).await;
+    let client = app.client_com_papel(Papel::ReguladorEstadual, None, None).await;
+
+    let response = client
+        .get(format!("{}/pacientes/{}/dados-pessoais", &app.address, paciente_id))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    assert_eq!(403, response.status().as_u16());
+
+    let response = client
+        .post(format!("{}/pacientes/{}/anonimizar", &app.address, paciente_id))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    assert_eq!(403, response.status().as_u16());
+}
@@ -0,0 +1,252 @@
+use crate::helpers::{create_app, TestApp};
+use reqwest::Response;
+use gerenciador_leitos::domain::{Papel, SituacaoCovid, SituacaoLeito, TipoLeito};
+use gerenciador_leitos::query::Pagina;
+use gerenciador_leitos::routes::{LeitoId, UnidadeId, UnidadeSaude};
+use gerenciador_leitos::spreadsheet::LINHAS_MAXIMAS_XLSX;
+use uuid::Uuid;
+
+const XLSX: &str = "application/vnd.openxmlformats-officedocument.spreadsheetml.sheet";
+
+async fn cria_unidade(app: &TestApp, nome: &str, email: &str, municipio: &str) -> Uuid {
+    let response: Response = app.post_unidade(
+        String::from(nome),
+        String::from(email),
+        String::from("UPA"),
+        String::from(municipio)
+    ).await;
+    let unidade: UnidadeId = response.json().await.unwrap();
+    unidade.id
+}
+
+async fn cria_leito(app: &TestApp, tipo: TipoLeito, unidade_id: Uuid) -> Uuid {
+    let response = app.post_leito(tipo, SituacaoLeito::Livre, unidade_id).await;
+    let leito: LeitoId = response.json().await.unwrap();
+    leito.id
+}
+
+async fn get(client: &reqwest::Client, app: &TestApp, caminho: &str, accept: &str) -> Response {
+    client
+        .get(format!("{}{}", &app.address, caminho))
+        .header("Accept", accept)
+        .send()
+        .await
+        .expect("Failed to execute request.")
+}
+
+fn cabecalho<'a>(response: &'a Response, nome: &str) -> &'a str {
+    response.headers()[nome].to_str().unwrap()
+}
+
+// Linhas do CSV, sem o BOM
+async fn linhas(response: Response) -> Vec<String> {
+    let texto = response.text().await.unwrap();
+    let texto = texto.strip_prefix('\u{feff}').expect("O CSV começa com o BOM");
+    texto.lines().map(String::from).collect()
+}
+
+#[actix_rt::test]
+async fn unidades_are_exported_as_csv_with_filters_and_order() {
+    let app = create_app().await;
+    cria_unidade(&app, "UPA Sul", "upasul@gmail.com", "Natal").await;
+    cria_unidade(&app, "UPA Norte", "upanorte@gmail.com", "Natal").await;
+    cria_unidade(&app, "Hospital Regional", "regional@gmail.com", "Mossoró").await;
+
+    let response = get(&app.api_client, &app, "/unidades?municipio=natal&ordenar=nome&limit=1", "text/csv").await;
+    assert_eq!(200, response.status().as_u16());
+    assert_eq!("text/csv; charset=utf-8", cabecalho(&response, "content-type"));
+    assert_eq!("attachment; filename=\"unidades.csv\"", cabecalho(&response, "content-disposition"));
+
+    // a exportação ignora o limite da página e traz todas as linhas
+    let linhas = linhas(response).await;
+    assert_eq!("id;nome;email;tipo;municipio;desativado_em", linhas[0]);
+    assert_eq!(3, linhas.len());
+    assert!(linhas[1].contains(";UPA Norte;upanorte@gmail.com;UPA;Natal;"));
+    assert!(linhas[2].contains(";UPA Sul;"));
+}
+
+#[actix_rt::test]
+async fn csv_cells_that_look_like_formulas_are_escaped() {
+    let app = create_app().await;
+    cria_unidade(&app, "=HYPERLINK(\"http://x\")", "upasul@gmail.com", "Natal").await;
+    cria_unidade(&app, "@SUM(A1)", "upanorte@gmail.com", "+Natal").await;
+    cria_unidade(&app, "UPA - Centro", "centro@gmail.com", "-Natal").await;
+
+    let response = get(&app.api_client, &app, "/unidades?ordenar=nome", "text/csv").await;
+    let linhas = linhas(response).await;
+    assert_eq!(4, linhas.len());
+    assert!(linhas[1].contains(";\"'=HYPERLINK(\"\"http://x\"\")\";upasul@gmail.com;"));
+    assert!(linhas[2].ends_with(";'@SUM(A1);upanorte@gmail.com;UPA;'+Natal;"));
+    // só o início da célula conta
+    assert!(linhas[3].ends_with(";UPA - Centro;centro@gmail.com;UPA;'-Natal;"));
+}
+
+#[actix_rt::test]
+async fn json_remains_the_default() {
+    let app = create_app().await;
+    cria_unidade(&app, "UPA Sul", "upasul@gmail.com", "Natal").await;
+
+    for accept in &["*/*", "application/json", "application/pdf", "text/csv;q=0.5, application/json"] {
+        let response = get(&app.api_client, &app, "/unidades", accept).await;
+        assert_eq!(200, response.status().as_u16());
+        let pagina: Pagina<UnidadeSaude> = response.json().await.unwrap();
+        assert_eq!(1, pagina.total);
+    }
+
+    let response = get(&app.api_client, &app, "/unidades", "application/json;q=0.8, text/csv").await;
+    assert!(cabecalho(&response, "content-type").starts_with("text/csv"));
+}
+
+#[actix_rt::test]
+async fn json_export_is_streamed_as_an_array() {
+    let app = create_app().await;
+    let norte = cria_unidade(&app, "UPA Norte", "upanorte@gmail.com", "Natal").await;
+    let sul = cria_unidade(&app, "UPA Sul", "upasul@gmail.com", "Natal").await;
+    cria_leito(&app, TipoLeito::UtiAdulto, norte).await;
+
+    let response = get(&app.api_client, &app, "/leitos_disponiveis", "application/json").await;
+    assert_eq!(200, response.status().as_u16());
+    assert_eq!("application/json", cabecalho(&response, "content-type"));
+    // sem Content-Length: o corpo sai em partes
+    assert!(response.content_length().is_none());
+    let unidades: Vec<UnidadeSaude> = response.json().await.unwrap();
+    assert_eq!(vec![norte], unidades.iter().map(|u| u.id).collect::<Vec<Uuid>>());
+
+    cria_leito(&app, TipoLeito::UtiAdulto, sul).await;
+    let unidades: Vec<UnidadeSaude> = get(&app.api_client, &app, "/leitos_disponiveis", "application/json")
+        .await
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(2, unidades.len());
+
+    sqlx::query!("UPDATE leito SET situacao = 'bloqueado'")
+        .execute(&app.db_pool)
+        .await
+        .expect("Failed to update leitos.");
+    let response = get(&app.api_client, &app, "/leitos_disponiveis", "application/json").await;
+    assert_eq!("[]", response.text().await.unwrap());
+}
+
+#[actix_rt::test]
+async fn xlsx_export_refuses_more_rows_than_the_limit() {
+    let app = create_app().await;
+    sqlx::query!(
+        r#"
+        INSERT INTO unidadeSaude (id, nome, email, tipo, municipio)
+        SELECT gen_random_uuid(), 'UPA ' || n, 'upa' || n || '@gmail.com', 'UPA', 'Natal'
+        FROM generate_series(0, $1) as n
+        "#,
+        LINHAS_MAXIMAS_XLSX as i32
+    )
+    .execute(&app.db_pool)
+    .await
+    .expect("Failed to insert unidades.");
+
+    let response = get(&app.api_client, &app, "/unidades", XLSX).await;
+    assert_eq!(422, response.status().as_u16());
+
+    // o CSV não tem limite
+    let response = get(&app.api_client, &app, "/unidades", "text/csv").await;
+    assert_eq!(200, response.status().as_u16());
+    assert_eq!(LINHAS_MAXIMAS_XLSX as usize + 2, linhas(response).await.len());
+}
+
+#[actix_rt::test]
+async fn leitos_are_exported_as_xlsx() {
+    let app = create_app().await;
+    let unidade_id = cria_unidade(&app, "UPA Norte", "upanorte@gmail.com", "Natal").await;
+    cria_leito(&app, TipoLeito::UtiAdulto, unidade_id).await;
+
+    let response = get(&app.api_client, &app, "/leitos", XLSX).await;
+    assert_eq!(200, response.status().as_u16());
+    assert_eq!(XLSX, cabecalho(&response, "content-type"));
+    assert_eq!("attachment; filename=\"leitos.xlsx\"", cabecalho(&response, "content-disposition"));
+
+    // o XLSX é um pacote zip
+    let corpo = response.bytes().await.unwrap();
+    assert!(corpo.starts_with(b"PK\x03\x04"));
+}
+
+#[actix_rt::test]
+async fn exported_leitos_can_be_imported_back() {
+    let app = create_app().await;
+    let unidade_id = cria_unidade(&app, "UPA Norte", "upanorte@gmail.com", "Natal").await;
+    cria_leito(&app, TipoLeito::UtiAdulto, unidade_id).await;
+    cria_leito(&app, TipoLeito::EnfermariaClinica, unidade_id).await;
+
+    let response = get(&app.api_client, &app, "/leitos?ordenar=tipo", "text/csv").await;
+    let linhas = linhas(response).await;
+    assert_eq!(
+        "id;tipo;situacao;unidade_id;ventilador_mecanico;isolamento_pressao_negativa;hemodialise;desativado_em",
+        linhas[0]
+    );
+    assert!(linhas[1].ends_with(&format!(";UtiAdulto;Livre;{};não;não;não;", unidade_id)));
+
+    let response = app.api_client
+        .post(format!("{}/importacoes/leitos?simular=true", &app.address))
+        .header("Content-Type", "text/csv")
+        .body(linhas.join("\n"))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    assert_eq!(200, response.status().as_u16());
+}
+
+#[actix_rt::test]
+async fn leitos_disponiveis_export_respects_scope() {
+    let app = create_app().await;
+    let natal = cria_unidade(&app, "UPA Norte", "upanorte@gmail.com", "Natal").await;
+    let mossoro = cria_unidade(&app, "Hospital Regional", "regional@gmail.com", "Mossoró").await;
+    cria_unidade(&app, "UPA Sul", "upasul@gmail.com", "Natal").await;
+    cria_leito(&app, TipoLeito::UtiAdulto, natal).await;
+    cria_leito(&app, TipoLeito::UtiAdulto, mossoro).await;
+
+    let response = get(&app.api_client, &app, "/leitos_disponiveis", "text/csv").await;
+    assert_eq!(200, response.status().as_u16());
+    assert_eq!("attachment; filename=\"leitos_disponiveis.csv\"", cabecalho(&response, "content-disposition"));
+    assert_eq!(3, linhas(response).await.len());
+
+    let client = app.client_com_papel(Papel::GestorMunicipal, None, Some("Natal")).await;
+    let response = get(&client, &app, "/leitos_disponiveis", "text/csv").await;
+    let linhas = linhas(response).await;
+    assert_eq!(2, linhas.len());
+    assert!(linhas[1].starts_with(&format!("{};UPA Norte;", natal)));
+
+    let unidades: Vec<UnidadeSaude> = get(&client, &app, "/leitos_disponiveis", "application/json")
+        .await
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(vec![natal], unidades.iter().map(|u| u.id).collect::<Vec<Uuid>>());
+}
+
+#[actix_rt::test]
+async fn pacientes_covid_are_exported_as_csv() {
+    let app = create_app().await;
+    let unidade_id = cria_unidade(&app, "UPA Norte", "upanorte@gmail.com", "Natal").await;
+    for (nome, situacao) in &[("Fulano", SituacaoCovid::Confirmado), ("Beltrano", SituacaoCovid::Suspeito)] {
+        let leito_id = cria_leito(&app, TipoLeito::UtiAdulto, unidade_id).await;
+        app.post_paciente(
+            String::from(*nome),
+            String::from("Masculino"),
+            54,
+            format!("{}@gmail.com", nome.to_lowercase()),
+            String::from("84991223344"),
+            Some(*situacao),
+            leito_id
+        ).await;
+    }
+
+    let caminho = format!("/pacientes/covid/{}", unidade_id);
+    let response = get(&app.api_client, &app, &caminho, "text/csv").await;
+    assert_eq!(200, response.status().as_u16());
+    let linhas = linhas(response).await;
+    assert_eq!("id;nome;sexo;idade;email;telefone;situacao_covid;leito_id", linhas[0]);
+    assert_eq!(2, linhas.len());
+    assert!(linhas[1].contains(";Fulano;Masculino;54;fulano@gmail.com;84991223344;Confirmado;"));
+
+    let response = get(&app.api_client, &app, &format!("{}?situacao=Suspeito", caminho), XLSX).await;
+    assert_eq!(200, response.status().as_u16());
+    assert_eq!(XLSX, cabecalho(&response, "content-type"));
+}
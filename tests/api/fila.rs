@@ -0,0 +1,208 @@
+use crate::helpers::{create_app, TestApp};
+use std::collections::HashMap;
+use reqwest::Response;
+use gerenciador_leitos::domain::{SituacaoLeito, TipoLeito};
+use gerenciador_leitos::error::ErrorBody;
+use gerenciador_leitos::query::Pagina;
+use gerenciador_leitos::routes::{EntradaFila, LeitoId, PacienteId, PropostaLeito, UnidadeId};
+use uuid::Uuid;
+
+async fn cria_leito(app: &TestApp, tipo: TipoLeito, municipio: &str) -> Uuid {
+    let response: Response = app.post_unidade(String::from("UBS Aux"), format!("{}@gmail.com", Uuid::new_v4()), String::from("UBS"), String::from(municipio)).await;
+    let hospital_id: UnidadeId = response.json().await.unwrap();
+
+    let response: Response = app.post_leito(tipo, SituacaoLeito::Livre, hospital_id.id).await;
+    assert_eq!(200, response.status().as_u16());
+    let leito_id: LeitoId = response.json().await.unwrap();
+    leito_id.id
+}
+
+// cria um paciente sem leito
+async fn cria_paciente(app: &TestApp) -> Uuid {
+    let body = serde_json::json!({
+        "nome": "Fulano",
+        "sexo": "Masculino",
+        "idade": 54,
+        "email": "fulano@gmail.com",
+        "telefone": "84991223344",
+        "situacao_covid": "Confirmado"
+    });
+
+    let response = app.api_client
+        .post(format!("{}/pacientes", &app.address))
+        .json(&body)
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    assert_eq!(200, response.status().as_u16());
+
+    let paciente_id: PacienteId = response.json().await.unwrap();
+    paciente_id.id
+}
+
+async fn entra_na_fila(app: &TestApp, paciente_id: Uuid, prioridade: i16, tipo: TipoLeito) -> Response {
+    let body = serde_json::json!({
+        "paciente_id": paciente_id.to_string(),
+        "prioridade": prioridade,
+        "tipo_leito": tipo,
+        "municipio_origem": "Natal"
+    });
+
+    app.api_client
+        .post(format!("{}/fila", &app.address))
+        .json(&body)
+        .send()
+        .await
+        .expect("Failed to execute request.")
+}
+
+async fn get_fila(app: &TestApp, parametros: &str) -> Pagina<EntradaFila> {
+    let response = app.api_client
+        .get(format!("{}/fila{}", &app.address, parametros))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    assert_eq!(200, response.status().as_u16());
+    response.json().await.unwrap()
+}
+
+#[actix_rt::test]
+async fn get_fila_orders_by_prioridade_then_arrival() {
+    let app = create_app().await;
+    let leve = cria_paciente(&app).await;
+    let grave = cria_paciente(&app).await;
+    let grave_depois = cria_paciente(&app).await;
+
+    for (paciente_id, prioridade) in [(leve, 2), (grave, 5), (grave_depois, 5)].iter() {
+        let response = entra_na_fila(&app, *paciente_id, *prioridade, TipoLeito::UtiAdulto).await;
+        assert_eq!(200, response.status().as_u16());
+    }
+
+    let fila = get_fila(&app, "").await;
+    let ordem: Vec<Uuid> = fila.itens.iter().map(|entrada| entrada.paciente_id).collect();
+    assert_eq!(ordem, vec![grave, grave_depois, leve]);
+
+    // o cursor continua de onde a página anterior parou, na mesma ordem
+    let primeira = get_fila(&app, "?limit=2").await;
+    assert_eq!(3, primeira.total);
+    let ordem: Vec<Uuid> = primeira.itens.iter().map(|entrada| entrada.paciente_id).collect();
+    assert_eq!(ordem, vec![grave, grave_depois]);
+    let cursor = primeira.next_cursor.expect("A fila tem mais uma página");
+    let segunda = get_fila(&app, &format!("?limit=2&cursor={}", cursor)).await;
+    let ordem: Vec<Uuid> = segunda.itens.iter().map(|entrada| entrada.paciente_id).collect();
+    assert_eq!(ordem, vec![leve]);
+    assert!(segunda.next_cursor.is_none());
+}
+
+#[actix_rt::test]
+async fn create_entrada_fila_returns_400_for_invalid_prioridade() {
+    let app = create_app().await;
+    let paciente_id = cria_paciente(&app).await;
+
+    let response = entra_na_fila(&app, paciente_id, 6, TipoLeito::UtiAdulto).await;
+    assert_eq!(400, response.status().as_u16());
+
+    let body: ErrorBody = response.json().await.unwrap();
+    assert_eq!(body.fields[0].field, "prioridade");
+}
+
+#[actix_rt::test]
+async fn create_entrada_fila_returns_400_for_blank_municipio_origem() {
+    let app = create_app().await;
+    let paciente_id = cria_paciente(&app).await;
+
+    let response = app.api_client
+        .post(format!("{}/fila", &app.address))
+        .json(&serde_json::json!({
+            "paciente_id": paciente_id.to_string(),
+            "prioridade": 3,
+            "tipo_leito": TipoLeito::UtiAdulto,
+            "municipio_origem": "  "
+        }))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    assert_eq!(400, response.status().as_u16());
+
+    let body: ErrorBody = response.json().await.unwrap();
+    assert_eq!(body.fields[0].field, "municipio_origem");
+    assert_eq!(0, get_fila(&app, "").await.total);
+}
+
+#[actix_rt::test]
+async fn create_entrada_fila_returns_409_when_already_waiting() {
+    let app = create_app().await;
+    let paciente_id = cria_paciente(&app).await;
+
+    let response = entra_na_fila(&app, paciente_id, 3, TipoLeito::UtiAdulto).await;
+    assert_eq!(200, response.status().as_u16());
+
+    let response = entra_na_fila(&app, paciente_id, 4, TipoLeito::UtiAdulto).await;
+    assert_eq!(409, response.status().as_u16());
+}
+
+#[actix_rt::test]
+async fn proposta_prefers_free_leito_of_same_tipo_and_municipio() {
+    let app = create_app().await;
+    cria_leito(&app, TipoLeito::UtiAdulto, "Mossoró").await;
+    cria_leito(&app, TipoLeito::EnfermariaClinica, "Natal").await;
+    let esperado = cria_leito(&app, TipoLeito::UtiAdulto, "Natal").await;
+    let paciente_id = cria_paciente(&app).await;
+
+    let response = entra_na_fila(&app, paciente_id, 4, TipoLeito::UtiAdulto).await;
+    let entrada: EntradaFila = response.json().await.unwrap();
+
+    let response = app.api_client
+        .get(format!("{}/fila/{}/proposta", &app.address, entrada.id))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    assert_eq!(200, response.status().as_u16());
+
+    let proposta: PropostaLeito = response.json().await.unwrap();
+    assert_eq!(proposta.leito_id, esperado);
+    assert!(proposta.mesmo_municipio);
+}
+
+#[actix_rt::test]
+async fn proposta_returns_404_without_compatible_leito() {
+    let app = create_app().await;
+    cria_leito(&app, TipoLeito::EnfermariaClinica, "Natal").await;
+    let paciente_id = cria_paciente(&app).await;
+
+    let response = entra_na_fila(&app, paciente_id, 4, TipoLeito::UtiNeonatal).await;
+    let entrada: EntradaFila = response.json().await.unwrap();
+
+    let response = app.api_client
+        .get(format!("{}/fila/{}/proposta", &app.address, entrada.id))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    assert_eq!(404, response.status().as_u16());
+}
+
+#[actix_rt::test]
+async fn atender_entrada_fila_admits_paciente_and_leaves_queue() {
+    let app = create_app().await;
+    let leito_id = cria_leito(&app, TipoLeito::UtiAdulto, "Natal").await;
+    let paciente_id = cria_paciente(&app).await;
+
+    let response = entra_na_fila(&app, paciente_id, 4, TipoLeito::UtiAdulto).await;
+    let entrada: EntradaFila = response.json().await.unwrap();
+
+    let mut map = HashMap::new();
+    map.insert("leito_id", leito_id.to_string());
+    let response = app.api_client
+        .post(format!("{}/fila/{}/atender", &app.address, entrada.id))
+        .json(&map)
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    assert_eq!(200, response.status().as_u16());
+
+    let atendida: EntradaFila = response.json().await.unwrap();
+    assert!(atendida.atendida_em.is_some());
+    assert!(atendida.internacao_id.is_some());
+
+    assert!(get_fila(&app, "").await.itens.is_empty());
+}
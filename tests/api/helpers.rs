@@ -3,7 +3,8 @@ use gerenciador_leitos::configuration::{get_configuration, DatabaseSettings};
 use gerenciador_leitos::startup::{get_connection_pool, Application};
 use sqlx::{Connection, Executor, PgConnection, PgPool};
 use uuid::Uuid;
-use gerenciador_leitos::routes::{UnidadeData, LeitoData, PacienteData};
+use gerenciador_leitos::routes::{UnidadeData, LeitoData, PacienteData, TokenAcesso};
+use gerenciador_leitos::domain::{MotivoAlta, Papel, SituacaoCovid, SituacaoLeito, TipoLeito};
 use std::collections::HashMap;
 use gerenciador_leitos::telemetry::{get_subscriber, init_subscriber};
 
@@ -19,8 +20,13 @@ lazy_static::lazy_static! {
 pub struct TestApp {
     pub address: String,
     pub db_pool: PgPool,
+    // cliente HTTP autenticado com o token do usuário de teste
+    pub api_client: reqwest::Client,
 }
 
+pub const EMAIL_TESTE: &str = "teste@sigleitos.com";
+pub const SENHA_TESTE: &str = "senha-de-teste";
+
 impl TestApp {
     // cria uma nova unidade usando HTTP POST na rota /unidades
     // note que o username do usuário é uma String fake gerada automaticamente
@@ -32,9 +38,8 @@ impl TestApp {
         map.insert("municipio", unidade.municipio.clone());
         map.insert("tipo", unidade.tipo.clone());
 
-        let client = reqwest::Client::new();
-        let response = client
-            .post(&format!("{}/unidades", &self.address))
+        let response = self.api_client
+            .post(format!("{}/unidades", &self.address))
             .header("Content-Type", "application/json")
             .json(&map)
             .send()
@@ -44,16 +49,22 @@ impl TestApp {
         response
     }
 
-    pub async fn post_leito(&self, tipo: String, situacao: String, unidade_id: Uuid) -> reqwest::Response {
-        let unidade = LeitoData { tipo, situacao, unidade_id };
+    pub async fn post_leito(&self, tipo: TipoLeito, situacao: SituacaoLeito, unidade_id: Uuid) -> reqwest::Response {
+        let unidade = LeitoData {
+            tipo,
+            situacao,
+            ventilador_mecanico: false,
+            isolamento_pressao_negativa: false,
+            hemodialise: false,
+            unidade_id
+        };
         let mut map = HashMap::new();
-        map.insert("tipo", unidade.tipo.clone());
-        map.insert("situacao", unidade.situacao.clone());
+        map.insert("tipo", unidade.tipo.to_string());
+        map.insert("situacao", unidade.situacao.to_string());
         map.insert("unidade_id", unidade.unidade_id.to_string());
 
-        let client = reqwest::Client::new();
-        let response = client
-            .post(&format!("{}/leitos", &self.address))
+        let response = self.api_client
+            .post(format!("{}/leitos", &self.address))
             .header("Content-Type", "application/json")
             .json(&map)
             .send()
@@ -63,20 +74,65 @@ impl TestApp {
         response
     }
 
-    pub async fn post_paciente(&self, nome: String, sexo: String, idade: String, email: String, telefone: String, covid_19: String, leito_id: Uuid) -> reqwest::Response {
-        let unidade = PacienteData { nome, sexo, idade, email, telefone, covid_19, leito_id };
+    #[allow(clippy::too_many_arguments)]
+    pub async fn post_paciente(&self, nome: String, sexo: String, idade: i32, email: String, telefone: String, situacao_covid: Option<SituacaoCovid>, leito_id: Uuid) -> reqwest::Response {
+        let paciente = PacienteData { nome, sexo, idade, email, telefone, situacao_covid, leito_id: Some(leito_id) };
+
+        let response = self.api_client
+            .post(format!("{}/pacientes", &self.address))
+            .header("Content-Type", "application/json")
+            .json(&paciente)
+            .send()
+            .await
+            .expect("Failed to execute request.");
+
+        response
+    }
+
+    // cadastra, pelo admin de teste, um usuário com o papel informado e devolve um cliente logado com ele
+    pub async fn client_com_papel(&self, papel: Papel, unidade_id: Option<Uuid>, municipio: Option<&str>) -> reqwest::Client {
+        let email = format!("{}@sigleitos.com", Uuid::new_v4());
+        let body = serde_json::json!({
+            "nome": papel.to_string(),
+            "email": email,
+            "senha": SENHA_TESTE,
+            "papel": papel,
+            "unidade_id": unidade_id.map(|id| id.to_string()),
+            "municipio": municipio
+        });
+
+        let response = self.api_client
+            .post(format!("{}/usuarios", &self.address))
+            .json(&body)
+            .send()
+            .await
+            .expect("Failed to execute request.");
+        assert_eq!(200, response.status().as_u16());
+
+        login_client(&self.address, &email, SENHA_TESTE).await
+    }
+
+    pub async fn post_internacao(&self, paciente_id: Uuid, leito_id: Uuid) -> reqwest::Response {
         let mut map = HashMap::new();
-        map.insert("nome", unidade.nome.clone());
-        map.insert("sexo", unidade.sexo.clone());
-        map.insert("idade", unidade.idade.to_string());
-        map.insert("email", unidade.email.to_string());
-        map.insert("telefone", unidade.telefone.to_string());
-        map.insert("covid_19", unidade.covid_19.to_string());
-        map.insert("leito_id", unidade.leito_id.to_string());
-
-        let client = reqwest::Client::new();
-        let response = client
-            .post(&format!("{}/pacientes", &self.address))
+        map.insert("leito_id", leito_id.to_string());
+
+        let response = self.api_client
+            .post(format!("{}/pacientes/{}/internar", &self.address, paciente_id))
+            .header("Content-Type", "application/json")
+            .json(&map)
+            .send()
+            .await
+            .expect("Failed to execute request.");
+
+        response
+    }
+
+    pub async fn post_alta(&self, internacao_id: Uuid, motivo: MotivoAlta) -> reqwest::Response {
+        let mut map = HashMap::new();
+        map.insert("motivo", motivo);
+
+        let response = self.api_client
+            .post(format!("{}/internacoes/{}/alta", &self.address, internacao_id))
             .header("Content-Type", "application/json")
             .json(&map)
             .send()
@@ -85,10 +141,32 @@ impl TestApp {
 
         response
     }
+
+    // ETag atual do recurso, exigido no If-Match das escritas; None se ele não existir
+    pub async fn etag(&self, caminho: &str) -> Option<String> {
+        let response = self.api_client
+            .get(format!("{}{}", &self.address, caminho))
+            .send()
+            .await
+            .expect("Failed to execute request.");
+
+        response.headers()
+            .get("etag")
+            .map(|etag| etag.to_str().unwrap().to_string())
+    }
 }
 
 // Cria uma nova instância da API
 pub async fn create_app() -> TestApp {
+    let app = create_app_sem_usuarios().await;
+    let api_client = authenticated_client(&app.address).await;
+
+    TestApp { api_client, ..app }
+}
+
+// Sobe a aplicação com o banco vazio, antes do cadastro do primeiro usuário; o cliente
+// não envia token
+pub async fn create_app_sem_usuarios() -> TestApp {
     lazy_static::initialize(&TRACING);
 
     // Randomise configuration to ensure test isolation
@@ -109,16 +187,59 @@ pub async fn create_app() -> TestApp {
         .await
         .expect("Failed to build application.");
     let address = format!("http://localhost:{}", application.port());
-    let _ = tokio::spawn(application.run_until_stopped());
+    tokio::spawn(application.run_until_stopped());
 
     TestApp {
         address,
         db_pool: get_connection_pool(&configuration.database)
             .await
             .expect("Failed to connect to the database"),
+        api_client: reqwest::Client::new(),
     }
 }
 
+// Cadastra o usuário de teste (o primeiro cadastro dispensa token) e faz login com ele
+async fn authenticated_client(address: &str) -> reqwest::Client {
+    let mut map = HashMap::new();
+    map.insert("nome", "Teste");
+    map.insert("email", EMAIL_TESTE);
+    map.insert("senha", SENHA_TESTE);
+    map.insert("papel", "Admin");
+    let response = reqwest::Client::new()
+        .post(format!("{}/usuarios", address))
+        .json(&map)
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    assert_eq!(200, response.status().as_u16());
+
+    login_client(address, EMAIL_TESTE, SENHA_TESTE).await
+}
+
+// Faz login e devolve um cliente que envia o token em todas as requisições
+async fn login_client(address: &str, email: &str, senha: &str) -> reqwest::Client {
+    let mut map = HashMap::new();
+    map.insert("email", email);
+    map.insert("senha", senha);
+    let response = reqwest::Client::new()
+        .post(format!("{}/login", address))
+        .json(&map)
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    let token: TokenAcesso = response.json().await.expect("Failed to log in.");
+
+    let mut headers = reqwest::header::HeaderMap::new();
+    headers.insert(
+        reqwest::header::AUTHORIZATION,
+        format!("Bearer {}", token.token).parse().unwrap(),
+    );
+    reqwest::Client::builder()
+        .default_headers(headers)
+        .build()
+        .expect("Failed to build client.")
+}
+
 // Configura um novo banco de dados a cada teste executado, 
 // promovento isolamento entre os testes
 async fn configure_database(config: &DatabaseSettings) -> PgPool {
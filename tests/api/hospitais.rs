@@ -2,12 +2,14 @@ use crate::helpers::create_app;
 use std::collections::HashMap;
 use reqwest::Response;
 use gerenciador_leitos::routes::{UnidadeSaude, UnidadeId};
+use gerenciador_leitos::error::ErrorBody;
 use sqlx::{types::Uuid, Row};
+use gerenciador_leitos::query::Pagina;
 
 #[actix_rt::test]
 async fn create_unidade_returns_200() {
     let app = create_app().await;
-    let client = reqwest::Client::new();
+    let client = &app.api_client;
     let mut map = HashMap::new();
     map.insert("nome", "UBS Teste");
     map.insert("email", "ubsteste@gmail.com");
@@ -15,7 +17,7 @@ async fn create_unidade_returns_200() {
     map.insert("tipo", "UBS");
     
     let response = client
-        .post(&format!("{}/unidades", &app.address))
+        .post(format!("{}/unidades", &app.address))
         .header("Content-Type", "application/json")
         .json(&map)
         .send()
@@ -38,7 +40,7 @@ async fn create_unidade_returns_200() {
 #[actix_rt::test]
 async fn get_all_unidades_returns_200() {
     let app = create_app().await;
-    let client = reqwest::Client::new();
+    let client = &app.api_client;
 
     // cria primeira unidade
     let nome_1 = String::from("UBS Teste 1");
@@ -60,7 +62,7 @@ async fn get_all_unidades_returns_200() {
 
     // faz o pedido de todos os usuários na rota /users
     let response = client
-        .get(&format!("{}/unidades", &app.address))
+        .get(format!("{}/unidades", &app.address))
         .header("Content-Type", "application/json")
         .send()
         .await
@@ -69,14 +71,14 @@ async fn get_all_unidades_returns_200() {
     assert_eq!(200, response.status().as_u16());
 
     // verifica se foram retornados 2 usuários
-    let unidades: Vec<UnidadeId> = response.json().await.unwrap();
+    let unidades: Vec<UnidadeId> = response.json::<Pagina<UnidadeId>>().await.unwrap().itens;
     assert_eq!(2, unidades.len());
 }
 
 #[actix_rt::test]
 async fn get_unidade_by_id_returns_200() {
     let app = create_app().await;
-    let client = reqwest::Client::new();
+    let client = &app.api_client;
 
     // cria primeira unidade
     let nome_1 = String::from("UBS Teste 1");
@@ -90,7 +92,7 @@ async fn get_unidade_by_id_returns_200() {
     
     // consulta o usuário criado usando HTTP GET pela rota /users/{id}
     let response = client
-        .get(&format!("{}/unidades/{}", &app.address, unidade_id.id))
+        .get(format!("{}/unidades/{}", &app.address, unidade_id.id))
         .header("Content-Type", "application/json")
         .send()
         .await
@@ -110,7 +112,7 @@ async fn get_unidade_by_id_returns_200() {
 #[actix_rt::test]
 async fn update_unidade_returns_200() {
     let app = create_app().await;
-    let client = reqwest::Client::new();
+    let client = &app.api_client;
 
     // cria unidade
     let nome = String::from("UBS Update 1");
@@ -128,19 +130,20 @@ async fn update_unidade_returns_200() {
         nome: String::from("UBS Update 2"),
         email: String::from("ubsupdate1@gmail.com"),
         tipo,
-        municipio
+        municipio,
+        desativado_em: None
     };
 
     // gera um HashMap que será mapeado pro json a ser enviado na requisição de atualização
     let mut map = HashMap::new();
-    map.insert("id", unidade.id.to_string());
     map.insert("nome", unidade.nome.clone());
     map.insert("email", unidade.email.clone());
     map.insert("tipo", unidade.tipo.clone());
     map.insert("municipio", unidade.municipio.clone());
 
     let response = client
-        .put(&format!("{}/unidades", &app.address))
+        .put(format!("{}/unidades/{}", &app.address, unidade.id))
+        .header("If-Match", app.etag(&format!("/unidades/{}", unidade.id)).await.unwrap())
         .header("Content-Type", "application/json")
         .json(&map)
         .send()
@@ -162,7 +165,7 @@ async fn update_unidade_returns_200() {
 #[actix_rt::test]
 async fn delete_unidade_returns_200() {
     let app = create_app().await;
-    let client = reqwest::Client::new();
+    let client = &app.api_client;
 
     // cria unidade
     let nome = String::from("UBS Delete");
@@ -175,7 +178,8 @@ async fn delete_unidade_returns_200() {
     let id: Uuid = user_id.id;
 
     let response = client
-        .delete(&format!("{}/unidades/{}", &app.address, id))
+        .delete(format!("{}/unidades/{}", &app.address, id))
+        .header("If-Match", app.etag(&format!("/unidades/{}", id)).await.unwrap())
         .header("Content-Type", "application/json")
         .send()
         .await
@@ -193,4 +197,67 @@ async fn delete_unidade_returns_200() {
 
     // verifica se foi retornada alguma coisa, se sim, o usuário não foi removido, levantando falha
     assert_eq!(count, 0);
-}
\ No newline at end of file
+}
+
+#[actix_rt::test]
+async fn get_unidade_by_id_returns_404_for_unknown_id() {
+    let app = create_app().await;
+    let client = &app.api_client;
+
+    let response = client
+        .get(format!("{}/unidades/{}", &app.address, Uuid::new_v4()))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+
+    assert_eq!(404, response.status().as_u16());
+
+    let body: ErrorBody = response.json().await.unwrap();
+    assert_eq!(body.code, "not_found");
+}
+
+#[actix_rt::test]
+async fn create_unidade_returns_409_for_duplicate_email() {
+    let app = create_app().await;
+
+    let nome = String::from("UBS Teste");
+    let email = String::from("ubsteste@gmail.com");
+    let tipo = String::from("UBS");
+    let municipio = String::from("Natal");
+
+    let response: Response = app.post_unidade(nome.clone(), email.clone(), tipo.clone(), municipio.clone()).await;
+    assert_eq!(200, response.status().as_u16());
+
+    // tenta cadastrar outra unidade com o mesmo email
+    let response: Response = app.post_unidade(nome, email, tipo, municipio).await;
+    assert_eq!(409, response.status().as_u16());
+
+    let body: ErrorBody = response.json().await.unwrap();
+    assert_eq!(body.code, "conflict");
+    assert_eq!(body.fields.len(), 1);
+    assert_eq!(body.fields[0].field, "email");
+}
+
+#[actix_rt::test]
+async fn create_unidade_returns_400_for_missing_field() {
+    let app = create_app().await;
+    let client = &app.api_client;
+    let mut map = HashMap::new();
+    map.insert("nome", "UBS Teste");
+    map.insert("municipio", "Natal");
+    map.insert("tipo", "UBS");
+
+    let response = client
+        .post(format!("{}/unidades", &app.address))
+        .header("Content-Type", "application/json")
+        .json(&map)
+        .send()
+        .await
+        .expect("Failed to execute request.");
+
+    assert_eq!(400, response.status().as_u16());
+
+    let body: ErrorBody = response.json().await.unwrap();
+    assert_eq!(body.code, "validation_error");
+    assert_eq!(body.fields[0].field, "email");
+}
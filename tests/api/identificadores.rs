@@ -0,0 +1,86 @@
+use crate::helpers::create_app;
+use gerenciador_leitos::error::ErrorBody;
+use reqwest::Method;
+use uuid::Uuid;
+
+// todas as rotas que recebem um identificador no caminho
+const ROTAS_COM_ID: [(&str, &str); 9] = [
+    ("GET", "/unidades"),
+    ("DELETE", "/unidades"),
+    ("GET", "/leitos"),
+    ("DELETE", "/leitos"),
+    ("GET", "/pacientes"),
+    ("DELETE", "/pacientes"),
+    ("GET", "/pacientes/covid"),
+    ("GET", "/internacoes"),
+    ("GET", "/transferencias"),
+];
+
+async fn send(client: &reqwest::Client, method: &str, url: String) -> reqwest::Response {
+    client
+        .request(Method::from_bytes(method.as_bytes()).unwrap(), url)
+        .send()
+        .await
+        .expect("Failed to execute request.")
+}
+
+#[actix_rt::test]
+async fn malformed_uuid_returns_400_on_every_id_route() {
+    let app = create_app().await;
+    let client = &app.api_client;
+
+    for (method, rota) in ROTAS_COM_ID.iter() {
+        for id in ["abc", "123", "not-a-uuid-at-all-0000000000000000"].iter() {
+            let response = send(client, method, format!("{}{}/{}", &app.address, rota, id)).await;
+
+            assert_eq!(400, response.status().as_u16(), "{} {}/{}", method, rota, id);
+
+            let body: ErrorBody = response.json().await.unwrap();
+            assert_eq!(body.code, "validation_error");
+            assert_eq!(body.fields[0].field, "id");
+        }
+    }
+}
+
+#[actix_rt::test]
+async fn empty_uuid_returns_404_on_every_id_route() {
+    let app = create_app().await;
+    let client = &app.api_client;
+
+    for (method, rota) in ROTAS_COM_ID.iter() {
+        let response = send(client, method, format!("{}{}/", &app.address, rota)).await;
+
+        assert_eq!(404, response.status().as_u16(), "{} {}/", method, rota);
+
+        let body: ErrorBody = response.json().await.unwrap();
+        assert_eq!(body.code, "not_found");
+    }
+}
+
+#[actix_rt::test]
+async fn nil_uuid_returns_404_on_every_resource_route() {
+    let app = create_app().await;
+    let client = &app.api_client;
+
+    for (method, rota) in ROTAS_COM_ID.iter().filter(|(_, rota)| *rota != "/pacientes/covid") {
+        let response = send(client, method, format!("{}{}/{}", &app.address, rota, Uuid::nil())).await;
+
+        assert_eq!(404, response.status().as_u16(), "{} {}/nil", method, rota);
+
+        let body: ErrorBody = response.json().await.unwrap();
+        assert_eq!(body.code, "not_found");
+    }
+}
+
+#[actix_rt::test]
+async fn nil_uuid_returns_empty_list_for_pacientes_covid() {
+    let app = create_app().await;
+    let client = &app.api_client;
+
+    let response = send(client, "GET", format!("{}/pacientes/covid/{}", &app.address, Uuid::nil())).await;
+
+    assert_eq!(200, response.status().as_u16());
+
+    let pacientes: Vec<serde_json::Value> = response.json().await.unwrap();
+    assert!(pacientes.is_empty());
+}
@@ -0,0 +1,226 @@
+use crate::helpers::{create_app, TestApp};
+use reqwest::Response;
+use gerenciador_leitos::domain::{Papel, SituacaoLeito, TipoLeito};
+use gerenciador_leitos::error::ErrorBody;
+use gerenciador_leitos::query::Pagina;
+use gerenciador_leitos::routes::{Leito, ResultadoImportacao, UnidadeId, UnidadeSaude};
+use uuid::Uuid;
+
+async fn cria_unidade(app: &TestApp, nome: &str, email: &str) -> Uuid {
+    let response: Response = app.post_unidade(
+        String::from(nome),
+        String::from(email),
+        String::from("Hospital"),
+        String::from("Natal")
+    ).await;
+    let unidade: UnidadeId = response.json().await.unwrap();
+    unidade.id
+}
+
+async fn importa(client: &reqwest::Client, app: &TestApp, caminho: &str, csv: impl Into<Vec<u8>>) -> Response {
+    client
+        .post(format!("{}{}", &app.address, caminho))
+        .header("Content-Type", "text/csv")
+        .body(csv.into())
+        .send()
+        .await
+        .expect("Failed to execute request.")
+}
+
+async fn total(app: &TestApp, caminho: &str) -> i64 {
+    let pagina: Pagina<serde_json::Value> = app.api_client
+        .get(format!("{}{}", &app.address, caminho))
+        .send()
+        .await
+        .expect("Failed to execute request.")
+        .json()
+        .await
+        .unwrap();
+    pagina.total
+}
+
+fn campos(body: &ErrorBody) -> Vec<&str> {
+    body.fields.iter().map(|f| f.field.as_str()).collect()
+}
+
+#[actix_rt::test]
+async fn importa_unidades_from_semicolon_csv() {
+    let app = create_app().await;
+    let csv = "Nome;E-mail;Tipo;Município\n\
+               Hospital Regional;regional@saude.rn.gov.br;Hospital;Mossoró\n\
+               \n\
+               UPA Zona Norte;\"upanorte@saude.rn.gov.br\";UPA;Natal\n";
+
+    let response = importa(&app.api_client, &app, "/importacoes/unidades", csv).await;
+    assert_eq!(200, response.status().as_u16());
+    let resultado: ResultadoImportacao<UnidadeSaude> = response.json().await.unwrap();
+    assert!(!resultado.simulacao);
+    assert_eq!(2, resultado.total);
+    assert_eq!("Mossoró", resultado.criados[0].municipio);
+    assert_eq!("upanorte@saude.rn.gov.br", resultado.criados[1].email);
+
+    assert_eq!(2, total(&app, "/unidades").await);
+}
+
+#[actix_rt::test]
+async fn importa_unidades_in_latin1() {
+    let app = create_app().await;
+    let (csv, _, _) = encoding_rs::WINDOWS_1252.encode(
+        "nome;email;tipo;municipio\nHospital São José;saojose@gmail.com;Hospital;São Gonçalo do Amarante\n"
+    );
+
+    let response = importa(&app.api_client, &app, "/importacoes/unidades", csv.into_owned()).await;
+    assert_eq!(200, response.status().as_u16());
+    let resultado: ResultadoImportacao<UnidadeSaude> = response.json().await.unwrap();
+    assert_eq!("Hospital São José", resultado.criados[0].nome);
+    assert_eq!("São Gonçalo do Amarante", resultado.criados[0].municipio);
+}
+
+#[actix_rt::test]
+async fn simulacao_returns_what_would_be_created_without_saving() {
+    let app = create_app().await;
+    let csv = "nome;email;tipo;municipio\nUPA Sul;upasul@gmail.com;UPA;Natal\n";
+
+    let response = importa(&app.api_client, &app, "/importacoes/unidades?simular=true", csv).await;
+    assert_eq!(200, response.status().as_u16());
+    let resultado: ResultadoImportacao<UnidadeSaude> = response.json().await.unwrap();
+    assert!(resultado.simulacao);
+    assert_eq!(1, resultado.total);
+    assert_eq!("UPA Sul", resultado.criados[0].nome);
+
+    assert_eq!(0, total(&app, "/unidades").await);
+}
+
+#[actix_rt::test]
+async fn invalid_rows_are_reported_and_nothing_is_saved() {
+    let app = create_app().await;
+    let csv = "nome;email;tipo;municipio\n\
+               UPA Sul;upasul@gmail.com;UPA;Natal\n\
+               UPA Oeste;upaoeste@;UPA;Natal\n\
+               ;upaleste@gmail.com;UPA;\n";
+
+    let response = importa(&app.api_client, &app, "/importacoes/unidades", csv).await;
+    assert_eq!(400, response.status().as_u16());
+    let body: ErrorBody = response.json().await.unwrap();
+    assert_eq!(vec!["linhas[3].email", "linhas[4].nome", "linhas[4].municipio"], campos(&body));
+    assert!(body.message.contains("2 linha(s)"));
+
+    assert_eq!(0, total(&app, "/unidades").await);
+}
+
+#[actix_rt::test]
+async fn missing_columns_and_empty_files_are_refused() {
+    let app = create_app().await;
+
+    let response = importa(&app.api_client, &app, "/importacoes/unidades", "nome;email\nUPA;upa@gmail.com\n").await;
+    assert_eq!(400, response.status().as_u16());
+    let body: ErrorBody = response.json().await.unwrap();
+    assert_eq!(vec!["tipo", "municipio"], campos(&body));
+
+    let response = importa(&app.api_client, &app, "/importacoes/unidades", "nome;email;tipo;municipio\n").await;
+    assert_eq!(400, response.status().as_u16());
+}
+
+#[actix_rt::test]
+async fn importa_leitos_by_unidade_id_or_email() {
+    let app = create_app().await;
+    let norte = cria_unidade(&app, "UPA Norte", "upanorte@gmail.com").await;
+    let sul = cria_unidade(&app, "UPA Sul", "upasul@gmail.com").await;
+    let csv = format!(
+        "tipo;situacao;unidade_id;unidade_email;ventilador_mecanico;hemodialise\n\
+         UtiAdulto;;{};;sim;não\n\
+         EnfermariaClinica;Bloqueado;;UPASUL@gmail.com;;S\n",
+        norte
+    );
+
+    let response = importa(&app.api_client, &app, "/importacoes/leitos", csv).await;
+    assert_eq!(200, response.status().as_u16());
+    let resultado: ResultadoImportacao<Leito> = response.json().await.unwrap();
+    assert_eq!(2, resultado.total);
+
+    let uti = &resultado.criados[0];
+    assert_eq!((TipoLeito::UtiAdulto, SituacaoLeito::Livre, norte), (uti.tipo, uti.situacao, uti.unidade_id));
+    assert!(uti.ventilador_mecanico && !uti.hemodialise);
+    let enfermaria = &resultado.criados[1];
+    assert_eq!((SituacaoLeito::Bloqueado, sul), (enfermaria.situacao, enfermaria.unidade_id));
+    assert!(enfermaria.hemodialise);
+
+    assert_eq!(2, total(&app, "/leitos").await);
+}
+
+#[actix_rt::test]
+async fn invalid_leito_rows_are_reported() {
+    let app = create_app().await;
+    let norte = cria_unidade(&app, "UPA Norte", "upanorte@gmail.com").await;
+    let csv = format!(
+        "tipo;situacao;unidade_id;ventilador_mecanico\n\
+         UtiAdulto;Livre;{0};sim\n\
+         Maca;Livre;{0};\n\
+         UtiAdulto;Ocupado;{0};talvez\n\
+         UtiAdulto;Livre;{1};\n",
+        norte,
+        Uuid::new_v4()
+    );
+
+    let response = importa(&app.api_client, &app, "/importacoes/leitos", csv).await;
+    assert_eq!(400, response.status().as_u16());
+    let body: ErrorBody = response.json().await.unwrap();
+    assert_eq!(
+        vec!["linhas[3].tipo", "linhas[4].ventilador_mecanico", "linhas[4].situacao", "linhas[5].unidade_id"],
+        campos(&body)
+    );
+
+    assert_eq!(0, total(&app, "/leitos").await);
+}
+
+#[actix_rt::test]
+async fn funcionario_unidade_only_imports_leitos_into_own_unidade() {
+    let app = create_app().await;
+    let propria = cria_unidade(&app, "UPA Norte", "upanorte@gmail.com").await;
+    let outra = cria_unidade(&app, "UPA Sul", "upasul@gmail.com").await;
+    let client = app.client_com_papel(Papel::FuncionarioUnidade, Some(propria), None).await;
+
+    let csv = format!("tipo;unidade_id\nUtiAdulto;{}\nUtiAdulto;{}\n", propria, outra);
+    let response = importa(&client, &app, "/importacoes/leitos", csv).await;
+    assert_eq!(400, response.status().as_u16());
+    let body: ErrorBody = response.json().await.unwrap();
+    assert_eq!(vec!["linhas[3].unidade_id"], campos(&body));
+
+    let response = importa(&client, &app, "/importacoes/leitos", format!("tipo;unidade_id\nUtiAdulto;{}\n", propria)).await;
+    assert_eq!(200, response.status().as_u16());
+
+    // unidades seguem restritas ao admin
+    let response = importa(&client, &app, "/importacoes/unidades", "nome;email;tipo;municipio\n").await;
+    assert_eq!(403, response.status().as_u16());
+}
+
+#[actix_rt::test]
+async fn leitos_referencing_inactive_or_ambiguous_unidades_are_reported() {
+    let app = create_app().await;
+    let desativada = cria_unidade(&app, "UPA Norte", "upanorte@gmail.com").await;
+    cria_unidade(&app, "UPA Sul", "upasul@gmail.com").await;
+    cria_unidade(&app, "UPA Sul II", "UPASUL@gmail.com").await;
+    sqlx::query!("UPDATE unidadeSaude SET desativado_em = now() WHERE id = $1", desativada)
+        .execute(&app.db_pool)
+        .await
+        .expect("Failed to deactivate unidade.");
+
+    let csv = format!(
+        "tipo;unidade_id;unidade_email\n\
+         UtiAdulto;{};\n\
+         UtiAdulto;;upanorte@gmail.com\n\
+         UtiAdulto;;UpaSul@gmail.com\n\
+         UtiAdulto;;\n",
+        desativada
+    );
+
+    let response = importa(&app.api_client, &app, "/importacoes/leitos", csv).await;
+    assert_eq!(400, response.status().as_u16());
+    let body: ErrorBody = response.json().await.unwrap();
+    assert_eq!(
+        vec!["linhas[2].unidade_id", "linhas[3].unidade_email", "linhas[4].unidade_email", "linhas[5].unidade_id"],
+        campos(&body)
+    );
+    assert_eq!("unidade desativada", body.fields[0].message);
+    assert!(body.fields[2].message.starts_with("e-mail de 2 unidades"));
+}
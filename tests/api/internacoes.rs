@@ -0,0 +1,284 @@
+use crate::helpers::{create_app, TestApp};
+use std::collections::HashMap;
+use reqwest::Response;
+use gerenciador_leitos::domain::{MotivoAlta, SituacaoLeito, TipoLeito};
+use gerenciador_leitos::error::ErrorBody;
+use gerenciador_leitos::routes::{Internacao, LeitoId, Paciente, PacienteId, UnidadeId};
+use uuid::Uuid;
+
+// cria uma unidade com um leito na situação informada
+async fn cria_leito(app: &TestApp, situacao: SituacaoLeito) -> Uuid {
+    let response: Response = app.post_unidade(String::from("UBS Aux"), format!("{}@gmail.com", Uuid::new_v4()), String::from("UBS"), String::from("Natal")).await;
+    let hospital_id: UnidadeId = response.json().await.unwrap();
+
+    let response: Response = app.post_leito(TipoLeito::UtiAdulto, situacao, hospital_id.id).await;
+    assert_eq!(200, response.status().as_u16());
+    let leito_id: LeitoId = response.json().await.unwrap();
+    leito_id.id
+}
+
+// cria um paciente sem leito
+async fn cria_paciente(app: &TestApp) -> Uuid {
+    let client = &app.api_client;
+    let body = serde_json::json!({
+        "nome": "Fulano",
+        "sexo": "Masculino",
+        "idade": 54,
+        "email": "fulano@gmail.com",
+        "telefone": "84991223344",
+        "situacao_covid": "Confirmado"
+    });
+
+    let response = client
+        .post(format!("{}/pacientes", &app.address))
+        .json(&body)
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    assert_eq!(200, response.status().as_u16());
+
+    let paciente_id: PacienteId = response.json().await.unwrap();
+    paciente_id.id
+}
+
+async fn situacao_do_leito(app: &TestApp, leito_id: Uuid) -> SituacaoLeito {
+    sqlx::query!(r#"SELECT situacao as "situacao: SituacaoLeito" FROM leito WHERE id = $1"#, leito_id)
+        .fetch_one(&app.db_pool)
+        .await
+        .expect("Failed to fetch saved leito.")
+        .situacao
+}
+
+#[actix_rt::test]
+async fn create_paciente_without_leito_returns_200() {
+    let app = create_app().await;
+    let client = &app.api_client;
+
+    let paciente_id = cria_paciente(&app).await;
+
+    let response = client
+        .get(format!("{}/pacientes/{}", &app.address, paciente_id))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+
+    let paciente: Paciente = response.json().await.unwrap();
+    assert_eq!(paciente.leito_id, None);
+}
+
+#[actix_rt::test]
+async fn internar_paciente_occupies_leito() {
+    let app = create_app().await;
+    let leito_id = cria_leito(&app, SituacaoLeito::Livre).await;
+    let paciente_id = cria_paciente(&app).await;
+
+    let response = app.post_internacao(paciente_id, leito_id).await;
+    assert_eq!(200, response.status().as_u16());
+
+    let internacao: Internacao = response.json().await.unwrap();
+    assert_eq!(internacao.paciente_id, paciente_id);
+    assert_eq!(internacao.leito_id, leito_id);
+    assert_eq!(internacao.alta_em, None);
+
+    assert_eq!(situacao_do_leito(&app, leito_id).await, SituacaoLeito::Ocupado);
+}
+
+#[actix_rt::test]
+async fn internar_paciente_returns_409_for_occupied_leito() {
+    let app = create_app().await;
+    let leito_id = cria_leito(&app, SituacaoLeito::Livre).await;
+    let primeiro = cria_paciente(&app).await;
+    let segundo = cria_paciente(&app).await;
+
+    let response = app.post_internacao(primeiro, leito_id).await;
+    assert_eq!(200, response.status().as_u16());
+
+    let response = app.post_internacao(segundo, leito_id).await;
+    assert_eq!(409, response.status().as_u16());
+
+    let body: ErrorBody = response.json().await.unwrap();
+    assert_eq!(body.fields[0].field, "leito_id");
+}
+
+#[actix_rt::test]
+async fn internar_paciente_returns_409_when_already_admitted() {
+    let app = create_app().await;
+    let primeiro_leito = cria_leito(&app, SituacaoLeito::Livre).await;
+    let segundo_leito = cria_leito(&app, SituacaoLeito::Livre).await;
+    let paciente_id = cria_paciente(&app).await;
+
+    let response = app.post_internacao(paciente_id, primeiro_leito).await;
+    assert_eq!(200, response.status().as_u16());
+
+    let response = app.post_internacao(paciente_id, segundo_leito).await;
+    assert_eq!(409, response.status().as_u16());
+
+    // o segundo leito continua livre
+    assert_eq!(situacao_do_leito(&app, segundo_leito).await, SituacaoLeito::Livre);
+}
+
+#[actix_rt::test]
+async fn internar_paciente_returns_404_for_unknown_paciente() {
+    let app = create_app().await;
+    let leito_id = cria_leito(&app, SituacaoLeito::Livre).await;
+
+    let response = app.post_internacao(Uuid::new_v4(), leito_id).await;
+    assert_eq!(404, response.status().as_u16());
+}
+
+#[actix_rt::test]
+async fn alta_frees_leito_for_higienizacao() {
+    let app = create_app().await;
+    let leito_id = cria_leito(&app, SituacaoLeito::Livre).await;
+    let paciente_id = cria_paciente(&app).await;
+
+    let response = app.post_internacao(paciente_id, leito_id).await;
+    let internacao: Internacao = response.json().await.unwrap();
+
+    let response = app.post_alta(internacao.id, MotivoAlta::AltaMedica).await;
+    assert_eq!(200, response.status().as_u16());
+
+    let encerrada: Internacao = response.json().await.unwrap();
+    assert!(encerrada.alta_em.is_some());
+    assert_eq!(encerrada.motivo_alta, Some(MotivoAlta::AltaMedica));
+
+    assert_eq!(situacao_do_leito(&app, leito_id).await, SituacaoLeito::EmHigienizacao);
+
+    // uma segunda alta para a mesma internação é rejeitada
+    let response = app.post_alta(internacao.id, MotivoAlta::Obito).await;
+    assert_eq!(409, response.status().as_u16());
+}
+
+#[actix_rt::test]
+async fn get_internacoes_paciente_returns_full_history() {
+    let app = create_app().await;
+    let client = &app.api_client;
+    let primeiro_leito = cria_leito(&app, SituacaoLeito::Livre).await;
+    let segundo_leito = cria_leito(&app, SituacaoLeito::Livre).await;
+    let paciente_id = cria_paciente(&app).await;
+
+    // primeira internação, encerrada por transferência
+    let response = app.post_internacao(paciente_id, primeiro_leito).await;
+    let primeira: Internacao = response.json().await.unwrap();
+    app.post_alta(primeira.id, MotivoAlta::Transferencia).await;
+
+    // segunda internação, em aberto
+    let response = app.post_internacao(paciente_id, segundo_leito).await;
+    assert_eq!(200, response.status().as_u16());
+
+    let response = client
+        .get(format!("{}/pacientes/{}/internacoes", &app.address, paciente_id))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+
+    assert_eq!(200, response.status().as_u16());
+
+    let internacoes: Vec<Internacao> = response.json().await.unwrap();
+    assert_eq!(2, internacoes.len());
+    assert_eq!(internacoes[0].leito_id, primeiro_leito);
+    assert_eq!(internacoes[0].motivo_alta, Some(MotivoAlta::Transferencia));
+    assert_eq!(internacoes[1].leito_id, segundo_leito);
+    assert_eq!(internacoes[1].alta_em, None);
+
+    // o leito atual do paciente é o da internação em aberto
+    let response = client
+        .get(format!("{}/pacientes/{}", &app.address, paciente_id))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+
+    let paciente: Paciente = response.json().await.unwrap();
+    assert_eq!(paciente.leito_id, Some(segundo_leito));
+}
+
+#[actix_rt::test]
+async fn concurrent_internacoes_for_same_leito_allow_exactly_one() {
+    let app = create_app().await;
+    let leito_id = cria_leito(&app, SituacaoLeito::Livre).await;
+
+    let mut pacientes = Vec::new();
+    for _ in 0..8 {
+        pacientes.push(cria_paciente(&app).await);
+    }
+
+    // dispara todas as internações em paralelo para o mesmo leito
+    let mut requisicoes = Vec::new();
+    for paciente_id in pacientes {
+        let address = app.address.clone();
+        let client = app.api_client.clone();
+        requisicoes.push(tokio::spawn(async move {
+            let mut map = HashMap::new();
+            map.insert("leito_id", leito_id.to_string());
+            client
+                .post(format!("{}/pacientes/{}/internar", address, paciente_id))
+                .json(&map)
+                .send()
+                .await
+                .expect("Failed to execute request.")
+                .status()
+                .as_u16()
+        }));
+    }
+
+    let mut status = Vec::new();
+    for requisicao in requisicoes {
+        status.push(requisicao.await.unwrap());
+    }
+
+    assert_eq!(1, status.iter().filter(|s| **s == 200).count(), "{:?}", status);
+    assert_eq!(7, status.iter().filter(|s| **s == 409).count(), "{:?}", status);
+
+    let abertas: i64 = sqlx::query!("SELECT COUNT(*) as \"count!\" FROM internacao WHERE leito_id = $1 AND alta_em IS NULL", leito_id)
+        .fetch_one(&app.db_pool)
+        .await
+        .expect("Failed to count internacoes.")
+        .count;
+    assert_eq!(1, abertas);
+}
+
+#[actix_rt::test]
+async fn concurrent_internacoes_for_same_paciente_allow_exactly_one() {
+    let app = create_app().await;
+    let paciente_id = cria_paciente(&app).await;
+
+    let mut leitos = Vec::new();
+    for _ in 0..8 {
+        leitos.push(cria_leito(&app, SituacaoLeito::Livre).await);
+    }
+
+    // dispara as internações do mesmo paciente em leitos diferentes
+    let mut requisicoes = Vec::new();
+    for leito_id in leitos.clone() {
+        let address = app.address.clone();
+        let client = app.api_client.clone();
+        requisicoes.push(tokio::spawn(async move {
+            let mut map = HashMap::new();
+            map.insert("leito_id", leito_id.to_string());
+            client
+                .post(format!("{}/pacientes/{}/internar", address, paciente_id))
+                .json(&map)
+                .send()
+                .await
+                .expect("Failed to execute request.")
+                .status()
+                .as_u16()
+        }));
+    }
+
+    let mut status = Vec::new();
+    for requisicao in requisicoes {
+        status.push(requisicao.await.unwrap());
+    }
+
+    assert_eq!(1, status.iter().filter(|s| **s == 200).count(), "{:?}", status);
+
+    // apenas um dos leitos foi ocupado
+    let mut ocupados = 0;
+    for leito_id in leitos {
+        if situacao_do_leito(&app, leito_id).await == SituacaoLeito::Ocupado {
+            ocupados += 1;
+        }
+    }
+    assert_eq!(1, ocupados);
+}
@@ -1,13 +1,31 @@
-use crate::helpers::create_app;
+use crate::helpers::{create_app, TestApp};
 use std::collections::HashMap;
 use reqwest::Response;
+use gerenciador_leitos::domain::{SituacaoLeito, TipoLeito};
 use gerenciador_leitos::routes::{ UnidadeId, Leito, LeitoId};
+use gerenciador_leitos::error::ErrorBody;
+use uuid::Uuid;
 use sqlx::{Row};
+use gerenciador_leitos::query::Pagina;
+
+// interna um paciente no leito, deixando-o ocupado
+async fn ocupa_leito(app: &TestApp, leito_id: Uuid) {
+    let response: Response = app.post_paciente(
+        String::from("Fulano"),
+        String::from("Masculino"),
+        54,
+        String::from("fulano@gmail.com"),
+        String::from("84991223344"),
+        None,
+        leito_id
+    ).await;
+    assert_eq!(200, response.status().as_u16());
+}
 
 #[actix_rt::test]
 async fn create_leito_returns_200() {
     let app = create_app().await;
-    let client = reqwest::Client::new();
+    let client = &app.api_client;
 
     // cria uma unidade de saude
     let nome = String::from("UBS Aux");
@@ -20,12 +38,12 @@ async fn create_leito_returns_200() {
     let id: String = hospital_id.id.to_string();
 
     let mut map = HashMap::new();
-    map.insert("tipo", "UTI");
-    map.insert("situacao", "Ocupado");
+    map.insert("tipo", "UtiAdulto");
+    map.insert("situacao", "Livre");
     map.insert("unidade_id", &id);
     
     let response = client
-        .post(&format!("{}/leitos", &app.address))
+        .post(format!("{}/leitos", &app.address))
         .header("Content-Type", "application/json")
         .json(&map)
         .send()
@@ -34,20 +52,20 @@ async fn create_leito_returns_200() {
         
     assert_eq!(200, response.status().as_u16());
 
-    let data = sqlx::query!("SELECT tipo, situacao, unidade_id FROM leito",)
+    let data = sqlx::query!("SELECT tipo as \"tipo: TipoLeito\", situacao as \"situacao: SituacaoLeito\", unidade_id FROM leito",)
         .fetch_one(&app.db_pool)
         .await
         .expect("Failed to fetch saved usuario.");
 
-    assert_eq!(data.tipo, "UTI");
-    assert_eq!(data.situacao, "Ocupado");
+    assert_eq!(data.tipo, TipoLeito::UtiAdulto);
+    assert_eq!(data.situacao, SituacaoLeito::Livre);
     assert_eq!(data.unidade_id, hospital_id.id);
 }
 
 #[actix_rt::test]
 async fn get_all_leitos_returns_200() {
     let app = create_app().await;
-    let client = reqwest::Client::new();
+    let client = &app.api_client;
 
     // cria uma unidade de saude
     let nome = String::from("UBS Aux");
@@ -61,16 +79,16 @@ async fn get_all_leitos_returns_200() {
     //let id: String = hospital_id.id.to_string();
     
     //cria primeiro leito
-    let tipo_1 = String::from("UTI");
-    let situacao_1 = String::from("Ocupado");
+    let tipo_1 = TipoLeito::UtiAdulto;
+    let situacao_1 = SituacaoLeito::Livre;
     let unidade_id_1 = hospital_id.id;
     
     let response_1: Response = app.post_leito(tipo_1, situacao_1, unidade_id_1).await;
     assert_eq!(200, response_1.status().as_u16());
 
     //cria segundo leito
-    let tipo_2 = String::from("Enfermaria");
-    let situacao_2 = String::from("Vazio");
+    let tipo_2 = TipoLeito::EnfermariaClinica;
+    let situacao_2 = SituacaoLeito::Livre;
     let unidade_id_2 = hospital_id.id;
     
     let response_2: Response = app.post_leito(tipo_2, situacao_2, unidade_id_2).await;
@@ -78,7 +96,7 @@ async fn get_all_leitos_returns_200() {
 
     // faz o pedido de todos os leitos
     let response = client
-        .get(&format!("{}/leitos", &app.address))
+        .get(format!("{}/leitos", &app.address))
         .header("Content-Type", "application/json")
         .send()
         .await
@@ -87,14 +105,14 @@ async fn get_all_leitos_returns_200() {
     assert_eq!(200, response.status().as_u16());
 
     // verifica se foram retornados 2 usuários
-    let leitos: Vec<LeitoId> = response.json().await.unwrap();
+    let leitos: Vec<LeitoId> = response.json::<Pagina<LeitoId>>().await.unwrap().itens;
     assert_eq!(2, leitos.len());
 }
 
 #[actix_rt::test]
 async fn get_leito_by_id_returns_200() {
     let app = create_app().await;
-    let client = reqwest::Client::new();
+    let client = &app.api_client;
 
     // cria uma unidade de saude
     let nome = String::from("UBS Aux");
@@ -107,8 +125,8 @@ async fn get_leito_by_id_returns_200() {
     let hospital_id: UnidadeId = response.json().await.unwrap();
     
     //cria primeiro leito
-    let tipo_1 = String::from("UTI");
-    let situacao_1 = String::from("Ocupado");
+    let tipo_1 = TipoLeito::UtiAdulto;
+    let situacao_1 = SituacaoLeito::Livre;
     let unidade_id_1 = hospital_id.id;
     
     let response_1: Response = app.post_leito(tipo_1, situacao_1, unidade_id_1).await;
@@ -117,7 +135,7 @@ async fn get_leito_by_id_returns_200() {
     
     // consulta o leito criado
     let response = client
-        .get(&format!("{}/leitos/{}", &app.address, leito_id.id))
+        .get(format!("{}/leitos/{}", &app.address, leito_id.id))
         .header("Content-Type", "application/json")
         .send()
         .await
@@ -128,15 +146,15 @@ async fn get_leito_by_id_returns_200() {
     let saved_leito: Leito = response.json().await.unwrap();
 
     // verifica se o username do usuário retornado é igual ao que foi criado
-    assert_eq!(saved_leito.tipo, String::from("UTI"));
-    assert_eq!(saved_leito.situacao, String::from("Ocupado"));
+    assert_eq!(saved_leito.tipo, TipoLeito::UtiAdulto);
+    assert_eq!(saved_leito.situacao, SituacaoLeito::Livre);
     assert_eq!(saved_leito.unidade_id, hospital_id.id);
 }
 
 #[actix_rt::test]
 async fn update_leito_returns_200() {
     let app = create_app().await;
-    let client = reqwest::Client::new();
+    let client = &app.api_client;
 
      // cria uma unidade de saude
      let nome = String::from("UBS Aux");
@@ -149,8 +167,8 @@ async fn update_leito_returns_200() {
      let hospital_id: UnidadeId = response.json().await.unwrap();
      
      //cria primeiro leito
-     let tipo_1 = String::from("UTI");
-     let situacao_1 = String::from("Ocupado");
+     let tipo_1 = TipoLeito::UtiAdulto;
+     let situacao_1 = SituacaoLeito::Livre;
      let unidade_id_1 = hospital_id.id;
      
      let response_1: Response = app.post_leito(tipo_1, situacao_1, unidade_id_1).await;
@@ -160,20 +178,24 @@ async fn update_leito_returns_200() {
      // instancia um leito
     let leito = Leito {
         id: leito_id.id,
-        tipo: String::from("Enfermaria"),
-        situacao: String::from("Vazio"),
-        unidade_id: unidade_id_1.clone()
+        tipo: TipoLeito::EnfermariaClinica,
+        situacao: SituacaoLeito::EmHigienizacao,
+        ventilador_mecanico: false,
+        isolamento_pressao_negativa: false,
+        hemodialise: false,
+        unidade_id: unidade_id_1,
+        desativado_em: None
     };
 
     // gera um HashMap que será mapeado pro json a ser enviado na requisição de atualização
     let mut map = HashMap::new();
-    map.insert("id", leito.id.to_string());
-    map.insert("tipo", leito.tipo.clone());
-    map.insert("situacao", leito.situacao.clone());
+    map.insert("tipo", leito.tipo.to_string());
+    map.insert("situacao", leito.situacao.to_string());
     map.insert("unidade_id", leito.unidade_id.to_string());
 
     let response = client
-        .put(&format!("{}/leitos", &app.address))
+        .put(format!("{}/leitos/{}", &app.address, leito.id))
+        .header("If-Match", app.etag(&format!("/leitos/{}", leito.id)).await.unwrap())
         .header("Content-Type", "application/json")
         .json(&map)
         .send()
@@ -183,7 +205,7 @@ async fn update_leito_returns_200() {
     assert_eq!(200, response.status().as_u16());
 
     // finalmente, verifica se o usuário foi atualizado
-    let saved = sqlx::query!("SELECT tipo, situacao FROM leito WHERE id = $1", leito_id.id)
+    let saved = sqlx::query!("SELECT tipo as \"tipo: TipoLeito\", situacao as \"situacao: SituacaoLeito\" FROM leito WHERE id = $1", leito_id.id)
                     .fetch_one(&app.db_pool)
                     .await
                     .expect("Failed to fetch saved user.");
@@ -195,7 +217,7 @@ async fn update_leito_returns_200() {
 #[actix_rt::test]
 async fn delete_leito_returns_200() {
     let app = create_app().await;
-    let client = reqwest::Client::new();
+    let client = &app.api_client;
 
     // cria uma unidade de saude
     let nome = String::from("UBS Aux");
@@ -208,8 +230,8 @@ async fn delete_leito_returns_200() {
     let hospital_id: UnidadeId = response.json().await.unwrap();
     
     //cria primeiro leito
-    let tipo_1 = String::from("UTI");
-    let situacao_1 = String::from("Ocupado");
+    let tipo_1 = TipoLeito::UtiAdulto;
+    let situacao_1 = SituacaoLeito::Livre;
     let unidade_id_1 = hospital_id.id;
     
     let response_1: Response = app.post_leito(tipo_1, situacao_1, unidade_id_1).await;
@@ -217,7 +239,8 @@ async fn delete_leito_returns_200() {
     let leito_id: LeitoId = response_1.json().await.unwrap();
 
     let response = client
-        .delete(&format!("{}/leitos/{}", &app.address, leito_id.id))
+        .delete(format!("{}/leitos/{}", &app.address, leito_id.id))
+        .header("If-Match", app.etag(&format!("/leitos/{}", leito_id.id)).await.unwrap())
         .header("Content-Type", "application/json")
         .send()
         .await
@@ -235,4 +258,310 @@ async fn delete_leito_returns_200() {
 
     // verifica se foi retornada alguma coisa, se sim, o leito não foi removido, levantando falha
     assert_eq!(count, 0);
-}
\ No newline at end of file
+}
+
+#[actix_rt::test]
+async fn create_leito_returns_422_for_unknown_unidade() {
+    let app = create_app().await;
+
+    // nenhuma unidade foi cadastrada, então a referência é inválida
+    let response: Response = app.post_leito(TipoLeito::UtiAdulto, SituacaoLeito::Livre, Uuid::new_v4()).await;
+    assert_eq!(422, response.status().as_u16());
+
+    let body: ErrorBody = response.json().await.unwrap();
+    assert_eq!(body.code, "unprocessable_entity");
+    assert_eq!(body.fields[0].field, "unidade_id");
+}
+
+#[actix_rt::test]
+async fn create_transicao_leito_returns_200_for_allowed_transition() {
+    let app = create_app().await;
+    let client = &app.api_client;
+
+    // cria uma unidade de saude
+    let response: Response = app.post_unidade(String::from("UBS Aux"), String::from("ubsaux@gmail.com"), String::from("UBS"), String::from("Natal")).await;
+    let hospital_id: UnidadeId = response.json().await.unwrap();
+
+    // cria um leito livre
+    let response: Response = app.post_leito(TipoLeito::UtiAdulto, SituacaoLeito::Livre, hospital_id.id).await;
+    let leito_id: LeitoId = response.json().await.unwrap();
+
+    let mut map = HashMap::new();
+    map.insert("situacao", "Reservado");
+
+    let response = client
+        .post(format!("{}/leitos/{}/transicoes", &app.address, leito_id.id))
+        .json(&map)
+        .send()
+        .await
+        .expect("Failed to execute request.");
+
+    assert_eq!(200, response.status().as_u16());
+
+    let leito: Leito = response.json().await.unwrap();
+    assert_eq!(leito.situacao, SituacaoLeito::Reservado);
+}
+
+#[actix_rt::test]
+async fn create_transicao_leito_returns_409_for_illegal_transition() {
+    let app = create_app().await;
+    let client = &app.api_client;
+
+    // cria uma unidade de saude
+    let response: Response = app.post_unidade(String::from("UBS Aux"), String::from("ubsaux@gmail.com"), String::from("UBS"), String::from("Natal")).await;
+    let hospital_id: UnidadeId = response.json().await.unwrap();
+
+    // cria um leito e interna um paciente nele
+    let response: Response = app.post_leito(TipoLeito::UtiAdulto, SituacaoLeito::Livre, hospital_id.id).await;
+    let leito_id: LeitoId = response.json().await.unwrap();
+    ocupa_leito(&app, leito_id.id).await;
+
+    // um leito ocupado não pode ser reservado sem passar pela higienização
+    let mut map = HashMap::new();
+    map.insert("situacao", "Reservado");
+
+    let response = client
+        .post(format!("{}/leitos/{}/transicoes", &app.address, leito_id.id))
+        .json(&map)
+        .send()
+        .await
+        .expect("Failed to execute request.");
+
+    assert_eq!(409, response.status().as_u16());
+
+    let body: ErrorBody = response.json().await.unwrap();
+    assert_eq!(body.code, "conflict");
+    assert_eq!(body.fields[0].field, "situacao");
+
+    // a situação do leito permanece inalterada
+    let saved = sqlx::query!(r#"SELECT situacao as "situacao: SituacaoLeito" FROM leito WHERE id = $1"#, leito_id.id)
+        .fetch_one(&app.db_pool)
+        .await
+        .expect("Failed to fetch saved leito.");
+
+    assert_eq!(saved.situacao, SituacaoLeito::Ocupado);
+}
+
+#[actix_rt::test]
+async fn update_leito_returns_409_for_illegal_transition() {
+    let app = create_app().await;
+    let client = &app.api_client;
+
+    // cria uma unidade de saude
+    let response: Response = app.post_unidade(String::from("UBS Aux"), String::from("ubsaux@gmail.com"), String::from("UBS"), String::from("Natal")).await;
+    let hospital_id: UnidadeId = response.json().await.unwrap();
+
+    // cria um leito e interna um paciente nele
+    let response: Response = app.post_leito(TipoLeito::UtiAdulto, SituacaoLeito::Livre, hospital_id.id).await;
+    let leito_id: LeitoId = response.json().await.unwrap();
+    ocupa_leito(&app, leito_id.id).await;
+
+    let mut map = HashMap::new();
+    map.insert("tipo", TipoLeito::UtiAdulto.to_string());
+    map.insert("situacao", SituacaoLeito::Livre.to_string());
+    map.insert("unidade_id", hospital_id.id.to_string());
+
+    let response = client
+        .put(format!("{}/leitos/{}", &app.address, leito_id.id))
+        .header("If-Match", app.etag(&format!("/leitos/{}", leito_id.id)).await.unwrap())
+        .json(&map)
+        .send()
+        .await
+        .expect("Failed to execute request.");
+
+    assert_eq!(409, response.status().as_u16());
+}
+
+#[actix_rt::test]
+async fn create_leito_returns_400_for_unknown_situacao() {
+    let app = create_app().await;
+    let client = &app.api_client;
+
+    // cria uma unidade de saude
+    let response: Response = app.post_unidade(String::from("UBS Aux"), String::from("ubsaux@gmail.com"), String::from("UBS"), String::from("Natal")).await;
+    let hospital_id: UnidadeId = response.json().await.unwrap();
+    let id: String = hospital_id.id.to_string();
+
+    // a situação é sensível a maiúsculas: "ocupado" não é um valor válido
+    let mut map = HashMap::new();
+    map.insert("tipo", "UtiAdulto");
+    map.insert("situacao", "ocupado");
+    map.insert("unidade_id", &id);
+
+    let response = client
+        .post(format!("{}/leitos", &app.address))
+        .json(&map)
+        .send()
+        .await
+        .expect("Failed to execute request.");
+
+    assert_eq!(400, response.status().as_u16());
+}
+
+#[actix_rt::test]
+async fn get_unidades_com_leitos_only_counts_livre() {
+    let app = create_app().await;
+    let client = &app.api_client;
+
+    // cria duas unidades, apenas a primeira com leito livre
+    let response: Response = app.post_unidade(String::from("UBS Livre"), String::from("ubslivre@gmail.com"), String::from("UBS"), String::from("Natal")).await;
+    let unidade_livre: UnidadeId = response.json().await.unwrap();
+    let response: Response = app.post_unidade(String::from("UBS Cheia"), String::from("ubscheia@gmail.com"), String::from("UBS"), String::from("Natal")).await;
+    let unidade_cheia: UnidadeId = response.json().await.unwrap();
+
+    app.post_leito(TipoLeito::UtiAdulto, SituacaoLeito::Livre, unidade_livre.id).await;
+    app.post_leito(TipoLeito::UtiAdulto, SituacaoLeito::Reservado, unidade_cheia.id).await;
+    app.post_leito(TipoLeito::UtiAdulto, SituacaoLeito::EmHigienizacao, unidade_cheia.id).await;
+    app.post_leito(TipoLeito::UtiAdulto, SituacaoLeito::Bloqueado, unidade_cheia.id).await;
+
+    let response = client
+        .get(format!("{}/leitos_disponiveis", &app.address))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+
+    assert_eq!(200, response.status().as_u16());
+
+    let unidades: Vec<UnidadeId> = response.json().await.unwrap();
+    assert_eq!(1, unidades.len());
+    assert_eq!(unidades[0].id, unidade_livre.id);
+}
+
+#[actix_rt::test]
+async fn get_all_leitos_filters_by_tipo_and_recursos() {
+    let app = create_app().await;
+    let client = &app.api_client;
+
+    // cria uma unidade de saude
+    let response: Response = app.post_unidade(String::from("UBS Aux"), String::from("ubsaux@gmail.com"), String::from("UBS"), String::from("Natal")).await;
+    let hospital_id: UnidadeId = response.json().await.unwrap();
+    let unidade_id = hospital_id.id.to_string();
+
+    // cria um leito de UTI com ventilador e um de enfermaria sem recursos
+    let mut map = HashMap::new();
+    map.insert("tipo", serde_json::json!("UtiAdulto"));
+    map.insert("situacao", serde_json::json!("Livre"));
+    map.insert("ventilador_mecanico", serde_json::json!(true));
+    map.insert("unidade_id", serde_json::json!(unidade_id));
+
+    let response = client
+        .post(format!("{}/leitos", &app.address))
+        .json(&map)
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    assert_eq!(200, response.status().as_u16());
+    let uti_id: LeitoId = response.json().await.unwrap();
+
+    app.post_leito(TipoLeito::EnfermariaClinica, SituacaoLeito::Livre, hospital_id.id).await;
+
+    // filtra pelo tipo
+    let response = client
+        .get(format!("{}/leitos?tipo=UtiAdulto", &app.address))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    assert_eq!(200, response.status().as_u16());
+    let leitos: Vec<Leito> = response.json::<Pagina<Leito>>().await.unwrap().itens;
+    assert_eq!(1, leitos.len());
+    assert_eq!(leitos[0].id, uti_id.id);
+    assert!(leitos[0].ventilador_mecanico);
+
+    // filtra pelo recurso
+    let response = client
+        .get(format!("{}/leitos?ventilador_mecanico=false", &app.address))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    let leitos: Vec<Leito> = response.json::<Pagina<Leito>>().await.unwrap().itens;
+    assert_eq!(1, leitos.len());
+    assert_eq!(leitos[0].tipo, TipoLeito::EnfermariaClinica);
+}
+
+#[actix_rt::test]
+async fn get_all_leitos_returns_400_for_unknown_tipo() {
+    let app = create_app().await;
+    let client = &app.api_client;
+
+    let response = client
+        .get(format!("{}/leitos?tipo=UTI", &app.address))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+
+    assert_eq!(400, response.status().as_u16());
+
+    let body: ErrorBody = response.json().await.unwrap();
+    assert_eq!(body.code, "validation_error");
+}
+
+#[actix_rt::test]
+async fn create_leito_returns_400_for_ocupado() {
+    let app = create_app().await;
+
+    // cria uma unidade de saude
+    let response: Response = app.post_unidade(String::from("UBS Aux"), String::from("ubsaux@gmail.com"), String::from("UBS"), String::from("Natal")).await;
+    let hospital_id: UnidadeId = response.json().await.unwrap();
+
+    // um leito só fica ocupado pela internação de um paciente
+    let response: Response = app.post_leito(TipoLeito::UtiAdulto, SituacaoLeito::Ocupado, hospital_id.id).await;
+    assert_eq!(400, response.status().as_u16());
+
+    let body: ErrorBody = response.json().await.unwrap();
+    assert_eq!(body.fields[0].field, "situacao");
+}
+
+#[actix_rt::test]
+async fn create_transicao_leito_returns_409_for_manual_occupation() {
+    let app = create_app().await;
+    let client = &app.api_client;
+
+    // cria uma unidade de saude
+    let response: Response = app.post_unidade(String::from("UBS Aux"), String::from("ubsaux@gmail.com"), String::from("UBS"), String::from("Natal")).await;
+    let hospital_id: UnidadeId = response.json().await.unwrap();
+
+    let response: Response = app.post_leito(TipoLeito::UtiAdulto, SituacaoLeito::Livre, hospital_id.id).await;
+    let leito_id: LeitoId = response.json().await.unwrap();
+
+    let mut map = HashMap::new();
+    map.insert("situacao", "Ocupado");
+
+    let response = client
+        .post(format!("{}/leitos/{}/transicoes", &app.address, leito_id.id))
+        .json(&map)
+        .send()
+        .await
+        .expect("Failed to execute request.");
+
+    assert_eq!(409, response.status().as_u16());
+}
+
+#[actix_rt::test]
+async fn create_transicao_leito_returns_409_while_internacao_is_open() {
+    let app = create_app().await;
+    let client = &app.api_client;
+
+    // cria uma unidade de saude
+    let response: Response = app.post_unidade(String::from("UBS Aux"), String::from("ubsaux@gmail.com"), String::from("UBS"), String::from("Natal")).await;
+    let hospital_id: UnidadeId = response.json().await.unwrap();
+
+    let response: Response = app.post_leito(TipoLeito::UtiAdulto, SituacaoLeito::Livre, hospital_id.id).await;
+    let leito_id: LeitoId = response.json().await.unwrap();
+    ocupa_leito(&app, leito_id.id).await;
+
+    // a higienização é permitida pela máquina de estados, mas o leito só é liberado pela alta
+    let mut map = HashMap::new();
+    map.insert("situacao", "EmHigienizacao");
+
+    let response = client
+        .post(format!("{}/leitos/{}/transicoes", &app.address, leito_id.id))
+        .json(&map)
+        .send()
+        .await
+        .expect("Failed to execute request.");
+
+    assert_eq!(409, response.status().as_u16());
+
+    let body: ErrorBody = response.json().await.unwrap();
+    assert_eq!(body.fields[0].field, "situacao");
+}
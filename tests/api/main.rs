@@ -2,4 +2,24 @@ mod helpers;
 mod ping;
 mod hospitais;
 mod leitos;
-mod pacientes;
\ No newline at end of file
+mod pacientes;
+mod identificadores;
+mod internacoes;
+mod transferencias;
+mod fila;
+mod relatorios;
+mod usuarios;
+mod autorizacao;
+mod auditoria;
+mod dados_pessoais;
+mod paginacao;
+mod busca;
+mod rotas_aninhadas;
+mod validacao;
+mod covid;
+mod condicoes;
+mod remocao;
+mod atualizacao_parcial;
+mod concorrencia;
+mod importacoes;
+mod exportacoes;
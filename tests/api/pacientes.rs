@@ -1,13 +1,15 @@
 use crate::helpers::create_app;
-use std::collections::HashMap;
 use reqwest::Response;
+use gerenciador_leitos::domain::{SituacaoCovid, SituacaoLeito, TipoLeito};
 use gerenciador_leitos::routes::{ UnidadeId, LeitoId, Paciente, PacienteId};
 use sqlx::{Row};
+use gerenciador_leitos::query::Pagina;
+use gerenciador_leitos::error::ErrorBody;
 
 #[actix_rt::test]
 async fn create_paciente_returns_200() {
     let app = create_app().await;
-    let client = reqwest::Client::new();
+    let client = &app.api_client;
 
     // cria uma unidade de saude
     let nome = String::from("UBS Aux");
@@ -19,8 +21,8 @@ async fn create_paciente_returns_200() {
     let hospital_id: UnidadeId = response.json().await.unwrap();
 
     //cria um leito
-    let tipo_1 = String::from("UTI");
-    let situacao_1 = String::from("Ocupado");
+    let tipo_1 = TipoLeito::UtiAdulto;
+    let situacao_1 = SituacaoLeito::Livre;
     let unidade_id_1 = hospital_id.id;
     
     let response_1: Response = app.post_leito(tipo_1, situacao_1, unidade_id_1).await;
@@ -28,19 +30,20 @@ async fn create_paciente_returns_200() {
     let leito_id: LeitoId = response_1.json().await.unwrap();
     let id: String = leito_id.id.to_string();
 
-    let mut map = HashMap::new();
-    map.insert("nome", "Fulano");
-    map.insert("sexo", "Masculino");
-    map.insert("idade", "29");
-    map.insert("email", "fulano@gmail.com");
-    map.insert("telefone", "84998874321");
-    map.insert("covid_19", "Sim");
-    map.insert("leito_id", &id);
+    let body = serde_json::json!({
+        "nome": "Fulano",
+        "sexo": "Masculino",
+        "idade": 29,
+        "email": "fulano@gmail.com",
+        "telefone": "84998874321",
+        "situacao_covid": "Confirmado",
+        "leito_id": id
+    });
     
     let response = client
-        .post(&format!("{}/pacientes", &app.address))
+        .post(format!("{}/pacientes", &app.address))
         .header("Content-Type", "application/json")
-        .json(&map)
+        .json(&body)
         .send()
         .await
         .expect("Failed to execute request.");
@@ -48,25 +51,39 @@ async fn create_paciente_returns_200() {
     assert_eq!(200, response.status().as_u16());
 
 
-    // nome, sexo, idade, email, telefone, covid_19, leito_id
-    let data = sqlx::query!("SELECT nome, sexo, idade, email, telefone, covid_19, leito_id  FROM paciente",)
+    // nome, sexo, idade, email, telefone, leito_id
+    let data = sqlx::query!("SELECT id, nome, sexo, idade, email, telefone FROM paciente",)
         .fetch_one(&app.db_pool)
         .await
         .expect("Failed to fetch saved usuario.");
 
     assert_eq!(data.nome, "Fulano");
     assert_eq!(data.sexo, "Masculino");
-    assert_eq!(data.idade, "29");
+    assert_eq!(data.idade, Some(29));
     assert_eq!(data.email, "fulano@gmail.com");
     assert_eq!(data.telefone, "84998874321");
-    assert_eq!(data.covid_19, "Sim");
-    assert_eq!(data.leito_id, leito_id.id);
+
+    // o leito informado no cadastro gera uma internação em aberto
+    let internacao = sqlx::query!("SELECT leito_id FROM internacao WHERE paciente_id = $1 AND alta_em IS NULL", data.id)
+        .fetch_one(&app.db_pool)
+        .await
+        .expect("Failed to fetch saved internacao.");
+
+    assert_eq!(internacao.leito_id, leito_id.id);
+
+    // a situação informada no cadastro vira o primeiro registro de covid
+    let registro = sqlx::query!(r#"SELECT situacao as "situacao: SituacaoCovid" FROM registro_covid WHERE paciente_id = $1"#, data.id)
+        .fetch_one(&app.db_pool)
+        .await
+        .expect("Failed to fetch saved registro_covid.");
+
+    assert_eq!(registro.situacao, SituacaoCovid::Confirmado);
 }
 
 #[actix_rt::test]
 async fn get_all_pacientes_returns_200() {
     let app = create_app().await;
-    let client = reqwest::Client::new();
+    let client = &app.api_client;
 
     // cria uma unidade de saude
     let nome = String::from("UBS Aux");
@@ -79,8 +96,8 @@ async fn get_all_pacientes_returns_200() {
     let hospital_id: UnidadeId = response.json().await.unwrap();
     
     //cria primeiro leito
-    let tipo_1 = String::from("UTI");
-    let situacao_1 = String::from("Ocupado");
+    let tipo_1 = TipoLeito::UtiAdulto;
+    let situacao_1 = SituacaoLeito::Livre;
     let unidade_id_1 = hospital_id.id;
     
     let response_1: Response = app.post_leito(tipo_1, situacao_1, unidade_id_1).await;
@@ -88,8 +105,8 @@ async fn get_all_pacientes_returns_200() {
     let leito_id_1: LeitoId = response_1.json().await.unwrap();
 
     //cria segundo leito
-    let tipo_2= String::from("UTI");
-    let situacao_2 = String::from("Ocupado");
+    let tipo_2 = TipoLeito::UtiAdulto;
+    let situacao_2 = SituacaoLeito::Livre;
     let unidade_id_2 = hospital_id.id;
     
     let response_2: Response = app.post_leito(tipo_2, situacao_2, unidade_id_2).await;
@@ -99,30 +116,30 @@ async fn get_all_pacientes_returns_200() {
     //cria primeiro paciente
     let nome = String::from("Fulano");
     let sexo = String::from("Masculino");
-    let idade = String::from("54");
+    let idade = 54;
     let email = String::from("fulano@gmail.com");
-    let telefone = String::from("991223344");
-    let covid_19 = String::from("Sim");
+    let telefone = String::from("84991223344");
+    let situacao_covid = Some(SituacaoCovid::Confirmado);
     let leito_id = leito_id_1.id;
 
-    let response_1: Response = app.post_paciente(nome, sexo, idade, email, telefone, covid_19, leito_id).await;
+    let response_1: Response = app.post_paciente(nome, sexo, idade, email, telefone, situacao_covid, leito_id).await;
     assert_eq!(200, response_1.status().as_u16());
 
     //cria segundo paciente
     let nome = String::from("Sicrano");
     let sexo = String::from("Masculino");
-    let idade = String::from("45");
+    let idade = 45;
     let email = String::from("sicrano@gmail.com");
-    let telefone = String::from("991225566");
-    let covid_19 = String::from("Não");
+    let telefone = String::from("84991225566");
+    let situacao_covid = None;
     let leito_id = leito_id_2.id;
 
-    let response_2: Response = app.post_paciente(nome, sexo, idade, email, telefone, covid_19, leito_id).await;
+    let response_2: Response = app.post_paciente(nome, sexo, idade, email, telefone, situacao_covid, leito_id).await;
     assert_eq!(200, response_2.status().as_u16());
 
     // faz o pedido de todos os leitos
     let response = client
-        .get(&format!("{}/pacientes", &app.address))
+        .get(format!("{}/pacientes", &app.address))
         .header("Content-Type", "application/json")
         .send()
         .await
@@ -131,14 +148,14 @@ async fn get_all_pacientes_returns_200() {
     assert_eq!(200, response.status().as_u16());
 
     // verifica se foram retornados 2 usuários
-    let pacientes: Vec<PacienteId> = response.json().await.unwrap();
+    let pacientes: Vec<PacienteId> = response.json::<Pagina<PacienteId>>().await.unwrap().itens;
     assert_eq!(2, pacientes.len());
 }
 
 #[actix_rt::test]
 async fn get_paciente_by_id_returns_200() {
     let app = create_app().await;
-    let client = reqwest::Client::new();
+    let client = &app.api_client;
 
     // cria uma unidade de saude
     let nome = String::from("UBS Aux");
@@ -151,8 +168,8 @@ async fn get_paciente_by_id_returns_200() {
     let hospital_id: UnidadeId = response.json().await.unwrap();
     
     //cria um leito
-    let tipo_1 = String::from("UTI");
-    let situacao_1 = String::from("Ocupado");
+    let tipo_1 = TipoLeito::UtiAdulto;
+    let situacao_1 = SituacaoLeito::Livre;
     let unidade_id_1 = hospital_id.id;
     
     let response_1: Response = app.post_leito(tipo_1, situacao_1, unidade_id_1).await;
@@ -162,19 +179,19 @@ async fn get_paciente_by_id_returns_200() {
      //cria paciente
      let nome = String::from("Fulano");
      let sexo = String::from("Masculino");
-     let idade = String::from("54");
+     let idade = 54;
      let email = String::from("fulano@gmail.com");
-     let telefone = String::from("991223344");
-     let covid_19 = String::from("Sim");
+     let telefone = String::from("84991223344");
+     let situacao_covid = Some(SituacaoCovid::Confirmado);
      let leito_id = leito_id.id;
  
-     let response_1: Response = app.post_paciente(nome, sexo, idade, email, telefone, covid_19, leito_id.clone()).await;
+     let response_1: Response = app.post_paciente(nome, sexo, idade, email, telefone, situacao_covid, leito_id).await;
      assert_eq!(200, response_1.status().as_u16());
      let paciente_id: PacienteId = response_1.json().await.unwrap();
     
     // consulta o leito criado
     let response = client
-        .get(&format!("{}/pacientes/{}", &app.address, paciente_id.id))
+        .get(format!("{}/pacientes/{}", &app.address, paciente_id.id))
         .header("Content-Type", "application/json")
         .send()
         .await
@@ -186,18 +203,18 @@ async fn get_paciente_by_id_returns_200() {
 
     assert_eq!(saved.nome, String::from("Fulano"));
     assert_eq!(saved.sexo, String::from("Masculino"));
-    assert_eq!(saved.idade, String::from("54"));
+    assert_eq!(saved.idade, Some(54));
     assert_eq!(saved.email, String::from("fulano@gmail.com"));
-    assert_eq!(saved.telefone, String::from("991223344"));
-    assert_eq!(saved.covid_19,  String::from("Sim"));
-    assert_eq!(saved.leito_id, leito_id);
+    assert_eq!(saved.telefone, String::from("84991223344"));
+    assert_eq!(saved.situacao_covid, Some(SituacaoCovid::Confirmado));
+    assert_eq!(saved.leito_id, Some(leito_id));
 
 }
 
 #[actix_rt::test]
 async fn update_paciente_returns_200() {
     let app = create_app().await;
-    let client = reqwest::Client::new();
+    let client = &app.api_client;
 
      // cria uma unidade de saude
      let nome = String::from("UBS Aux");
@@ -210,8 +227,8 @@ async fn update_paciente_returns_200() {
      let hospital_id: UnidadeId = response.json().await.unwrap();
      
      //cria primeiro leito
-     let tipo_1 = String::from("UTI");
-     let situacao_1 = String::from("Ocupado");
+     let tipo_1 = TipoLeito::UtiAdulto;
+     let situacao_1 = SituacaoLeito::Livre;
      let unidade_id_1 = hospital_id.id;
      
      let response_1: Response = app.post_leito(tipo_1, situacao_1, unidade_id_1).await;
@@ -221,13 +238,13 @@ async fn update_paciente_returns_200() {
     //cria paciente
     let nome = String::from("Fulano");
     let sexo = String::from("Masculino");
-    let idade = String::from("54");
+    let idade = 54;
     let email = String::from("fulano@gmail.com");
-    let telefone = String::from("991223344");
-    let covid_19 = String::from("Sim");
+    let telefone = String::from("84991223344");
+    let situacao_covid = Some(SituacaoCovid::Confirmado);
     let leito_id = leito_id.id;
 
-    let response_1: Response = app.post_paciente(nome, sexo, idade, email, telefone, covid_19, leito_id.clone()).await;
+    let response_1: Response = app.post_paciente(nome, sexo, idade, email, telefone, situacao_covid, leito_id).await;
     assert_eq!(200, response_1.status().as_u16());
     let paciente_id: PacienteId = response_1.json().await.unwrap();
 
@@ -236,28 +253,18 @@ async fn update_paciente_returns_200() {
         id: paciente_id.id,
         nome: String::from("Sicrano"),
         sexo: String::from("Masculino"),
-        idade: String::from("54"),
+        idade: Some(54),
         email: String::from("sicrano@gmail.com"),
-        telefone:String::from("991223344"),
-        covid_19: String::from("Sim"),
-        leito_id: leito_id.clone()
+        telefone:String::from("84991223344"),
+        situacao_covid: Some(SituacaoCovid::Confirmado),
+        leito_id: Some(leito_id)
     };
 
-    // gera um HashMap que será mapeado pro json a ser enviado na requisição de atualização
-    let mut map = HashMap::new();
-    map.insert("id", paciente.id.to_string());
-    map.insert("nome", paciente.nome.clone());
-    map.insert("sexo", paciente.sexo.clone());
-    map.insert("idade", paciente.idade.to_string());
-    map.insert("email", paciente.email.clone());
-    map.insert("telefone", paciente.telefone.clone());
-    map.insert("covid_19", paciente.covid_19.clone());
-    map.insert("leito_id", paciente.leito_id.to_string());
-
     let response = client
-        .put(&format!("{}/pacientes", &app.address))
+        .put(format!("{}/pacientes/{}", &app.address, paciente.id))
+        .header("If-Match", app.etag(&format!("/pacientes/{}", paciente.id)).await.unwrap())
         .header("Content-Type", "application/json")
-        .json(&map)
+        .json(&paciente)
         .send()
         .await
         .expect("Failed to execute request.");
@@ -277,50 +284,33 @@ async fn update_paciente_returns_200() {
 #[actix_rt::test]
 async fn delete_paciente_returns_200() {
     let app = create_app().await;
-    let client = reqwest::Client::new();
+    let client = &app.api_client;
 
-    // cria uma unidade de saude
-    let nome = String::from("UBS Aux");
-    let email = String::from("ubsaux@gmail.com");
-    let tipo = String::from("UBS");
-    let municipio = String::from("Natal");
-    
-    let response: Response = app.post_unidade(nome, email, tipo, municipio).await;
+    // cria paciente sem leito nem situação de covid, sem histórico
+    let response = client
+        .post(format!("{}/pacientes", &app.address))
+        .json(&serde_json::json!({
+            "nome": "Fulano",
+            "sexo": "Masculino",
+            "idade": 54,
+            "email": "fulano@gmail.com",
+            "telefone": "84991223344"
+        }))
+        .send()
+        .await
+        .expect("Failed to execute request.");
     assert_eq!(200, response.status().as_u16());
-    let hospital_id: UnidadeId = response.json().await.unwrap();
-    
-    //cria primeiro leito
-    let tipo_1 = String::from("UTI");
-    let situacao_1 = String::from("Ocupado");
-    let unidade_id_1 = hospital_id.id;
-    
-    let response_1: Response = app.post_leito(tipo_1, situacao_1, unidade_id_1).await;
-    assert_eq!(200, response_1.status().as_u16());
-    let leito_id: LeitoId = response_1.json().await.unwrap();
-
-     //cria paciente
-     let nome = String::from("Fulano");
-     let sexo = String::from("Masculino");
-     let idade = String::from("54");
-     let email = String::from("fulano@gmail.com");
-     let telefone = String::from("991223344");
-     let covid_19 = String::from("Sim");
-     let leito_id = leito_id.id;
- 
-     let response_1: Response = app.post_paciente(nome, sexo, idade, email, telefone, covid_19, leito_id).await;
-     assert_eq!(200, response_1.status().as_u16());
-     let paciente_id: PacienteId = response_1.json().await.unwrap();
+    let paciente_id: PacienteId = response.json().await.unwrap();
 
     let response = client
-        .delete(&format!("{}/pacientes/{}", &app.address, paciente_id.id))
-        .header("Content-Type", "application/json")
+        .delete(format!("{}/pacientes/{}", &app.address, paciente_id.id))
+        .header("If-Match", app.etag(&format!("/pacientes/{}", paciente_id.id)).await.unwrap())
         .send()
         .await
         .expect("Failed to execute request.");
 
     assert_eq!(200, response.status().as_u16());
 
-    // finalmente, verifica se o leito foi removido
     let count: i64 = sqlx::query("SELECT COUNT(id) as count FROM paciente")
         .fetch_one(&app.db_pool)
         .await
@@ -328,6 +318,56 @@ async fn delete_paciente_returns_200() {
         .try_get("count")
         .unwrap();
 
-    // verifica se foi retornada alguma coisa, se sim, o leito não foi removido, levantando falha
     assert_eq!(count, 0);
-}
\ No newline at end of file
+}
+
+#[actix_rt::test]
+async fn delete_paciente_with_history_returns_409() {
+    let app = create_app().await;
+    let client = &app.api_client;
+
+    let response: Response = app.post_unidade(
+        String::from("UBS Aux"),
+        String::from("ubsaux@gmail.com"),
+        String::from("UBS"),
+        String::from("Natal")
+    ).await;
+    let hospital_id: UnidadeId = response.json().await.unwrap();
+    let response = app.post_leito(TipoLeito::UtiAdulto, SituacaoLeito::Livre, hospital_id.id).await;
+    let leito_id: LeitoId = response.json().await.unwrap();
+
+    let response: Response = app.post_paciente(
+        String::from("Fulano"),
+        String::from("Masculino"),
+        54,
+        String::from("fulano@gmail.com"),
+        String::from("84991223344"),
+        Some(SituacaoCovid::Confirmado),
+        leito_id.id
+    ).await;
+    assert_eq!(200, response.status().as_u16());
+    let paciente_id: PacienteId = response.json().await.unwrap();
+
+    let response = client
+        .delete(format!("{}/pacientes/{}", &app.address, paciente_id.id))
+        .header("If-Match", app.etag(&format!("/pacientes/{}", paciente_id.id)).await.unwrap())
+        .send()
+        .await
+        .expect("Failed to execute request.");
+
+    assert_eq!(409, response.status().as_u16());
+    let body: ErrorBody = response.json().await.unwrap();
+    let campos: Vec<&str> = body.fields.iter().map(|f| f.field.as_str()).collect();
+    assert_eq!(vec!["internacoes", "registros_covid"], campos);
+
+    // o paciente e a internação continuam lá
+    let paciente: Paciente = client
+        .get(format!("{}/pacientes/{}", &app.address, paciente_id.id))
+        .send()
+        .await
+        .expect("Failed to execute request.")
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(Some(leito_id.id), paciente.leito_id);
+}
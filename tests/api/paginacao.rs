@@ -0,0 +1,182 @@
+use crate::helpers::{create_app, TestApp};
+use reqwest::Response;
+use gerenciador_leitos::domain::{SituacaoCovid, SituacaoLeito, TipoLeito};
+use gerenciador_leitos::error::ErrorBody;
+use gerenciador_leitos::query::Pagina;
+use gerenciador_leitos::routes::{Leito, LeitoId, Paciente, UnidadeId, UnidadeSaude};
+use std::collections::HashSet;
+use uuid::Uuid;
+
+async fn cria_unidade(app: &TestApp, nome: &str, municipio: &str) -> Uuid {
+    let response: Response = app.post_unidade(
+        String::from(nome),
+        format!("{}@gmail.com", nome.to_lowercase().replace(' ', "")),
+        String::from("UPA"),
+        String::from(municipio)
+    ).await;
+    assert_eq!(200, response.status().as_u16());
+    let unidade: UnidadeId = response.json().await.unwrap();
+    unidade.id
+}
+
+async fn cria_leito(app: &TestApp, tipo: TipoLeito, situacao: SituacaoLeito, unidade_id: Uuid) -> Uuid {
+    let response = app.post_leito(tipo, situacao, unidade_id).await;
+    assert_eq!(200, response.status().as_u16());
+    let leito: LeitoId = response.json().await.unwrap();
+    leito.id
+}
+
+async fn get(app: &TestApp, caminho: &str) -> Response {
+    app.api_client
+        .get(format!("{}{}", &app.address, caminho))
+        .send()
+        .await
+        .expect("Failed to execute request.")
+}
+
+// percorre todas as páginas da listagem seguindo o next_cursor
+async fn todas_as_paginas(app: &TestApp, caminho: &str) -> Vec<Pagina<Leito>> {
+    let separador = if caminho.contains('?') { '&' } else { '?' };
+    let mut paginas: Vec<Pagina<Leito>> = Vec::new();
+    let mut cursor: Option<String> = None;
+    loop {
+        let url = match &cursor {
+            Some(cursor) => format!("{}{}cursor={}", caminho, separador, cursor),
+            None => caminho.to_string(),
+        };
+        let response = get(app, &url).await;
+        assert_eq!(200, response.status().as_u16());
+        let pagina: Pagina<Leito> = response.json().await.unwrap();
+        cursor = pagina.next_cursor.clone();
+        paginas.push(pagina);
+        if cursor.is_none() {
+            return paginas;
+        }
+    }
+}
+
+#[actix_rt::test]
+async fn get_leitos_pages_through_every_leito_once() {
+    let app = create_app().await;
+    let unidade_id = cria_unidade(&app, "UPA Norte", "Natal").await;
+    let mut criados = HashSet::new();
+    for _ in 0..5 {
+        criados.insert(cria_leito(&app, TipoLeito::UtiAdulto, SituacaoLeito::Livre, unidade_id).await);
+    }
+
+    let paginas = todas_as_paginas(&app, "/leitos?limit=2").await;
+
+    let tamanhos: Vec<usize> = paginas.iter().map(|p| p.itens.len()).collect();
+    assert_eq!(vec![2, 2, 1], tamanhos);
+    assert!(paginas.iter().all(|p| p.total == 5));
+    let vistos: HashSet<Uuid> = paginas.iter().flat_map(|p| p.itens.iter().map(|l| l.id)).collect();
+    assert_eq!(criados, vistos);
+}
+
+#[actix_rt::test]
+async fn get_leitos_sorted_by_tipo_keeps_ties_stable_across_pages() {
+    let app = create_app().await;
+    let unidade_id = cria_unidade(&app, "UPA Norte", "Natal").await;
+    for tipo in [TipoLeito::EnfermariaClinica, TipoLeito::UtiAdulto, TipoLeito::EnfermariaClinica, TipoLeito::UtiAdulto].iter() {
+        cria_leito(&app, *tipo, SituacaoLeito::Livre, unidade_id).await;
+    }
+
+    let paginas = todas_as_paginas(&app, "/leitos?limit=1&ordenar=-tipo").await;
+
+    let leitos: Vec<Leito> = paginas.into_iter().flat_map(|p| p.itens).collect();
+    assert_eq!(4, leitos.len());
+    assert_eq!(4, leitos.iter().map(|l| l.id).collect::<HashSet<Uuid>>().len());
+    let tipos: Vec<TipoLeito> = leitos.iter().map(|l| l.tipo).collect();
+    // a ordem é a do enum no banco, não a alfabética
+    assert_eq!(
+        vec![TipoLeito::EnfermariaClinica, TipoLeito::EnfermariaClinica, TipoLeito::UtiAdulto, TipoLeito::UtiAdulto],
+        tipos
+    );
+}
+
+#[actix_rt::test]
+async fn get_leitos_filters_by_situacao_and_unidade() {
+    let app = create_app().await;
+    let norte = cria_unidade(&app, "UPA Norte", "Natal").await;
+    let sul = cria_unidade(&app, "UPA Sul", "Natal").await;
+    let bloqueado = cria_leito(&app, TipoLeito::UtiAdulto, SituacaoLeito::Bloqueado, norte).await;
+    cria_leito(&app, TipoLeito::UtiAdulto, SituacaoLeito::Livre, norte).await;
+    cria_leito(&app, TipoLeito::UtiAdulto, SituacaoLeito::Bloqueado, sul).await;
+
+    let response = get(&app, &format!("/leitos?situacao=Bloqueado&unidade_id={}", norte)).await;
+    assert_eq!(200, response.status().as_u16());
+    let pagina: Pagina<Leito> = response.json().await.unwrap();
+    assert_eq!(1, pagina.total);
+    assert_eq!(bloqueado, pagina.itens[0].id);
+    assert!(pagina.next_cursor.is_none());
+}
+
+#[actix_rt::test]
+async fn get_unidades_sorts_by_nome_and_filters_by_municipio() {
+    let app = create_app().await;
+    cria_unidade(&app, "UPA Bela Vista", "Natal").await;
+    cria_unidade(&app, "UPA Alecrim", "Natal").await;
+    cria_unidade(&app, "UPA Centro", "Natal").await;
+    cria_unidade(&app, "UPA Abolição", "Mossoró").await;
+
+    let response = get(&app, "/unidades?municipio=natal&ordenar=-nome").await;
+    assert_eq!(200, response.status().as_u16());
+    let pagina: Pagina<UnidadeSaude> = response.json().await.unwrap();
+    let nomes: Vec<&str> = pagina.itens.iter().map(|u| u.nome.as_str()).collect();
+    assert_eq!(vec!["UPA Centro", "UPA Bela Vista", "UPA Alecrim"], nomes);
+    assert_eq!(3, pagina.total);
+}
+
+#[actix_rt::test]
+async fn get_pacientes_filters_by_situacao_covid() {
+    let app = create_app().await;
+    let unidade_id = cria_unidade(&app, "UPA Norte", "Natal").await;
+    for situacao_covid in [Some(SituacaoCovid::Confirmado), None, Some(SituacaoCovid::Confirmado)].iter() {
+        let leito_id = cria_leito(&app, TipoLeito::UtiAdulto, SituacaoLeito::Livre, unidade_id).await;
+        let response = app.post_paciente(
+            String::from("Fulano"),
+            String::from("Masculino"),
+            54,
+            String::from("fulano@gmail.com"),
+            String::from("84991223344"),
+            *situacao_covid,
+            leito_id
+        ).await;
+        assert_eq!(200, response.status().as_u16());
+    }
+
+    let response = get(&app, "/pacientes?situacao_covid=Confirmado").await;
+    assert_eq!(200, response.status().as_u16());
+    let pagina: Pagina<Paciente> = response.json().await.unwrap();
+    assert_eq!(2, pagina.total);
+    assert!(pagina.itens.iter().all(|p| p.situacao_covid == Some(SituacaoCovid::Confirmado) && p.leito_id.is_some()));
+}
+
+#[actix_rt::test]
+async fn invalid_pagination_params_return_400() {
+    let app = create_app().await;
+    let unidade_id = cria_unidade(&app, "UPA Norte", "Natal").await;
+    for _ in 0..2 {
+        cria_leito(&app, TipoLeito::UtiAdulto, SituacaoLeito::Livre, unidade_id).await;
+    }
+
+    let response = get(&app, "/leitos?limit=1").await;
+    let pagina: Pagina<Leito> = response.json().await.unwrap();
+    let cursor = pagina.next_cursor.unwrap();
+
+    let casos = vec![
+        ("/leitos?limit=0".to_string(), "limit"),
+        ("/leitos?limit=1000".to_string(), "limit"),
+        ("/leitos?ordenar=unidade_id".to_string(), "ordenar"),
+        ("/leitos?cursor=naoehumcursor".to_string(), "cursor"),
+        // o cursor foi gerado para a ordenação padrão
+        (format!("/leitos?ordenar=tipo&cursor={}", cursor), "cursor"),
+    ];
+
+    for (caminho, campo) in casos {
+        let response = get(&app, &caminho).await;
+        assert_eq!(400, response.status().as_u16(), "{}", caminho);
+        let body: ErrorBody = response.json().await.unwrap();
+        assert_eq!(campo, body.fields[0].field);
+    }
+}
@@ -6,7 +6,7 @@ async fn ping_returns_200_and_no_body() {
     let client = reqwest::Client::new();
 
     let response = client
-        .get(&format!("{}/ping", &app.address))
+        .get(format!("{}/ping", &app.address))
         .send()
         .await
         .expect("Failed to execute request.");
@@ -0,0 +1,161 @@
+use crate::helpers::{create_app, TestApp};
+use reqwest::Response;
+use gerenciador_leitos::domain::{MotivoAlta, SituacaoCovid, SituacaoLeito, TipoLeito};
+use gerenciador_leitos::routes::{LeitoId, PontoHistorico, RelatorioOcupacao, UnidadeId};
+use chrono::{Duration, Utc};
+use uuid::Uuid;
+
+async fn cria_unidade(app: &TestApp, municipio: &str) -> Uuid {
+    let response: Response = app.post_unidade(String::from("UBS Aux"), format!("{}@gmail.com", Uuid::new_v4()), String::from("UBS"), String::from(municipio)).await;
+    let hospital_id: UnidadeId = response.json().await.unwrap();
+    hospital_id.id
+}
+
+async fn cria_leito(app: &TestApp, tipo: TipoLeito, situacao: SituacaoLeito, unidade_id: Uuid) -> Uuid {
+    let response: Response = app.post_leito(tipo, situacao, unidade_id).await;
+    assert_eq!(200, response.status().as_u16());
+    let leito_id: LeitoId = response.json().await.unwrap();
+    leito_id.id
+}
+
+async fn get_relatorio(app: &TestApp, query: &str) -> RelatorioOcupacao {
+    let response = app.api_client
+        .get(format!("{}/relatorios/ocupacao{}", &app.address, query))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    assert_eq!(200, response.status().as_u16());
+    response.json().await.unwrap()
+}
+
+// Natal: um leito ocupado, um livre e um bloqueado; Mossoró: um leito livre
+async fn cenario(app: &TestApp) -> (Uuid, Uuid) {
+    let natal = cria_unidade(app, "Natal").await;
+    let mossoro = cria_unidade(app, "Mossoró").await;
+
+    let ocupado = cria_leito(app, TipoLeito::UtiAdulto, SituacaoLeito::Livre, natal).await;
+    app.post_paciente(String::from("Fulano"), String::from("Masculino"), 54, String::from("fulano@gmail.com"), String::from("84991223344"), Some(SituacaoCovid::Confirmado), ocupado).await;
+    cria_leito(app, TipoLeito::UtiAdulto, SituacaoLeito::Livre, natal).await;
+    cria_leito(app, TipoLeito::EnfermariaClinica, SituacaoLeito::Bloqueado, natal).await;
+    cria_leito(app, TipoLeito::UtiAdulto, SituacaoLeito::Livre, mossoro).await;
+
+    (natal, mossoro)
+}
+
+#[actix_rt::test]
+async fn relatorio_ocupacao_aggregates_per_unidade_municipio_and_tipo() {
+    let app = create_app().await;
+    let (natal, _) = cenario(&app).await;
+
+    let relatorio = get_relatorio(&app, "").await;
+
+    assert_eq!(2, relatorio.unidades.len());
+    let unidade = relatorio.unidades.iter().find(|u| u.unidade_id == natal).unwrap();
+    assert_eq!(unidade.ocupacao.total, 3);
+    assert_eq!(unidade.ocupacao.ocupados, 1);
+    assert_eq!(unidade.ocupacao.livres, 1);
+    assert_eq!(unidade.ocupacao.bloqueados, 1);
+    // o leito bloqueado não entra na taxa
+    assert!((unidade.ocupacao.taxa_ocupacao - 0.5).abs() < f64::EPSILON);
+
+    assert_eq!(2, relatorio.municipios.len());
+
+    let uti = relatorio.tipos.iter().find(|t| t.tipo == TipoLeito::UtiAdulto).unwrap();
+    assert_eq!(uti.ocupacao.total, 3);
+    assert_eq!(uti.ocupacao.ocupados, 1);
+    assert_eq!(uti.ocupacao.livres, 2);
+}
+
+#[actix_rt::test]
+async fn relatorio_ocupacao_applies_filters() {
+    let app = create_app().await;
+    let (_, mossoro) = cenario(&app).await;
+
+    let relatorio = get_relatorio(&app, "?municipio=mossoró").await;
+    assert_eq!(1, relatorio.unidades.len());
+    assert_eq!(relatorio.unidades[0].unidade_id, mossoro);
+    assert_eq!(relatorio.municipios[0].ocupacao.total, 1);
+
+    let relatorio = get_relatorio(&app, "?tipo=EnfermariaClinica").await;
+    assert_eq!(1, relatorio.tipos.len());
+    assert_eq!(relatorio.tipos[0].ocupacao.bloqueados, 1);
+    assert!((relatorio.tipos[0].ocupacao.taxa_ocupacao).abs() < f64::EPSILON);
+}
+
+#[actix_rt::test]
+async fn leito_evento_records_every_situacao_change() {
+    let app = create_app().await;
+    let unidade_id = cria_unidade(&app, "Natal").await;
+    let leito_id = cria_leito(&app, TipoLeito::UtiAdulto, SituacaoLeito::Livre, unidade_id).await;
+
+    // criação, internação e alta
+    app.post_paciente(String::from("Fulano"), String::from("Masculino"), 54, String::from("fulano@gmail.com"), String::from("84991223344"), Some(SituacaoCovid::Confirmado), leito_id).await;
+    let internacao_id = sqlx::query!("SELECT id FROM internacao WHERE leito_id = $1", leito_id)
+        .fetch_one(&app.db_pool)
+        .await
+        .expect("Failed to fetch internacao.")
+        .id;
+    app.post_alta(internacao_id, MotivoAlta::AltaMedica).await;
+
+    let eventos = sqlx::query!(
+        r#"SELECT situacao as "situacao: SituacaoLeito" FROM leito_evento WHERE leito_id = $1 ORDER BY id"#,
+        leito_id
+    )
+    .fetch_all(&app.db_pool)
+    .await
+    .expect("Failed to fetch eventos.");
+
+    let situacoes: Vec<Option<SituacaoLeito>> = eventos.into_iter().map(|e| e.situacao).collect();
+    assert_eq!(situacoes, vec![Some(SituacaoLeito::Livre), Some(SituacaoLeito::Ocupado), Some(SituacaoLeito::EmHigienizacao)]);
+
+    // o histórico não pode ser reescrito
+    let result = sqlx::query!("DELETE FROM leito_evento WHERE leito_id = $1", leito_id)
+        .execute(&app.db_pool)
+        .await;
+    assert!(result.is_err());
+}
+
+#[actix_rt::test]
+async fn historico_ocupacao_reconstructs_occupancy_over_time() {
+    let app = create_app().await;
+    let (natal, _) = cenario(&app).await;
+
+    let agora = Utc::now();
+    let response = app.api_client
+        .get(format!("{}/relatorios/ocupacao/historico", &app.address))
+        .query(&[
+            ("de", (agora - Duration::hours(2)).to_rfc3339()),
+            ("ate", agora.to_rfc3339()),
+            ("granularidade", String::from("hora")),
+            ("unidade_id", natal.to_string()),
+        ])
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    assert_eq!(200, response.status().as_u16());
+
+    let pontos: Vec<PontoHistorico> = response.json().await.unwrap();
+    // antes da criação dos leitos não há pontos; o último reflete o estado atual
+    let ultimo = pontos.last().unwrap();
+    assert!(pontos.iter().all(|p| p.unidade_id == natal));
+    assert_eq!(ultimo.ocupacao.total, 3);
+    assert_eq!(ultimo.ocupacao.ocupados, 1);
+    assert_eq!(ultimo.ocupacao.bloqueados, 1);
+}
+
+#[actix_rt::test]
+async fn historico_ocupacao_returns_400_for_inverted_period() {
+    let app = create_app().await;
+
+    let agora = Utc::now();
+    let response = app.api_client
+        .get(format!("{}/relatorios/ocupacao/historico", &app.address))
+        .query(&[
+            ("de", agora.to_rfc3339()),
+            ("ate", (agora - Duration::days(1)).to_rfc3339()),
+        ])
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    assert_eq!(400, response.status().as_u16());
+}
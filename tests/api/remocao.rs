@@ -0,0 +1,231 @@
+use crate::helpers::{create_app, TestApp};
+use reqwest::Response;
+use gerenciador_leitos::domain::{MotivoAlta, SituacaoLeito, TipoLeito};
+use gerenciador_leitos::error::ErrorBody;
+use gerenciador_leitos::query::Pagina;
+use gerenciador_leitos::routes::{Internacao, Leito, LeitoId, PacienteId, RelatorioOcupacao, UnidadeId, UnidadeSaude};
+use uuid::Uuid;
+
+async fn cria_unidade(app: &TestApp, nome: &str) -> Uuid {
+    let response: Response = app.post_unidade(
+        String::from(nome),
+        format!("{}@gmail.com", nome.to_lowercase().replace(' ', "")),
+        String::from("UPA"),
+        String::from("Natal")
+    ).await;
+    let unidade: UnidadeId = response.json().await.unwrap();
+    unidade.id
+}
+
+async fn cria_leito(app: &TestApp, unidade_id: Uuid) -> Uuid {
+    let response = app.post_leito(TipoLeito::UtiAdulto, SituacaoLeito::Livre, unidade_id).await;
+    assert_eq!(200, response.status().as_u16());
+    let leito: LeitoId = response.json().await.unwrap();
+    leito.id
+}
+
+// interna um paciente novo e devolve a internação aberta
+async fn interna_paciente(app: &TestApp, leito_id: Uuid) -> Internacao {
+    let response = app.post_paciente(
+        String::from("Fulano"),
+        String::from("Masculino"),
+        54,
+        String::from("fulano@gmail.com"),
+        String::from("84991223344"),
+        None,
+        leito_id
+    ).await;
+    assert_eq!(200, response.status().as_u16());
+    let paciente: PacienteId = response.json().await.unwrap();
+
+    let mut internacoes: Vec<Internacao> = get(app, &format!("/pacientes/{}/internacoes", paciente.id))
+        .await
+        .json()
+        .await
+        .unwrap();
+    internacoes.pop().unwrap()
+}
+
+async fn get(app: &TestApp, caminho: &str) -> Response {
+    app.api_client
+        .get(format!("{}{}", &app.address, caminho))
+        .send()
+        .await
+        .expect("Failed to execute request.")
+}
+
+// envia o ETag atual do recurso, lido sem os parâmetros de consulta
+async fn delete(app: &TestApp, caminho: &str) -> Response {
+    let recurso = caminho.split('?').next().unwrap();
+    app.api_client
+        .delete(format!("{}{}", &app.address, caminho))
+        .header("If-Match", app.etag(recurso).await.unwrap())
+        .send()
+        .await
+        .expect("Failed to execute request.")
+}
+
+fn campos(body: &ErrorBody) -> Vec<&str> {
+    body.fields.iter().map(|f| f.field.as_str()).collect()
+}
+
+#[actix_rt::test]
+async fn delete_unidade_with_leitos_returns_409_listing_dependents() {
+    let app = create_app().await;
+    let unidade_id = cria_unidade(&app, "UPA Norte").await;
+    cria_leito(&app, unidade_id).await;
+    cria_leito(&app, unidade_id).await;
+
+    let response = delete(&app, &format!("/unidades/{}", unidade_id)).await;
+    assert_eq!(409, response.status().as_u16());
+    let body: ErrorBody = response.json().await.unwrap();
+    assert_eq!(vec!["leitos"], campos(&body));
+    assert!(body.fields[0].message.starts_with("2 leito(s)"));
+
+    let unidade: UnidadeSaude = get(&app, &format!("/unidades/{}", unidade_id)).await.json().await.unwrap();
+    assert!(unidade.desativado_em.is_none());
+}
+
+#[actix_rt::test]
+async fn delete_unidade_without_dependents_removes_it() {
+    let app = create_app().await;
+    let unidade_id = cria_unidade(&app, "UPA Norte").await;
+
+    let response = delete(&app, &format!("/unidades/{}", unidade_id)).await;
+    assert_eq!(200, response.status().as_u16());
+
+    let response = get(&app, &format!("/unidades/{}", unidade_id)).await;
+    assert_eq!(404, response.status().as_u16());
+}
+
+#[actix_rt::test]
+async fn delete_unidade_with_cascata_deactivates_unidade_and_leitos() {
+    let app = create_app().await;
+    let norte = cria_unidade(&app, "UPA Norte").await;
+    let sul = cria_unidade(&app, "UPA Sul").await;
+    let leito_id = cria_leito(&app, norte).await;
+    cria_leito(&app, sul).await;
+
+    let response = delete(&app, &format!("/unidades/{}?cascata=true", norte)).await;
+    assert_eq!(200, response.status().as_u16());
+
+    // continua resolvível pelo id, mas sai das listagens e dos relatórios atuais
+    let unidade: UnidadeSaude = get(&app, &format!("/unidades/{}", norte)).await.json().await.unwrap();
+    assert!(unidade.desativado_em.is_some());
+    let leito: Leito = get(&app, &format!("/leitos/{}", leito_id)).await.json().await.unwrap();
+    assert!(leito.desativado_em.is_some());
+
+    let pagina: Pagina<UnidadeSaude> = get(&app, "/unidades").await.json().await.unwrap();
+    assert_eq!(vec![sul], pagina.itens.iter().map(|u| u.id).collect::<Vec<Uuid>>());
+    let pagina: Pagina<UnidadeSaude> = get(&app, "/unidades?incluir_desativadas=true").await.json().await.unwrap();
+    assert_eq!(2, pagina.total);
+
+    let pagina: Pagina<Leito> = get(&app, "/leitos").await.json().await.unwrap();
+    assert_eq!(1, pagina.total);
+    let pagina: Pagina<Leito> = get(&app, "/leitos?incluir_desativados=true").await.json().await.unwrap();
+    assert_eq!(2, pagina.total);
+
+    let relatorio: RelatorioOcupacao = get(&app, "/relatorios/ocupacao").await.json().await.unwrap();
+    assert_eq!(vec![sul], relatorio.unidades.iter().map(|u| u.unidade_id).collect::<Vec<Uuid>>());
+
+    // a unidade desativada não recebe novos leitos nem pode ser removida de novo
+    let response = app.post_leito(TipoLeito::UtiAdulto, SituacaoLeito::Livre, norte).await;
+    assert_eq!(409, response.status().as_u16());
+    let response = delete(&app, &format!("/unidades/{}", norte)).await;
+    assert_eq!(404, response.status().as_u16());
+}
+
+#[actix_rt::test]
+async fn delete_unidade_with_internados_is_refused_even_with_cascata() {
+    let app = create_app().await;
+    let unidade_id = cria_unidade(&app, "UPA Norte").await;
+    interna_paciente(&app, cria_leito(&app, unidade_id).await).await;
+
+    let response = delete(&app, &format!("/unidades/{}?cascata=true", unidade_id)).await;
+    assert_eq!(409, response.status().as_u16());
+    let body: ErrorBody = response.json().await.unwrap();
+    assert_eq!(vec!["internacoes"], campos(&body));
+
+    let leitos: Pagina<Leito> = get(&app, &format!("/unidades/{}/leitos", unidade_id)).await.json().await.unwrap();
+    assert_eq!(1, leitos.total);
+}
+
+#[actix_rt::test]
+async fn delete_unidade_with_reserved_leito_is_refused_even_with_cascata() {
+    let app = create_app().await;
+    let unidade_id = cria_unidade(&app, "UPA Norte").await;
+    let leito_id = cria_leito(&app, unidade_id).await;
+    let response = app.api_client
+        .post(format!("{}/leitos/{}/transicoes", &app.address, leito_id))
+        .json(&serde_json::json!({"situacao": "Reservado"}))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    assert_eq!(200, response.status().as_u16());
+
+    let response = delete(&app, &format!("/unidades/{}?cascata=true", unidade_id)).await;
+    assert_eq!(409, response.status().as_u16());
+    let body: ErrorBody = response.json().await.unwrap();
+    assert_eq!(vec!["reservas"], campos(&body));
+
+    let leito: Leito = get(&app, &format!("/leitos/{}", leito_id)).await.json().await.unwrap();
+    assert!(leito.desativado_em.is_none());
+}
+
+#[actix_rt::test]
+async fn delete_leito_with_history_deactivates_it() {
+    let app = create_app().await;
+    let unidade_id = cria_unidade(&app, "UPA Norte").await;
+    let leito_id = cria_leito(&app, unidade_id).await;
+    let internacao = interna_paciente(&app, leito_id).await;
+
+    let response = delete(&app, &format!("/leitos/{}", leito_id)).await;
+    assert_eq!(409, response.status().as_u16());
+    let body: ErrorBody = response.json().await.unwrap();
+    assert_eq!(vec!["internacoes"], campos(&body));
+
+    let response = app.post_alta(internacao.id, MotivoAlta::AltaMedica).await;
+    assert_eq!(200, response.status().as_u16());
+
+    let response = delete(&app, &format!("/leitos/{}", leito_id)).await;
+    assert_eq!(200, response.status().as_u16());
+
+    // a internação antiga continua apontando para o leito
+    let leito: Leito = get(&app, &format!("/leitos/{}", leito_id)).await.json().await.unwrap();
+    assert!(leito.desativado_em.is_some());
+    let response = get(&app, &format!("/internacoes/{}", internacao.id)).await;
+    assert_eq!(200, response.status().as_u16());
+
+    let response = app.api_client
+        .post(format!("{}/pacientes/{}/internar", &app.address, internacao.paciente_id))
+        .json(&serde_json::json!({"leito_id": leito_id.to_string()}))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    assert_eq!(409, response.status().as_u16());
+    let body: ErrorBody = response.json().await.unwrap();
+    assert_eq!(vec!["leito_id"], campos(&body));
+}
+
+#[actix_rt::test]
+async fn delete_leito_reservado_returns_409() {
+    let app = create_app().await;
+    let unidade_id = cria_unidade(&app, "UPA Norte").await;
+    let leito_id = cria_leito(&app, unidade_id).await;
+
+    let response = app.api_client
+        .post(format!("{}/leitos/{}/transicoes", &app.address, leito_id))
+        .json(&serde_json::json!({"situacao": "Reservado"}))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    assert_eq!(200, response.status().as_u16());
+
+    let response = delete(&app, &format!("/leitos/{}", leito_id)).await;
+    assert_eq!(409, response.status().as_u16());
+    let body: ErrorBody = response.json().await.unwrap();
+    assert_eq!(vec!["reserva"], campos(&body));
+
+    let leito: Leito = get(&app, &format!("/leitos/{}", leito_id)).await.json().await.unwrap();
+    assert!(leito.desativado_em.is_none());
+}
@@ -0,0 +1,144 @@
+use crate::helpers::{create_app, TestApp};
+use reqwest::Response;
+use gerenciador_leitos::domain::{Papel, SituacaoLeito, TipoLeito};
+use gerenciador_leitos::query::Pagina;
+use gerenciador_leitos::routes::{Leito, LeitoId, Paciente, PacienteId, ResumoUnidade, UnidadeId};
+use uuid::Uuid;
+
+async fn cria_unidade(app: &TestApp, nome: &str) -> Uuid {
+    let response: Response = app.post_unidade(
+        String::from(nome),
+        format!("{}@gmail.com", nome.to_lowercase().replace(' ', "")),
+        String::from("UPA"),
+        String::from("Natal")
+    ).await;
+    let unidade: UnidadeId = response.json().await.unwrap();
+    unidade.id
+}
+
+async fn cria_leito(app: &TestApp, situacao: SituacaoLeito, unidade_id: Uuid) -> Uuid {
+    let response = app.post_leito(TipoLeito::UtiAdulto, situacao, unidade_id).await;
+    assert_eq!(200, response.status().as_u16());
+    let leito: LeitoId = response.json().await.unwrap();
+    leito.id
+}
+
+async fn interna_paciente(app: &TestApp, nome: &str, leito_id: Uuid) -> Uuid {
+    let response = app.post_paciente(
+        String::from(nome),
+        String::from("Feminino"),
+        40,
+        String::from("paciente@gmail.com"),
+        String::from("84991223344"),
+        None,
+        leito_id
+    ).await;
+    assert_eq!(200, response.status().as_u16());
+    let paciente: PacienteId = response.json().await.unwrap();
+    paciente.id
+}
+
+async fn get(client: &reqwest::Client, app: &TestApp, caminho: &str) -> Response {
+    client
+        .get(format!("{}{}", &app.address, caminho))
+        .send()
+        .await
+        .expect("Failed to execute request.")
+}
+
+#[actix_rt::test]
+async fn get_leitos_da_unidade_lists_only_that_unidade() {
+    let app = create_app().await;
+    let norte = cria_unidade(&app, "UPA Norte").await;
+    let sul = cria_unidade(&app, "UPA Sul").await;
+    let leito_norte = cria_leito(&app, SituacaoLeito::Livre, norte).await;
+    cria_leito(&app, SituacaoLeito::Livre, sul).await;
+
+    let response = get(&app.api_client, &app, &format!("/unidades/{}/leitos", norte)).await;
+    assert_eq!(200, response.status().as_u16());
+    let pagina: Pagina<Leito> = response.json().await.unwrap();
+    assert_eq!(1, pagina.total);
+    assert_eq!(leito_norte, pagina.itens[0].id);
+}
+
+#[actix_rt::test]
+async fn get_pacientes_da_unidade_lists_internados_there() {
+    let app = create_app().await;
+    let norte = cria_unidade(&app, "UPA Norte").await;
+    let sul = cria_unidade(&app, "UPA Sul").await;
+    let paciente_norte = interna_paciente(&app, "Ana", cria_leito(&app, SituacaoLeito::Livre, norte).await).await;
+    interna_paciente(&app, "Bia", cria_leito(&app, SituacaoLeito::Livre, sul).await).await;
+
+    let response = get(&app.api_client, &app, &format!("/unidades/{}/pacientes", norte)).await;
+    assert_eq!(200, response.status().as_u16());
+    let pagina: Pagina<Paciente> = response.json().await.unwrap();
+    assert_eq!(1, pagina.total);
+    assert_eq!(paciente_norte, pagina.itens[0].id);
+}
+
+#[actix_rt::test]
+async fn get_paciente_do_leito_returns_current_paciente() {
+    let app = create_app().await;
+    let unidade_id = cria_unidade(&app, "UPA Norte").await;
+    let ocupado = cria_leito(&app, SituacaoLeito::Livre, unidade_id).await;
+    let paciente_id = interna_paciente(&app, "Ana", ocupado).await;
+    let vago = cria_leito(&app, SituacaoLeito::Livre, unidade_id).await;
+
+    let response = get(&app.api_client, &app, &format!("/leitos/{}/paciente", ocupado)).await;
+    assert_eq!(200, response.status().as_u16());
+    let paciente: Paciente = response.json().await.unwrap();
+    assert_eq!(paciente_id, paciente.id);
+    assert_eq!(Some(ocupado), paciente.leito_id);
+
+    let response = get(&app.api_client, &app, &format!("/leitos/{}/paciente", vago)).await;
+    assert_eq!(404, response.status().as_u16());
+
+    let response = get(&app.api_client, &app, &format!("/leitos/{}/paciente", Uuid::new_v4())).await;
+    assert_eq!(404, response.status().as_u16());
+}
+
+#[actix_rt::test]
+async fn get_resumo_unidade_counts_leitos_and_lists_pacientes() {
+    let app = create_app().await;
+    let unidade_id = cria_unidade(&app, "UPA Norte").await;
+    let paciente_id = interna_paciente(&app, "Ana", cria_leito(&app, SituacaoLeito::Livre, unidade_id).await).await;
+    cria_leito(&app, SituacaoLeito::Livre, unidade_id).await;
+    cria_leito(&app, SituacaoLeito::Bloqueado, unidade_id).await;
+
+    let response = get(&app.api_client, &app, &format!("/unidades/{}/resumo", unidade_id)).await;
+    assert_eq!(200, response.status().as_u16());
+    let resumo: ResumoUnidade = response.json().await.unwrap();
+    assert_eq!(unidade_id, resumo.unidade.id);
+    assert_eq!(3, resumo.ocupacao.total);
+    assert_eq!(1, resumo.ocupacao.ocupados);
+    assert_eq!(1, resumo.ocupacao.livres);
+    assert_eq!(1, resumo.ocupacao.bloqueados);
+    assert!((resumo.ocupacao.taxa_ocupacao - 0.5).abs() < 1e-9);
+    assert_eq!(1, resumo.pacientes.len());
+    assert_eq!(paciente_id, resumo.pacientes[0].id);
+}
+
+#[actix_rt::test]
+async fn nested_routes_of_missing_unidade_return_404() {
+    let app = create_app().await;
+    let id = Uuid::new_v4();
+
+    for sufixo in ["leitos", "pacientes", "resumo"].iter() {
+        let response = get(&app.api_client, &app, &format!("/unidades/{}/{}", id, sufixo)).await;
+        assert_eq!(404, response.status().as_u16(), "{}", sufixo);
+    }
+}
+
+#[actix_rt::test]
+async fn nested_routes_respect_unidade_scope() {
+    let app = create_app().await;
+    let norte = cria_unidade(&app, "UPA Norte").await;
+    let sul = cria_unidade(&app, "UPA Sul").await;
+    let client = app.client_com_papel(Papel::FuncionarioUnidade, Some(norte), None).await;
+
+    let response = get(&client, &app, &format!("/unidades/{}/resumo", norte)).await;
+    assert_eq!(200, response.status().as_u16());
+
+    let response = get(&client, &app, &format!("/unidades/{}/resumo", sul)).await;
+    assert_eq!(403, response.status().as_u16());
+}
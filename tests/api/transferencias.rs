@@ -0,0 +1,263 @@
+use crate::helpers::{create_app, TestApp, EMAIL_TESTE};
+use std::collections::HashMap;
+use reqwest::Response;
+use gerenciador_leitos::domain::{MotivoAlta, SituacaoCovid, SituacaoLeito, SituacaoTransferencia, TipoLeito};
+use gerenciador_leitos::error::ErrorBody;
+use gerenciador_leitos::routes::{LeitoId, Paciente, PacienteId, Transferencia, UnidadeId};
+use uuid::Uuid;
+
+async fn cria_unidade(app: &TestApp) -> Uuid {
+    let response: Response = app.post_unidade(String::from("UBS Aux"), format!("{}@gmail.com", Uuid::new_v4()), String::from("UBS"), String::from("Natal")).await;
+    let hospital_id: UnidadeId = response.json().await.unwrap();
+    hospital_id.id
+}
+
+async fn cria_leito(app: &TestApp, unidade_id: Uuid) -> Uuid {
+    let response: Response = app.post_leito(TipoLeito::UtiAdulto, SituacaoLeito::Livre, unidade_id).await;
+    assert_eq!(200, response.status().as_u16());
+    let leito_id: LeitoId = response.json().await.unwrap();
+    leito_id.id
+}
+
+// cria um paciente já internado no leito informado
+async fn interna_paciente(app: &TestApp, leito_id: Uuid) -> Uuid {
+    let response = app.post_paciente(String::from("Fulano"), String::from("Masculino"), 54, String::from("fulano@gmail.com"), String::from("84991223344"), Some(SituacaoCovid::Confirmado), leito_id).await;
+    assert_eq!(200, response.status().as_u16());
+    let paciente_id: PacienteId = response.json().await.unwrap();
+    paciente_id.id
+}
+
+async fn situacao_do_leito(app: &TestApp, leito_id: Uuid) -> SituacaoLeito {
+    sqlx::query!(r#"SELECT situacao as "situacao: SituacaoLeito" FROM leito WHERE id = $1"#, leito_id)
+        .fetch_one(&app.db_pool)
+        .await
+        .expect("Failed to fetch saved leito.")
+        .situacao
+}
+
+async fn id_do_usuario_de_teste(app: &TestApp) -> Uuid {
+    sqlx::query!("SELECT id FROM usuario WHERE email = $1", EMAIL_TESTE)
+        .fetch_one(&app.db_pool)
+        .await
+        .expect("Failed to fetch test user.")
+        .id
+}
+
+async fn solicita(app: &TestApp, leito_origem_id: Uuid, unidade_destino_id: Uuid) -> Response {
+    let mut map = HashMap::new();
+    map.insert("leito_origem_id", leito_origem_id.to_string());
+    map.insert("unidade_destino_id", unidade_destino_id.to_string());
+
+    app.api_client
+        .post(format!("{}/transferencias", &app.address))
+        .json(&map)
+        .send()
+        .await
+        .expect("Failed to execute request.")
+}
+
+async fn avanca(app: &TestApp, transferencia_id: Uuid, acao: &str, map: HashMap<&str, String>) -> Response {
+    app.api_client
+        .post(format!("{}/transferencias/{}/{}", &app.address, transferencia_id, acao))
+        .json(&map)
+        .send()
+        .await
+        .expect("Failed to execute request.")
+}
+
+fn aceite(leito_destino_id: Uuid) -> HashMap<&'static str, String> {
+    let mut map = HashMap::new();
+    map.insert("leito_destino_id", leito_destino_id.to_string());
+    map
+}
+
+#[actix_rt::test]
+async fn transferencia_moves_paciente_between_unidades() {
+    let app = create_app().await;
+    let origem = cria_leito(&app, cria_unidade(&app).await).await;
+    let unidade_destino = cria_unidade(&app).await;
+    let destino = cria_leito(&app, unidade_destino).await;
+    let paciente_id = interna_paciente(&app, origem).await;
+
+    let response = solicita(&app, origem, unidade_destino).await;
+    assert_eq!(200, response.status().as_u16());
+    let transferencia: Transferencia = response.json().await.unwrap();
+    assert_eq!(transferencia.situacao, SituacaoTransferencia::Solicitada);
+    assert_eq!(transferencia.paciente_id, paciente_id);
+    // o solicitante é o usuário autenticado, não um campo do corpo
+    let usuario_id = id_do_usuario_de_teste(&app).await;
+    assert_eq!(transferencia.solicitado_por, Some(usuario_id));
+
+    // o aceite reserva o leito de destino
+    let response = avanca(&app, transferencia.id, "aceitar", aceite(destino)).await;
+    assert_eq!(200, response.status().as_u16());
+    let aceita: Transferencia = response.json().await.unwrap();
+    assert_eq!(aceita.aceito_por, Some(usuario_id));
+    assert_eq!(situacao_do_leito(&app, destino).await, SituacaoLeito::Reservado);
+
+    let response = avanca(&app, transferencia.id, "iniciar", HashMap::new()).await;
+    assert_eq!(200, response.status().as_u16());
+
+    let response = avanca(&app, transferencia.id, "concluir", HashMap::new()).await;
+    assert_eq!(200, response.status().as_u16());
+    let concluida: Transferencia = response.json().await.unwrap();
+    assert_eq!(concluida.situacao, SituacaoTransferencia::Concluida);
+    assert!(concluida.internacao_destino_id.is_some());
+    assert!(concluida.finalizada_em.is_some());
+
+    assert_eq!(situacao_do_leito(&app, origem).await, SituacaoLeito::EmHigienizacao);
+    assert_eq!(situacao_do_leito(&app, destino).await, SituacaoLeito::Ocupado);
+
+    let response = app.api_client
+        .get(format!("{}/pacientes/{}", &app.address, paciente_id))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    let paciente: Paciente = response.json().await.unwrap();
+    assert_eq!(paciente.leito_id, Some(destino));
+}
+
+#[actix_rt::test]
+async fn alta_during_accepted_transferencia_returns_409() {
+    let app = create_app().await;
+    let origem = cria_leito(&app, cria_unidade(&app).await).await;
+    let unidade_destino = cria_unidade(&app).await;
+    let destino = cria_leito(&app, unidade_destino).await;
+    interna_paciente(&app, origem).await;
+
+    let response = solicita(&app, origem, unidade_destino).await;
+    let transferencia: Transferencia = response.json().await.unwrap();
+    avanca(&app, transferencia.id, "aceitar", aceite(destino)).await;
+
+    let response = app.post_alta(transferencia.internacao_origem_id, MotivoAlta::AltaMedica).await;
+    assert_eq!(409, response.status().as_u16());
+    let body: ErrorBody = response.json().await.unwrap();
+    assert!(body.message.contains(&transferencia.id.to_string()));
+    assert_eq!(situacao_do_leito(&app, origem).await, SituacaoLeito::Ocupado);
+    assert_eq!(situacao_do_leito(&app, destino).await, SituacaoLeito::Reservado);
+
+    // a transferência segue e a conclusão encerra a internação de origem
+    avanca(&app, transferencia.id, "iniciar", HashMap::new()).await;
+    let response = avanca(&app, transferencia.id, "concluir", HashMap::new()).await;
+    assert_eq!(200, response.status().as_u16());
+    assert_eq!(situacao_do_leito(&app, destino).await, SituacaoLeito::Ocupado);
+}
+
+#[actix_rt::test]
+async fn recusar_transferencia_releases_reserved_leito() {
+    let app = create_app().await;
+    let origem = cria_leito(&app, cria_unidade(&app).await).await;
+    let unidade_destino = cria_unidade(&app).await;
+    let destino = cria_leito(&app, unidade_destino).await;
+    interna_paciente(&app, origem).await;
+
+    let response = solicita(&app, origem, unidade_destino).await;
+    let transferencia: Transferencia = response.json().await.unwrap();
+    avanca(&app, transferencia.id, "aceitar", aceite(destino)).await;
+
+    let mut map = HashMap::new();
+    map.insert("motivo", String::from("Sem equipe disponível"));
+    let response = avanca(&app, transferencia.id, "recusar", map).await;
+    assert_eq!(200, response.status().as_u16());
+
+    let recusada: Transferencia = response.json().await.unwrap();
+    assert_eq!(recusada.situacao, SituacaoTransferencia::Recusada);
+    assert_eq!(recusada.motivo_recusa.as_deref(), Some("Sem equipe disponível"));
+
+    assert_eq!(situacao_do_leito(&app, destino).await, SituacaoLeito::Livre);
+    assert_eq!(situacao_do_leito(&app, origem).await, SituacaoLeito::Ocupado);
+}
+
+#[actix_rt::test]
+async fn concluir_transferencia_not_in_transit_returns_409() {
+    let app = create_app().await;
+    let origem = cria_leito(&app, cria_unidade(&app).await).await;
+    let unidade_destino = cria_unidade(&app).await;
+    interna_paciente(&app, origem).await;
+
+    let response = solicita(&app, origem, unidade_destino).await;
+    let transferencia: Transferencia = response.json().await.unwrap();
+
+    let response = avanca(&app, transferencia.id, "concluir", HashMap::new()).await;
+    assert_eq!(409, response.status().as_u16());
+
+    let body: ErrorBody = response.json().await.unwrap();
+    assert_eq!(body.fields[0].field, "situacao");
+}
+
+#[actix_rt::test]
+async fn aceitar_transferencia_with_leito_of_other_unidade_returns_400() {
+    let app = create_app().await;
+    let origem = cria_leito(&app, cria_unidade(&app).await).await;
+    let unidade_destino = cria_unidade(&app).await;
+    let outro = cria_leito(&app, cria_unidade(&app).await).await;
+    interna_paciente(&app, origem).await;
+
+    let response = solicita(&app, origem, unidade_destino).await;
+    let transferencia: Transferencia = response.json().await.unwrap();
+
+    let response = avanca(&app, transferencia.id, "aceitar", aceite(outro)).await;
+    assert_eq!(400, response.status().as_u16());
+    assert_eq!(situacao_do_leito(&app, outro).await, SituacaoLeito::Livre);
+}
+
+#[actix_rt::test]
+async fn create_transferencia_returns_409_for_duplicate_or_empty_leito() {
+    let app = create_app().await;
+    let unidade = cria_unidade(&app).await;
+    let origem = cria_leito(&app, unidade).await;
+    let vazio = cria_leito(&app, unidade).await;
+    let unidade_destino = cria_unidade(&app).await;
+    interna_paciente(&app, origem).await;
+
+    let response = solicita(&app, origem, unidade_destino).await;
+    assert_eq!(200, response.status().as_u16());
+
+    // já existe uma transferência em andamento para esta internação
+    let response = solicita(&app, origem, unidade_destino).await;
+    assert_eq!(409, response.status().as_u16());
+
+    // leito sem paciente internado
+    let response = solicita(&app, vazio, unidade_destino).await;
+    assert_eq!(409, response.status().as_u16());
+}
+
+#[actix_rt::test]
+async fn reserved_leito_is_only_taken_by_its_transferencia() {
+    let app = create_app().await;
+    let origem = cria_leito(&app, cria_unidade(&app).await).await;
+    let unidade_destino = cria_unidade(&app).await;
+    let destino = cria_leito(&app, unidade_destino).await;
+    interna_paciente(&app, origem).await;
+
+    let response = solicita(&app, origem, unidade_destino).await;
+    let transferencia: Transferencia = response.json().await.unwrap();
+    avanca(&app, transferencia.id, "aceitar", aceite(destino)).await;
+    avanca(&app, transferencia.id, "iniciar", HashMap::new()).await;
+
+    // outro paciente, sem internação, tenta ocupar o leito reservado
+    let response = app.api_client
+        .post(format!("{}/pacientes", &app.address))
+        .json(&serde_json::json!({
+            "nome": "Beltrano",
+            "sexo": "Masculino",
+            "idade": 40,
+            "email": "beltrano@gmail.com",
+            "telefone": "84991223355"
+        }))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    let outro: PacienteId = response.json().await.unwrap();
+    let response = app.api_client
+        .post(format!("{}/pacientes/{}/internar", &app.address, outro.id))
+        .json(&serde_json::json!({"leito_id": destino}))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    assert_eq!(409, response.status().as_u16());
+
+    let response = avanca(&app, transferencia.id, "concluir", HashMap::new()).await;
+    assert_eq!(200, response.status().as_u16());
+    assert_eq!(situacao_do_leito(&app, destino).await, SituacaoLeito::Ocupado);
+}
@@ -0,0 +1,186 @@
+use crate::helpers::{create_app, create_app_sem_usuarios, EMAIL_TESTE, SENHA_TESTE};
+use std::collections::HashMap;
+use gerenciador_leitos::error::ErrorBody;
+use gerenciador_leitos::routes::TokenAcesso;
+
+async fn login(address: &str, email: &str, senha: &str) -> reqwest::Response {
+    let mut map = HashMap::new();
+    map.insert("email", email);
+    map.insert("senha", senha);
+
+    reqwest::Client::new()
+        .post(format!("{}/login", address))
+        .json(&map)
+        .send()
+        .await
+        .expect("Failed to execute request.")
+}
+
+#[actix_rt::test]
+async fn protected_route_without_token_returns_401() {
+    let app = create_app().await;
+
+    let response = reqwest::Client::new()
+        .delete(format!("{}/unidades/{}", &app.address, uuid::Uuid::new_v4()))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+
+    assert_eq!(401, response.status().as_u16());
+    assert_eq!(response.headers()["www-authenticate"], "Bearer");
+
+    let body: ErrorBody = response.json().await.unwrap();
+    assert_eq!(body.code, "unauthorized");
+}
+
+#[actix_rt::test]
+async fn protected_route_with_unknown_token_returns_401() {
+    let app = create_app().await;
+
+    let response = reqwest::Client::new()
+        .get(format!("{}/unidades", &app.address))
+        .bearer_auth("token-inexistente")
+        .send()
+        .await
+        .expect("Failed to execute request.");
+
+    assert_eq!(401, response.status().as_u16());
+}
+
+#[actix_rt::test]
+async fn login_with_wrong_senha_returns_401() {
+    let app = create_app().await;
+
+    let response = login(&app.address, EMAIL_TESTE, "senha-errada").await;
+    assert_eq!(401, response.status().as_u16());
+
+    let response = login(&app.address, "ninguem@sigleitos.com", SENHA_TESTE).await;
+    assert_eq!(401, response.status().as_u16());
+}
+
+#[actix_rt::test]
+async fn login_issues_token_that_logout_revokes() {
+    let app = create_app().await;
+
+    let response = login(&app.address, EMAIL_TESTE, SENHA_TESTE).await;
+    assert_eq!(200, response.status().as_u16());
+    let token: TokenAcesso = response.json().await.unwrap();
+
+    let client = reqwest::Client::new();
+    let response = client
+        .get(format!("{}/unidades", &app.address))
+        .bearer_auth(&token.token)
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    assert_eq!(200, response.status().as_u16());
+
+    let response = client
+        .post(format!("{}/logout", &app.address))
+        .bearer_auth(&token.token)
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    assert_eq!(200, response.status().as_u16());
+
+    let response = client
+        .get(format!("{}/unidades", &app.address))
+        .bearer_auth(&token.token)
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    assert_eq!(401, response.status().as_u16());
+}
+
+#[actix_rt::test]
+async fn expired_token_returns_401() {
+    let app = create_app().await;
+
+    let response = login(&app.address, EMAIL_TESTE, SENHA_TESTE).await;
+    let token: TokenAcesso = response.json().await.unwrap();
+
+    sqlx::query!("UPDATE token_acesso SET expira_em = now() - interval '1 minute'")
+        .execute(&app.db_pool)
+        .await
+        .expect("Failed to expire tokens.");
+
+    let response = reqwest::Client::new()
+        .get(format!("{}/unidades", &app.address))
+        .bearer_auth(&token.token)
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    assert_eq!(401, response.status().as_u16());
+}
+
+#[actix_rt::test]
+async fn create_usuario_requires_token_once_a_usuario_exists() {
+    let app = create_app().await;
+
+    let mut map = HashMap::new();
+    map.insert("nome", "Intruso");
+    map.insert("email", "intruso@sigleitos.com");
+    map.insert("senha", "12345678");
+    map.insert("papel", "ReguladorEstadual");
+
+    let response = reqwest::Client::new()
+        .post(format!("{}/usuarios", &app.address))
+        .json(&map)
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    assert_eq!(401, response.status().as_u16());
+
+    // autenticado, o cadastro funciona e o novo usuário consegue entrar
+    map.insert("nome", "Enfermeira");
+    map.insert("email", "enfermeira@sigleitos.com");
+    let response = app.api_client
+        .post(format!("{}/usuarios", &app.address))
+        .json(&map)
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    assert_eq!(200, response.status().as_u16());
+
+    let response = login(&app.address, "enfermeira@sigleitos.com", "12345678").await;
+    assert_eq!(200, response.status().as_u16());
+
+    let senha_hash = sqlx::query!("SELECT senha_hash FROM usuario WHERE email = 'enfermeira@sigleitos.com'")
+        .fetch_one(&app.db_pool)
+        .await
+        .expect("Failed to fetch usuario.")
+        .senha_hash;
+    assert!(senha_hash.starts_with("$argon2"));
+}
+
+#[actix_rt::test]
+async fn concurrent_first_cadastros_create_a_single_admin() {
+    let app = create_app_sem_usuarios().await;
+
+    let cadastros = (0..5).map(|i| {
+        let body = serde_json::json!({
+            "nome": "Admin",
+            "email": format!("admin{}@sigleitos.com", i),
+            "senha": SENHA_TESTE,
+            "papel": "Admin"
+        });
+        app.api_client
+            .post(format!("{}/usuarios", &app.address))
+            .json(&body)
+            .send()
+    });
+    let respostas = futures::future::join_all(cadastros).await;
+
+    let criados = respostas
+        .iter()
+        .filter(|response| response.as_ref().unwrap().status().as_u16() == 200)
+        .count();
+    assert_eq!(1, criados);
+
+    let total = sqlx::query!(r#"SELECT count(*) as "total!" FROM usuario"#)
+        .fetch_one(&app.db_pool)
+        .await
+        .expect("Failed to count usuarios.")
+        .total;
+    assert_eq!(1, total);
+}
@@ -0,0 +1,159 @@
+use crate::helpers::{create_app, TestApp};
+use reqwest::Response;
+use gerenciador_leitos::domain::{SituacaoCovid, SituacaoLeito, TipoLeito};
+use gerenciador_leitos::error::ErrorBody;
+use gerenciador_leitos::routes::{LeitoId, PacienteId, UnidadeId};
+use uuid::Uuid;
+
+async fn cria_leito(app: &TestApp) -> Uuid {
+    let response: Response = app.post_unidade(
+        String::from("UBS Aux"),
+        String::from("ubsaux@gmail.com"),
+        String::from("UBS"),
+        String::from("Natal")
+    ).await;
+    let unidade: UnidadeId = response.json().await.unwrap();
+
+    let response = app.post_leito(TipoLeito::UtiAdulto, SituacaoLeito::Livre, unidade.id).await;
+    let leito: LeitoId = response.json().await.unwrap();
+    leito.id
+}
+
+async fn post_json(app: &TestApp, caminho: &str, body: &serde_json::Value) -> Response {
+    app.api_client
+        .post(format!("{}{}", &app.address, caminho))
+        .json(body)
+        .send()
+        .await
+        .expect("Failed to execute request.")
+}
+
+fn campos(body: &ErrorBody) -> Vec<&str> {
+    body.fields.iter().map(|f| f.field.as_str()).collect()
+}
+
+#[actix_rt::test]
+async fn create_paciente_reports_every_invalid_field_at_once() {
+    let app = create_app().await;
+
+    let response = post_json(&app, "/pacientes", &serde_json::json!({
+        "nome": " ",
+        "sexo": "M",
+        "idade": 200,
+        "email": "fulano@",
+        "telefone": "123"
+    })).await;
+
+    assert_eq!(400, response.status().as_u16());
+    let body: ErrorBody = response.json().await.unwrap();
+    assert_eq!("validation_error", body.code);
+    assert_eq!(vec!["nome", "sexo", "idade", "email", "telefone"], campos(&body));
+
+    let total: i64 = sqlx::query_scalar("SELECT count(*) FROM paciente")
+        .fetch_one(&app.db_pool)
+        .await
+        .unwrap();
+    assert_eq!(0, total);
+}
+
+#[actix_rt::test]
+async fn create_paciente_rejects_non_numeric_idade() {
+    let app = create_app().await;
+
+    let response = post_json(&app, "/pacientes", &serde_json::json!({
+        "nome": "Fulano",
+        "sexo": "Masculino",
+        "idade": "abc",
+        "email": "fulano@gmail.com",
+        "telefone": "84991223344",
+        "situacao_covid": "Confirmado"
+    })).await;
+
+    assert_eq!(400, response.status().as_u16());
+}
+
+#[actix_rt::test]
+async fn create_paciente_accepts_usual_phone_formats() {
+    let app = create_app().await;
+    let leito_id = cria_leito(&app).await;
+
+    let validos = ["(84) 99122-3344", "+55 84 3211-4455", "8432114455"];
+    for telefone in validos.iter() {
+        let response = post_json(&app, "/pacientes", &serde_json::json!({
+            "nome": "Fulano",
+            "sexo": "Masculino",
+            "idade": 54,
+            "email": "fulano@gmail.com",
+            "telefone": telefone
+        })).await;
+        assert_eq!(200, response.status().as_u16(), "{}", telefone);
+    }
+
+    // sem DDD, DDD com zero, fixo começando com 9 e celular sem o 9 inicial
+    let invalidos = ["991223344", "(04) 99122-3344", "(84) 9211-4455", "(84) 81223-3445"];
+    for telefone in invalidos.iter() {
+        let response = post_json(&app, "/pacientes", &serde_json::json!({
+            "nome": "Fulano",
+            "sexo": "Masculino",
+            "idade": 54,
+            "email": "fulano@gmail.com",
+            "telefone": telefone,
+            "leito_id": leito_id.to_string()
+        })).await;
+        assert_eq!(400, response.status().as_u16(), "{}", telefone);
+        let body: ErrorBody = response.json().await.unwrap();
+        assert_eq!(vec!["telefone"], campos(&body));
+    }
+}
+
+#[actix_rt::test]
+async fn update_paciente_validates_payload() {
+    let app = create_app().await;
+    let leito_id = cria_leito(&app).await;
+    let response = app.post_paciente(
+        String::from("Fulano"),
+        String::from("Masculino"),
+        54,
+        String::from("fulano@gmail.com"),
+        String::from("84991223344"),
+        Some(SituacaoCovid::Confirmado),
+        leito_id
+    ).await;
+    let paciente: PacienteId = response.json().await.unwrap();
+
+    let response = app.api_client
+        .put(format!("{}/pacientes/{}", &app.address, paciente.id))
+        .header("If-Match", app.etag(&format!("/pacientes/{}", paciente.id)).await.unwrap())
+        .json(&serde_json::json!({
+            "nome": "Fulano",
+            "sexo": "Masculino",
+            "idade": -1,
+            "email": "fulano gmail.com",
+            "telefone": "84991223344",
+            "situacao_covid": "Confirmado",
+            "leito_id": leito_id.to_string()
+        }))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+
+    assert_eq!(400, response.status().as_u16());
+    let body: ErrorBody = response.json().await.unwrap();
+    assert_eq!(vec!["idade", "email"], campos(&body));
+}
+
+#[actix_rt::test]
+async fn create_unidade_validates_email_and_required_texts() {
+    let app = create_app().await;
+
+    let response = post_json(&app, "/unidades", &serde_json::json!({
+        "nome": "UBS Teste",
+        "email": "ubs@teste",
+        "tipo": "",
+        "municipio": "Natal"
+    })).await;
+
+    assert_eq!(400, response.status().as_u16());
+    let body: ErrorBody = response.json().await.unwrap();
+    assert_eq!(vec!["email", "tipo"], campos(&body));
+}